@@ -0,0 +1,1179 @@
+//! Transcoder for the Basis Universal images of `KHR_texture_basisu`: KTX2 files with ETC1S (BasisLZ supercompressed)
+//! or UASTC payloads are transcoded to BC7, ETC2 or RGBA8.
+//! See https://github.khronos.org/KTX-Specification/ (BasisLZ global data),
+//! https://github.com/BinomialLLC/basis_universal/wiki/.basis-File-Format-and-ETC1S-Texture-Video-Specification
+//! and https://github.com/BinomialLLC/basis_universal/wiki/UASTC-Texture-Specification
+use ktx2::Format;
+
+/// Format to transcode to, depending on the formats supported by the OpenGL implementation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    Bc7,
+    /// ETC2 RGB (or RGBA with EAC alpha) for ETC1S images, whose blocks are ETC1 blocks.
+    /// UASTC images are transcoded to RGBA8 instead.
+    Etc2,
+    Rgba8,
+}
+
+/// RGBA pixels of a 4x4 block (row by row)
+type Block = [[u8; 4]; 16];
+
+/// Transcodes the mip levels (largest first) of an ETC1S image. Returns the resulting format and levels.
+pub fn transcode_etc1s(global_data: &[u8], levels: &[&[u8]], width: u32, height: u32, target: Target)
+    -> Result<(Format, Vec<Vec<u8>>), String>
+{
+    let (etc1s, images) = Etc1s::new(global_data, levels.len())?;
+    let has_alpha = images.iter().any(|image| image.alpha.1 > 0);
+    let format = match (target, has_alpha) {
+        (Target::Bc7, _) => Format::BC7_UNORM_BLOCK,
+        (Target::Etc2, false) => Format::ETC2_R8G8B8_UNORM_BLOCK,
+        (Target::Etc2, true) => Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+        (Target::Rgba8, _) => Format::R8G8B8A8_UNORM,
+    };
+
+    let mut transcoded = Vec::with_capacity(levels.len());
+    for (i, (level, image)) in levels.iter().zip(&images).enumerate() {
+        let (level_width, level_height) = ((width >> i).max(1) as usize, (height >> i).max(1) as usize);
+        let (blocks_x, blocks_y) = (level_width.div_ceil(4), level_height.div_ceil(4));
+        let slice = |(offset, length): (usize, usize)| level.get(offset..offset + length)
+            .ok_or_else(|| format!("slice of level {} exceeds the level data", i));
+        let rgb = etc1s.decode_slice(slice(image.rgb)?, blocks_x, blocks_y)?;
+        let alpha = match image.alpha {
+            (_, 0) => None,
+            alpha => Some(etc1s.decode_slice(slice(alpha)?, blocks_x, blocks_y)?),
+        };
+
+        let data = if target == Target::Etc2 {
+            let mut data = Vec::with_capacity(rgb.len() * if has_alpha { 16 } else { 8 });
+            for (i, &(endpoint, selector)) in rgb.iter().enumerate() {
+                if has_alpha {
+                    let mut values = [255; 16];
+                    if let Some(ref alpha) = alpha {
+                        for (value, pixel) in values.iter_mut().zip(&etc1s.block_pixels(alpha[i])) {
+                            *value = pixel[1];
+                        }
+                    }
+                    data.extend_from_slice(&encode_eac(&values));
+                }
+                data.extend_from_slice(&etc1_block(&etc1s.endpoints[endpoint], &etc1s.selectors[selector]));
+            }
+            data
+        } else {
+            let blocks: Vec<Block> = rgb.iter().enumerate()
+                .map(|(i, &block)| {
+                    let mut pixels = etc1s.block_pixels(block);
+                    if let Some(ref alpha) = alpha {
+                        // the alpha slice is grayscale
+                        for (pixel, alpha) in pixels.iter_mut().zip(&etc1s.block_pixels(alpha[i])) {
+                            pixel[3] = alpha[1];
+                        }
+                    }
+                    pixels
+                })
+                .collect();
+            pack_blocks(&blocks, level_width, level_height, format)
+        };
+        transcoded.push(data);
+    }
+    Ok((format, transcoded))
+}
+
+/// Transcodes the mip levels (largest first, without supercompression) of a UASTC image.
+/// Returns the resulting format and levels.
+pub fn transcode_uastc(levels: &[&[u8]], width: u32, height: u32, target: Target)
+    -> Result<(Format, Vec<Vec<u8>>), String>
+{
+    let format = if target == Target::Bc7 { Format::BC7_UNORM_BLOCK } else { Format::R8G8B8A8_UNORM };
+    let mut transcoded = Vec::with_capacity(levels.len());
+    for (i, level) in levels.iter().enumerate() {
+        let (level_width, level_height) = ((width >> i).max(1) as usize, (height >> i).max(1) as usize);
+        if level.len() != level_width.div_ceil(4) * level_height.div_ceil(4) * 16 {
+            return Err(format!("invalid size of level {}", i))
+        }
+        let blocks = level.chunks_exact(16)
+            .map(decode_uastc)
+            .collect::<Result<Vec<_>, String>>()?;
+        transcoded.push(pack_blocks(&blocks, level_width, level_height, format));
+    }
+    Ok((format, transcoded))
+}
+
+/// Encodes the blocks of a level as BC7 or copies their pixels to RGBA8 rows
+fn pack_blocks(blocks: &[Block], width: usize, height: usize, format: Format) -> Vec<u8> {
+    if format == Format::BC7_UNORM_BLOCK {
+        let mut data = Vec::with_capacity(blocks.len() * 16);
+        for block in blocks {
+            data.extend_from_slice(&encode_bc7(block));
+        }
+        return data
+    }
+    let blocks_x = width.div_ceil(4);
+    let mut data = vec![0; width * height * 4];
+    for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i % width, i / width);
+        pixel.copy_from_slice(&blocks[y / 4 * blocks_x + x / 4][y % 4 * 4 + x % 4]);
+    }
+    data
+}
+
+/// Reads the bits of a bitstream, least significant bit first. Reads zeros after the end (like the Basis decoder).
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    fn bit(&mut self) -> u32 {
+        let bit = self.data.get(self.pos / 8).map_or(0, |&byte| u32::from(byte >> (self.pos % 8)) & 1);
+        self.pos += 1;
+        bit
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |value, i| value | self.bit() << i)
+    }
+
+    /// Variable length integer in chunks of `chunk_bits` bits, each followed by a continuation bit
+    fn vlc(&mut self, chunk_bits: u32) -> Result<u32, String> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.bits(chunk_bits + 1);
+            value |= (chunk & ((1 << chunk_bits) - 1)) << shift;
+            shift += chunk_bits;
+            if chunk >> chunk_bits == 0 {
+                return Ok(value)
+            }
+            if shift >= 32 {
+                return Err("invalid variable length integer".to_owned())
+            }
+        }
+    }
+}
+
+/// Canonical Huffman code (codes of each length assigned in symbol order), decoded bit by bit
+struct Huffman {
+    /// Number of codes of each length
+    counts: [u32; 17],
+    /// Symbols ordered by code length
+    symbols: Vec<u32>,
+}
+
+/// Order of the code length codes (16 and less: code lengths, 17-20: runs)
+const CODE_LENGTH_CODE_ORDER: [usize; 21] = [17, 18, 19, 20, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15, 16];
+
+impl Huffman {
+    fn new(code_sizes: &[u8]) -> Result<Self, String> {
+        let mut counts = [0; 17];
+        for &size in code_sizes {
+            counts[size as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i64;
+        for &count in &counts[1..] {
+            left = 2 * left - i64::from(count);
+            if left < 0 {
+                return Err("over-subscribed Huffman code".to_owned())
+            }
+        }
+        let symbols = (1..=16)
+            .flat_map(|len| (0..code_sizes.len() as u32).filter(move |&symbol| code_sizes[symbol as usize] == len))
+            .collect();
+        Ok(Huffman { counts, symbols })
+    }
+
+    /// Reads the code lengths of a Huffman code, themselves Huffman coded (with runs of zeros and repeats)
+    fn read(reader: &mut BitReader<'_>) -> Result<Self, String> {
+        let num_symbols = reader.bits(14) as usize;
+        if num_symbols == 0 {
+            return Huffman::new(&[])
+        }
+        let num_code_length_codes = reader.bits(5) as usize;
+        if !(1..=CODE_LENGTH_CODE_ORDER.len()).contains(&num_code_length_codes) {
+            return Err("invalid number of code length codes".to_owned())
+        }
+        let mut code_length_sizes = [0; 21];
+        for &code in &CODE_LENGTH_CODE_ORDER[..num_code_length_codes] {
+            code_length_sizes[code] = reader.bits(3) as u8;
+        }
+        let code_lengths = Huffman::new(&code_length_sizes)?;
+
+        let mut sizes = vec![0; num_symbols];
+        let mut i = 0;
+        while i < num_symbols {
+            let (len, size) = match code_lengths.decode(reader)? {
+                size @ 0..=16 => (1, size as u8),
+                17 => (reader.bits(3) as usize + 3, 0),
+                18 => (reader.bits(7) as usize + 11, 0),
+                code => {
+                    let len = if code == 19 { reader.bits(2) as usize + 3 } else { reader.bits(7) as usize + 7 };
+                    match i.checked_sub(1).map(|previous| sizes[previous]) {
+                        Some(size) if size > 0 => (len, size),
+                        _ => return Err("invalid repeated code length".to_owned()),
+                    }
+                }
+            };
+            if i + len > num_symbols {
+                return Err("too many code lengths".to_owned())
+            }
+            sizes[i..i + len].iter_mut().for_each(|s| *s = size);
+            i += len;
+        }
+        Huffman::new(&sizes)
+    }
+
+    fn decode(&self, reader: &mut BitReader<'_>) -> Result<u32, String> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &self.counts[1..] {
+            code |= reader.bit();
+            if code < first + count {
+                return Ok(self.symbols[(index + code - first) as usize])
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_owned())
+    }
+}
+
+/// ETC1 intensity modifiers, in the order of the (linear) ETC1S selectors
+const ETC1_MODIFIERS: [[i32; 4]; 8] = [
+    [-8, -2, 2, 8], [-17, -5, 5, 17], [-29, -9, 9, 29], [-42, -13, 13, 42],
+    [-60, -18, 18, 60], [-80, -24, 24, 80], [-106, -33, 33, 106], [-183, -47, 47, 183],
+];
+
+/// Base color (5 bits per channel) and intensity table of an ETC1S block
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct Endpoint {
+    color5: [u8; 3],
+    inten: u8,
+}
+
+impl Endpoint {
+    /// The colors of the selectors 0-3
+    fn colors(&self) -> [[u8; 3]; 4] {
+        let mut colors = [[0; 3]; 4];
+        for (color, &modifier) in colors.iter_mut().zip(&ETC1_MODIFIERS[self.inten as usize]) {
+            for (value, &c) in color.iter_mut().zip(&self.color5) {
+                *value = (i32::from(c << 3 | c >> 2) + modifier).clamp(0, 255) as u8;
+            }
+        }
+        colors
+    }
+}
+
+/// Selectors (0-3) of an ETC1S block, row by row
+type Selectors = [u8; 16];
+
+/// Byte offset and length of the RGB and alpha slices of an image within its level
+struct ImageDesc {
+    rgb: (usize, usize),
+    alpha: (usize, usize),
+}
+
+/// Codebooks and Huffman codes of the BasisLZ global data
+struct Etc1s {
+    endpoints: Vec<Endpoint>,
+    selectors: Vec<Selectors>,
+    endpoint_pred_model: Huffman,
+    delta_endpoint_model: Huffman,
+    selector_model: Huffman,
+    selector_history_buf_rle_model: Huffman,
+    selector_history_buf_size: usize,
+}
+
+const IMAGE_FLAG_P_FRAME: u32 = 2;
+
+/// Symbol of `endpoint_pred_model` to repeat the last prediction symbol
+const ENDPOINT_PRED_REPEAT_LAST_SYMBOL: u32 = 256;
+const ENDPOINT_PRED_MIN_REPEAT_COUNT: u32 = 3;
+const SELECTOR_HISTORY_BUF_RLE_COUNT_THRESH: usize = 3;
+const SELECTOR_HISTORY_BUF_RLE_COUNT_TOTAL: u32 = 64;
+
+/// Bounds of the previous color value for the choice of the color delta model (`COLOR5_PAL0_PREV_HI`...)
+const COLOR5_PAL0_PREV_HI: u8 = 9;
+const COLOR5_PAL1_PREV_HI: u8 = 21;
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, String> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| "unexpected end of the global data".to_owned())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<usize, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| "unexpected end of the global data".to_owned())
+}
+
+impl Etc1s {
+    /// Parses the global data of an image with `image_count` images (i.e. levels of a 2D texture)
+    fn new(data: &[u8], image_count: usize) -> Result<(Etc1s, Vec<ImageDesc>), String> {
+        let num_endpoints = read_u16(data, 0)? as usize;
+        let num_selectors = read_u16(data, 2)? as usize;
+        if num_endpoints == 0 || num_selectors == 0 {
+            return Err("empty codebook".to_owned())
+        }
+        let lengths = [read_u32(data, 4)?, read_u32(data, 8)?, read_u32(data, 12)?, read_u32(data, 16)?];
+
+        let mut images = Vec::with_capacity(image_count);
+        let mut offset = 20;
+        for _ in 0..image_count {
+            if read_u32(data, offset)? as u32 & IMAGE_FLAG_P_FRAME != 0 {
+                return Err("video frames are not supported".to_owned())
+            }
+            images.push(ImageDesc {
+                rgb: (read_u32(data, offset + 4)?, read_u32(data, offset + 8)?),
+                alpha: (read_u32(data, offset + 12)?, read_u32(data, offset + 16)?),
+            });
+            offset += 20;
+        }
+
+        let mut sections = Vec::with_capacity(3);
+        for &length in &lengths[..3] {
+            sections.push(data.get(offset..offset + length).ok_or("unexpected end of the global data")?);
+            offset += length;
+        }
+
+        let mut reader = BitReader::new(sections[2]);
+        let etc1s = Etc1s {
+            endpoints: decode_endpoints(sections[0], num_endpoints)?,
+            selectors: decode_selectors(sections[1], num_selectors)?,
+            endpoint_pred_model: Huffman::read(&mut reader)?,
+            delta_endpoint_model: Huffman::read(&mut reader)?,
+            selector_model: Huffman::read(&mut reader)?,
+            selector_history_buf_rle_model: Huffman::read(&mut reader)?,
+            selector_history_buf_size: reader.bits(13) as usize,
+        };
+        Ok((etc1s, images))
+    }
+
+    /// Decodes the endpoint and selector indices of the blocks of a slice
+    fn decode_slice(&self, data: &[u8], blocks_x: usize, blocks_y: usize) -> Result<Vec<(usize, usize)>, String> {
+        let mut reader = BitReader::new(data);
+        let num_endpoints = self.endpoints.len();
+        let num_selectors = self.selectors.len();
+        let rle_symbol = (num_selectors + self.selector_history_buf_size) as u32;
+        let mut history = SelectorHistory::new(self.selector_history_buf_size);
+        let mut rle_count = 0;
+
+        // endpoint index and (for the lower blocks of 2x2 groups) prediction bits of the blocks of the last two rows
+        let mut preds = [vec![(0, 0); blocks_x], vec![(0, 0); blocks_x]];
+        let mut pred_bits = 0;
+        let mut prev_pred_symbol = 0;
+        let mut pred_repeat_count = 0;
+        let mut prev_endpoint = 0;
+
+        let mut blocks = Vec::with_capacity(blocks_x * blocks_y);
+        for y in 0..blocks_y {
+            let row = y & 1;
+            for x in 0..blocks_x {
+                // the prediction symbol contains the predictions of a 2x2 group of blocks
+                if x & 1 == 0 {
+                    if y & 1 == 0 {
+                        if pred_repeat_count > 0 {
+                            pred_repeat_count -= 1;
+                            pred_bits = prev_pred_symbol;
+                        } else {
+                            pred_bits = self.endpoint_pred_model.decode(&mut reader)?;
+                            if pred_bits == ENDPOINT_PRED_REPEAT_LAST_SYMBOL {
+                                pred_repeat_count = reader.vlc(4)? + ENDPOINT_PRED_MIN_REPEAT_COUNT - 1;
+                                pred_bits = prev_pred_symbol;
+                            } else {
+                                prev_pred_symbol = pred_bits;
+                            }
+                        }
+                        preds[row ^ 1][x].1 = pred_bits >> 4;
+                    } else {
+                        pred_bits = preds[row][x].1;
+                    }
+                }
+
+                let invalid = || "invalid endpoint prediction".to_owned();
+                let endpoint = match pred_bits & 3 {
+                    // left, upper and upper left block
+                    0 if x > 0 => prev_endpoint,
+                    1 if y > 0 => preds[row ^ 1][x].0,
+                    2 if x > 0 && y > 0 => preds[row ^ 1][x - 1].0,
+                    3 => {
+                        let mut endpoint = self.delta_endpoint_model.decode(&mut reader)? as usize + prev_endpoint;
+                        if endpoint >= num_endpoints {
+                            endpoint -= num_endpoints;
+                        }
+                        if endpoint >= num_endpoints {
+                            return Err(invalid())
+                        }
+                        endpoint
+                    }
+                    _ => return Err(invalid()),
+                };
+                pred_bits >>= 2;
+                preds[row][x].0 = endpoint;
+                prev_endpoint = endpoint;
+
+                // selectors: an index, an entry of the history buffer or a run of the most recent one
+                let symbol = if rle_count > 0 {
+                    rle_count -= 1;
+                    num_selectors as u32
+                } else {
+                    match self.selector_model.decode(&mut reader)? {
+                        symbol if symbol == rle_symbol => {
+                            rle_count = match self.selector_history_buf_rle_model.decode(&mut reader)? {
+                                run if run == SELECTOR_HISTORY_BUF_RLE_COUNT_TOTAL - 1 => reader.vlc(7)? as usize,
+                                run => run as usize,
+                            } + SELECTOR_HISTORY_BUF_RLE_COUNT_THRESH;
+                            if rle_count > blocks_x * blocks_y {
+                                return Err("invalid selector run".to_owned())
+                            }
+                            rle_count -= 1;
+                            num_selectors as u32
+                        }
+                        symbol => symbol,
+                    }
+                };
+                let selector = match (symbol as usize).checked_sub(num_selectors) {
+                    None => {
+                        history.add(symbol as usize);
+                        symbol as usize
+                    }
+                    Some(index) => {
+                        let selector = *history.values.get(index).ok_or("invalid selector history index")?;
+                        history.use_index(index);
+                        selector
+                    }
+                };
+                blocks.push((endpoint, selector));
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn block_pixels(&self, (endpoint, selector): (usize, usize)) -> Block {
+        let colors = self.endpoints[endpoint].colors();
+        let mut pixels = [[0; 4]; 16];
+        for (pixel, &s) in pixels.iter_mut().zip(&self.selectors[selector]) {
+            let [r, g, b] = colors[s as usize];
+            *pixel = [r, g, b, 255];
+        }
+        pixels
+    }
+}
+
+/// Recently used selectors, approximately ordered by recency (`approx_move_to_front`)
+struct SelectorHistory {
+    values: Vec<usize>,
+    rover: usize,
+}
+
+impl SelectorHistory {
+    fn new(size: usize) -> Self {
+        SelectorHistory { values: vec![0; size], rover: size / 2 }
+    }
+
+    fn add(&mut self, value: usize) {
+        if self.values.is_empty() {
+            return
+        }
+        self.values[self.rover] = value;
+        self.rover += 1;
+        if self.rover == self.values.len() {
+            self.rover = self.values.len() / 2;
+        }
+    }
+
+    fn use_index(&mut self, index: usize) {
+        self.values.swap(index / 2, index);
+    }
+}
+
+/// Decodes the endpoint codebook (delta coded colors and intensities)
+fn decode_endpoints(data: &[u8], count: usize) -> Result<Vec<Endpoint>, String> {
+    let mut reader = BitReader::new(data);
+    let color_models = [Huffman::read(&mut reader)?, Huffman::read(&mut reader)?, Huffman::read(&mut reader)?];
+    let inten_model = Huffman::read(&mut reader)?;
+    let grayscale = reader.bit() == 1;
+
+    let mut previous = Endpoint { color5: [16; 3], inten: 0 };
+    let mut endpoints = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut endpoint = Endpoint {
+            color5: previous.color5,
+            inten: ((inten_model.decode(&mut reader)? + u32::from(previous.inten)) & 7) as u8,
+        };
+        for c in 0..if grayscale { 1 } else { 3 } {
+            let model = match previous.color5[c] {
+                value if value <= COLOR5_PAL0_PREV_HI => &color_models[0],
+                value if value <= COLOR5_PAL1_PREV_HI => &color_models[1],
+                _ => &color_models[2],
+            };
+            endpoint.color5[c] = ((model.decode(&mut reader)? + u32::from(previous.color5[c])) & 31) as u8;
+        }
+        if grayscale {
+            endpoint.color5 = [endpoint.color5[0]; 3];
+        }
+        endpoints.push(endpoint);
+        previous = endpoint;
+    }
+    Ok(endpoints)
+}
+
+/// Decodes the selector codebook (raw or with Huffman coded XOR deltas of the rows)
+fn decode_selectors(data: &[u8], count: usize) -> Result<Vec<Selectors>, String> {
+    let mut reader = BitReader::new(data);
+    if reader.bit() == 1 || reader.bit() == 1 {
+        return Err("global selector codebooks are not supported".to_owned())
+    }
+    let raw = reader.bit() == 1;
+    let delta_model = if raw { None } else { Some(Huffman::read(&mut reader)?) };
+
+    let mut previous = [0; 4];
+    let mut selectors = Vec::with_capacity(count);
+    for i in 0..count {
+        let mut selector = [0; 16];
+        for (row, previous) in previous.iter_mut().enumerate() {
+            *previous = match delta_model {
+                Some(ref model) if i > 0 => model.decode(&mut reader)? ^ *previous,
+                _ => reader.bits(8),
+            };
+            for x in 0..4 {
+                selector[row * 4 + x] = (*previous >> (2 * x) & 3) as u8;
+            }
+        }
+        selectors.push(selector);
+    }
+    Ok(selectors)
+}
+
+/// ETC1 block (differential mode without delta) of an ETC1S block
+fn etc1_block(endpoint: &Endpoint, selectors: &Selectors) -> [u8; 8] {
+    let [r, g, b] = endpoint.color5;
+    // pixel indices are ordered by column, with the sign (most significant bit) in the upper 16 bits
+    let mut indices = 0u32;
+    for (i, &selector) in selectors.iter().enumerate() {
+        let index: u32 = [3, 2, 0, 1][selector as usize];
+        let bit = (i % 4) * 4 + i / 4;
+        indices |= (index >> 1) << (bit + 16) | (index & 1) << bit;
+    }
+    let [i0, i1, i2, i3] = indices.to_be_bytes();
+    // both subblocks have the same table, the flip bit is irrelevant
+    [r << 3, g << 3, b << 3, endpoint.inten << 5 | endpoint.inten << 2 | 0b11, i0, i1, i2, i3]
+}
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14], [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12], [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11], [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10], [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9], [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9], [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9], [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8], [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Encodes the alpha values (row by row) of a block as an EAC block, searching the tables and
+/// the multipliers and base values that fit the range of the values
+fn encode_eac(values: &[u8; 16]) -> [u8; 8] {
+    let min = i32::from(*values.iter().min().unwrap());
+    let max = i32::from(*values.iter().max().unwrap());
+    // (error, base, multiplier, table, indices)
+    let mut best = (u32::MAX, 0, 0, 0, [0; 16]);
+    for (table, modifiers) in EAC_MODIFIERS.iter().enumerate() {
+        let span = modifiers[7] - modifiers[3];
+        let estimate = (max - min + span / 2) / span;
+        for multiplier in (estimate - 1).max(1)..=(estimate + 1).min(15) {
+            let center = (min + max - (modifiers[3] + modifiers[7]) * multiplier) / 2;
+            for base in (center - 1).max(0)..=(center + 1).min(255) {
+                let mut error = 0;
+                let mut indices = [0; 16];
+                for (index, &value) in indices.iter_mut().zip(values) {
+                    let (e, i) = modifiers.iter().enumerate()
+                        .map(|(i, &m)| (((base + m * multiplier).clamp(0, 255) - i32::from(value)).pow(2) as u32, i))
+                        .min().unwrap();
+                    error += e;
+                    *index = i as u64;
+                }
+                if error < best.0 {
+                    best = (error, base, multiplier, table, indices);
+                }
+            }
+        }
+    }
+    let (_, base, multiplier, table, indices) = best;
+    let mut bits = (base as u64) << 56 | (multiplier as u64) << 52 | (table as u64) << 48;
+    for (i, &index) in indices.iter().enumerate() {
+        // ordered by column
+        bits |= index << (45 - 3 * ((i % 4) * 4 + i / 4));
+    }
+    bits.to_be_bytes()
+}
+
+const BC7_WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// BC7 mode 6 block: 7 bit RGBA endpoints with a p-bit each, 4 bit indices
+struct Bc7Mode6 {
+    endpoints: [[u8; 4]; 2],
+    p_bits: [u8; 2],
+    indices: [u8; 16],
+    error: u32,
+}
+
+impl Bc7Mode6 {
+    fn new(pixels: &Block, e0: [f32; 4], e1: [f32; 4]) -> Self {
+        let (q0, p0) = quantize_bc7_endpoint(e0);
+        let (q1, p1) = quantize_bc7_endpoint(e1);
+        let mut palette = [[0; 4]; 16];
+        for (color, &w) in palette.iter_mut().zip(&BC7_WEIGHTS4) {
+            for c in 0..4 {
+                let (a, b) = (u32::from(q0[c] << 1 | p0), u32::from(q1[c] << 1 | p1));
+                color[c] = ((a * (64 - w) + b * w + 32) >> 6) as i32;
+            }
+        }
+        let mut indices = [0; 16];
+        let mut error = 0;
+        for (index, pixel) in indices.iter_mut().zip(pixels) {
+            let (e, i) = palette.iter().enumerate()
+                .map(|(i, color)| ((0..4).map(|c| (color[c] - i32::from(pixel[c])).pow(2) as u32).sum::<u32>(), i))
+                .min().unwrap();
+            error += e;
+            *index = i as u8;
+        }
+        Bc7Mode6 { endpoints: [q0, q1], p_bits: [p0, p1], indices, error }
+    }
+
+    fn pack(mut self) -> [u8; 16] {
+        // the index of the first pixel has an implicit most significant bit of 0
+        if self.indices[0] >= 8 {
+            self.endpoints.swap(0, 1);
+            self.p_bits.swap(0, 1);
+            self.indices.iter_mut().for_each(|index| *index = 15 - *index);
+        }
+        let mut bits = 1u128 << 6;
+        let mut pos = 7;
+        let mut put = |value: u8, count: u32| {
+            bits |= u128::from(value) << pos;
+            pos += count;
+        };
+        for c in 0..4 {
+            put(self.endpoints[0][c], 7);
+            put(self.endpoints[1][c], 7);
+        }
+        put(self.p_bits[0], 1);
+        put(self.p_bits[1], 1);
+        for (i, &index) in self.indices.iter().enumerate() {
+            put(index, if i == 0 { 3 } else { 4 });
+        }
+        bits.to_le_bytes()
+    }
+}
+
+/// 7 bit values and p-bit closest to an endpoint
+fn quantize_bc7_endpoint(endpoint: [f32; 4]) -> ([u8; 4], u8) {
+    let quantize = |p: u8| {
+        let mut values = [0; 4];
+        let mut error = 0.0;
+        for (value, &e) in values.iter_mut().zip(&endpoint) {
+            *value = ((e - f32::from(p)) / 2.0).round().clamp(0.0, 127.0) as u8;
+            error += (f32::from(*value << 1 | p) - e).powi(2);
+        }
+        (values, error)
+    };
+    let ((v0, e0), (v1, e1)) = (quantize(0), quantize(1));
+    if e0 <= e1 { (v0, 0) } else { (v1, 1) }
+}
+
+/// Encodes a block as BC7 mode 6: endpoints along the principal axis of the colors, refined by least squares
+fn encode_bc7(pixels: &Block) -> [u8; 16] {
+    let mut mean = [0.0f32; 4];
+    for pixel in pixels {
+        for c in 0..4 {
+            mean[c] += f32::from(pixel[c]) / 16.0;
+        }
+    }
+    let mut covariance = [[0.0f32; 4]; 4];
+    for pixel in pixels {
+        for i in 0..4 {
+            for j in 0..4 {
+                covariance[i][j] += (f32::from(pixel[i]) - mean[i]) * (f32::from(pixel[j]) - mean[j]);
+            }
+        }
+    }
+    // power iteration, starting with the channel of the largest variance
+    let start = (0..4).max_by(|&a, &b| covariance[a][a].total_cmp(&covariance[b][b])).unwrap();
+    let mut axis = covariance[start];
+    for _ in 0..8 {
+        let mut next = [0.0; 4];
+        for (i, value) in next.iter_mut().enumerate() {
+            *value = (0..4).map(|j| covariance[i][j] * axis[j]).sum();
+        }
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break
+        }
+        axis = next.map(|v| v / length);
+    }
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let axis = if length < 1e-6 { [0.0; 4] } else { axis.map(|v| v / length) };
+
+    let projections = pixels.iter().map(|pixel| (0..4).map(|c| (f32::from(pixel[c]) - mean[c]) * axis[c]).sum::<f32>());
+    let (min, max) = projections.fold((0.0f32, 0.0f32), |(min, max), t| (min.min(t), max.max(t)));
+    let endpoint = |t: f32| [0, 1, 2, 3].map(|c| mean[c] + axis[c] * t);
+    let mut best = Bc7Mode6::new(pixels, endpoint(min), endpoint(max));
+
+    for _ in 0..2 {
+        // least squares endpoints for the current indices
+        let (mut a, mut b, mut c) = (0.0, 0.0, 0.0);
+        let (mut r0, mut r1) = ([0.0f32; 4], [0.0f32; 4]);
+        for (pixel, &index) in pixels.iter().zip(&best.indices) {
+            let w = BC7_WEIGHTS4[index as usize] as f32 / 64.0;
+            a += (1.0 - w) * (1.0 - w);
+            b += (1.0 - w) * w;
+            c += w * w;
+            for i in 0..4 {
+                r0[i] += (1.0 - w) * f32::from(pixel[i]);
+                r1[i] += w * f32::from(pixel[i]);
+            }
+        }
+        let determinant = a * c - b * b;
+        if determinant.abs() < 1e-6 {
+            break
+        }
+        let e0 = [0, 1, 2, 3].map(|i| ((c * r0[i] - b * r1[i]) / determinant).clamp(0.0, 255.0));
+        let e1 = [0, 1, 2, 3].map(|i| ((a * r1[i] - b * r0[i]) / determinant).clamp(0.0, 255.0));
+        let candidate = Bc7Mode6::new(pixels, e0, e1);
+        if candidate.error >= best.error {
+            break
+        }
+        best = candidate;
+    }
+    best.pack()
+}
+
+/// Huffman codes (code, bits) of the UASTC modes. Mode 19 is reserved.
+const UASTC_MODE_CODES: [(u32, u32); 20] = [
+    (0x1, 4), (0x35, 6), (0x1d, 5), (0x3, 5), (0x13, 5), (0xb, 5), (0x1b, 5), (0x7, 5), (0x17, 5), (0xf, 5),
+    (0x2, 3), (0x0, 2), (0x6, 3), (0x1f, 5), (0xd, 5), (0x5, 7), (0x15, 6), (0x25, 6), (0x9, 4), (0x45, 7),
+];
+
+const UASTC_MODE_SOLID_COLOR: usize = 8;
+
+/// Parameters of a UASTC mode (an ASTC 4x4 block configuration)
+struct UastcMode {
+    weight_bits: u32,
+    /// ASTC range of the endpoint values (index into `BISE_RANGES`)
+    endpoint_range: usize,
+    /// 2: luminance/alpha, 3: RGB, 4: RGBA
+    components: usize,
+    /// Bits of the BC1/ETC1/ETC2 transcoding hints (not needed for decoding)
+    hint_bits: u32,
+}
+
+const fn mode(weight_bits: u32, endpoint_range: usize, components: usize, hint_bits: u32) -> UastcMode {
+    UastcMode { weight_bits, endpoint_range, components, hint_bits }
+}
+
+const UASTC_MODES: [UastcMode; 19] = [
+    mode(4, 19, 3, 15), mode(2, 20, 3, 15), mode(3, 8, 3, 15), mode(2, 7, 3, 15), mode(2, 12, 3, 15),
+    mode(3, 20, 3, 15), mode(2, 18, 3, 15), mode(2, 12, 3, 15), mode(0, 0, 4, 0), mode(2, 8, 4, 23),
+    mode(4, 13, 4, 17), mode(2, 13, 4, 17), mode(3, 19, 4, 17), mode(1, 20, 4, 23), mode(2, 20, 2, 23),
+    mode(4, 20, 2, 23), mode(2, 20, 2, 23), mode(2, 20, 2, 23), mode(5, 11, 3, 15),
+];
+
+/// ASTC partition seeds of the 2 subset partitions shared with BC7 (modes 2, 4, 9 and 16)
+const PARTITIONS2: [u32; 30] = [
+    28, 20, 16, 29, 91, 9, 107, 72, 149, 204, 50, 114, 496, 17, 78, 39,
+    252, 828, 43, 156, 116, 210, 476, 273, 684, 359, 246, 195, 694, 524,
+];
+/// ASTC partition seeds of the 3 subset partitions shared with BC7 (mode 3)
+const PARTITIONS3: [u32; 11] = [260, 74, 32, 156, 183, 15, 745, 0, 335, 902, 254];
+/// ASTC partition seeds of the 2 subset partitions matching 3 subset BC7 partitions (mode 7)
+const PARTITIONS_BC7_3_ASTC_2: [u32; 19] = [36, 48, 61, 137, 161, 183, 226, 281, 302, 307, 479, 495, 593, 594, 605, 799, 812, 988, 993];
+
+/// Number of bits, trits and quints of the ASTC integer sequence encoding ranges
+const BISE_RANGES: [(u32, u32, u32); 21] = [
+    (1, 0, 0), (0, 1, 0), (2, 0, 0), (0, 0, 1), (1, 1, 0), (3, 0, 0), (1, 0, 1), (2, 1, 0), (4, 0, 0), (2, 0, 1), (3, 1, 0),
+    (5, 0, 0), (3, 0, 1), (4, 1, 0), (6, 0, 0), (4, 0, 1), (5, 1, 0), (7, 0, 0), (5, 0, 1), (6, 1, 0), (8, 0, 0),
+];
+
+/// Decodes the pixels of a UASTC block
+fn decode_uastc(data: &[u8]) -> Result<Block, String> {
+    let mut reader = BitReader::new(data);
+    let mode = UASTC_MODE_CODES.iter()
+        .position(|&(code, bits)| u32::from(data[0]) & ((1 << bits) - 1) == code)
+        .filter(|&mode| mode < UASTC_MODES.len())
+        .ok_or("invalid UASTC mode")?;
+    reader.bits(UASTC_MODE_CODES[mode].1);
+    if mode == UASTC_MODE_SOLID_COLOR {
+        let color = [reader.bits(8) as u8, reader.bits(8) as u8, reader.bits(8) as u8, reader.bits(8) as u8];
+        return Ok([color; 16])
+    }
+    let params = &UASTC_MODES[mode];
+    reader.bits(params.hint_bits);
+
+    let (subsets, seed) = match mode {
+        2 | 4 | 9 | 16 => (2, PARTITIONS2.get(reader.bits(5) as usize)),
+        3 => (3, PARTITIONS3.get(reader.bits(4) as usize)),
+        7 => (2, PARTITIONS_BC7_3_ASTC_2.get(reader.bits(5) as usize)),
+        _ => (1, Some(&0)),
+    };
+    let seed = *seed.ok_or("invalid UASTC partition pattern")?;
+    // the component using the weights of the second plane
+    let ccs = match mode {
+        6 | 11 | 13 => Some(reader.bits(2) as usize),
+        17 => Some(3),
+        _ => None,
+    };
+
+    let values = read_endpoint_values(&mut reader, params.components * 2 * subsets, params.endpoint_range);
+    let endpoints: Vec<_> = values.chunks_exact(params.components * 2)
+        .map(|values| endpoint_colors(values, params.components))
+        .collect();
+
+    let mut subset = [0; 16];
+    if subsets > 1 {
+        for (i, s) in subset.iter_mut().enumerate() {
+            *s = astc_partition(seed, i as u32 % 4, i as u32 / 4, subsets as u32);
+        }
+    }
+    // the first texel of each subset (anchor) has an implicit most significant weight bit of 0
+    let is_anchor = |i: usize| !subset[..i].contains(&subset[i]);
+    let planes = if ccs.is_some() { 2 } else { 1 };
+    let mut weights = [0; 32];
+    for (i, weight) in weights[..16 * planes].iter_mut().enumerate() {
+        let bits = params.weight_bits - is_anchor(i / planes) as u32;
+        *weight = unquantize_weight(reader.bits(bits), params.weight_bits);
+    }
+
+    let mut pixels = [[0; 4]; 16];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let (e0, e1) = endpoints[subset[i]];
+        for c in 0..4 {
+            let weight = match ccs {
+                Some(ccs) => weights[2 * i + (c == ccs) as usize],
+                None => weights[i],
+            };
+            // endpoints expanded to 16 bits, like an ASTC decoder
+            let (a, b) = (u32::from(e0[c]) * 257, u32::from(e1[c]) * 257);
+            pixel[c] = ((a * (64 - weight) + b * weight + 32) >> 6 >> 8) as u8;
+        }
+    }
+    Ok(pixels)
+}
+
+/// Reads and unquantizes endpoint values. Unlike ASTC, UASTC stores the trits/quints of all values
+/// (packed into numbers of base 3 or 5 for 5 or 3 values) before the bits of the values.
+fn read_endpoint_values(reader: &mut BitReader<'_>, count: usize, range: usize) -> Vec<u8> {
+    let (bits, trits, quints) = BISE_RANGES[range];
+    let (bundle_size, base) = match (trits, quints) {
+        (1, _) => (5, 3),
+        (_, 1) => (3, 5),
+        _ => (0, 1u32),
+    };
+    let mut bundles = vec![];
+    if bundle_size > 0 {
+        for i in 0..count.div_ceil(bundle_size) {
+            let bits = match (trits, count - i * bundle_size) {
+                (1, 1) => 2,
+                (1, 2) => 4,
+                (1, 3) => 5,
+                (1, 4) => 7,
+                (1, _) => 8,
+                (_, 1) => 3,
+                (_, 2) => 5,
+                _ => 7,
+            };
+            bundles.push(reader.bits(bits));
+        }
+    }
+    (0..count)
+        .map(|i| {
+            let mut value = reader.bits(bits);
+            if let Some(bundle) = i.checked_div(bundle_size) {
+                value |= (bundles[bundle] / base.pow((i % bundle_size) as u32) % base) << bits;
+            }
+            unquantize_color(value, range)
+        })
+        .collect()
+}
+
+/// ASTC color endpoint unquantization to 0-255
+fn unquantize_color(value: u32, range: usize) -> u8 {
+    let (bits, trits, quints) = BISE_RANGES[range];
+    if trits == 0 && quints == 0 {
+        // bit replication
+        let (mut replicated, mut filled) = (0, 0);
+        while filled < 8 {
+            replicated = replicated << bits | value;
+            filled += bits;
+        }
+        return (replicated >> (filled - 8)) as u8
+    }
+    let m = value & ((1 << bits) - 1);
+    let digit = value >> bits;
+    if bits == 0 {
+        return (digit * 255 / if trits == 1 { 2 } else { 4 }) as u8
+    }
+    let a = if m & 1 == 1 { 0x1ff } else { 0 };
+    let x = m >> 1;
+    let (b, c) = match (trits, bits) {
+        (1, 1) => (0, 204),
+        (1, 2) => (x << 8 | x << 4 | x << 2 | x << 1, 93),
+        (1, 3) => (x << 7 | x << 2 | x, 44),
+        (1, 4) => (x << 6 | x, 22),
+        (1, 5) => (x << 5 | x >> 2, 11),
+        (1, _) => (x << 4 | x >> 4, 5),
+        (_, 1) => (0, 113),
+        (_, 2) => (x << 8 | x << 3 | x << 2, 54),
+        (_, 3) => (x << 7 | x << 1 | x >> 1, 26),
+        (_, 4) => (x << 6 | x >> 1, 13),
+        _ => (x << 5 | x >> 3, 6),
+    };
+    let t = (digit * c + b) ^ a;
+    ((a & 0x80) | t >> 2) as u8
+}
+
+/// ASTC weight unquantization (of UASTC weights without trits/quints) to 0-64
+fn unquantize_weight(value: u32, bits: u32) -> u32 {
+    let value = match bits {
+        1 => value * 63,
+        2 => value << 4 | value << 2 | value,
+        3 => value << 3 | value,
+        4 => value << 2 | value >> 2,
+        _ => value << 1 | value >> 4,
+    };
+    if value > 32 { value + 1 } else { value }
+}
+
+/// Endpoint colors of the ASTC color endpoint modes used by UASTC (LA, RGB and RGBA direct)
+fn endpoint_colors(v: &[u8], components: usize) -> ([u8; 4], [u8; 4]) {
+    if components == 2 {
+        return ([v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]])
+    }
+    let (a0, a1) = if components == 4 { (v[6], v[7]) } else { (255, 255) };
+    let sum = |a: u8, b: u8, c: u8| u32::from(a) + u32::from(b) + u32::from(c);
+    if sum(v[1], v[3], v[5]) >= sum(v[0], v[2], v[4]) {
+        ([v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1])
+    } else {
+        // blue contraction
+        let contract = |r: u8, g: u8, b: u8, a: u8|
+            [((u32::from(r) + u32::from(b)) >> 1) as u8, ((u32::from(g) + u32::from(b)) >> 1) as u8, b, a];
+        (contract(v[1], v[3], v[5], a1), contract(v[0], v[2], v[4], a0))
+    }
+}
+
+fn hash52(mut p: u32) -> u32 {
+    p ^= p >> 15;
+    p = p.wrapping_mul(0xeede0891);
+    p ^= p >> 5;
+    p = p.wrapping_add(p << 16);
+    p ^= p >> 7;
+    p ^= p >> 3;
+    p ^= p << 6;
+    p ^= p >> 17;
+    p
+}
+
+/// Subset of a texel of a 4x4 ASTC block with the given partition seed (ASTC `select_partition`)
+fn astc_partition(seed: u32, x: u32, y: u32, count: u32) -> usize {
+    // small blocks (less than 31 texels) use doubled coordinates
+    let (x, y) = (x << 1, y << 1);
+    let seed = seed + (count - 1) * 1024;
+    let rnum = hash52(seed);
+    let mut seeds = [0u32; 8];
+    for (i, s) in seeds.iter_mut().enumerate() {
+        *s = (rnum >> (4 * i)) & 0xf;
+        *s *= *s;
+    }
+    let (sh1, sh2) = if seed & 1 == 1 {
+        (if seed & 2 == 2 { 4 } else { 5 }, if count == 3 { 6 } else { 5 })
+    } else {
+        (if count == 3 { 6 } else { 5 }, if seed & 2 == 2 { 4 } else { 5 })
+    };
+    for (i, s) in seeds.iter_mut().enumerate() {
+        *s >>= if i % 2 == 0 { sh1 } else { sh2 };
+    }
+    let a = (seeds[0] * x + seeds[1] * y + (rnum >> 14)) & 0x3f;
+    let b = (seeds[2] * x + seeds[3] * y + (rnum >> 10)) & 0x3f;
+    let c = if count < 3 { 0 } else { (seeds[4] * x + seeds[5] * y + (rnum >> 6)) & 0x3f };
+    if a >= b && a >= c {
+        0
+    } else if b >= c {
+        1
+    } else {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes bits least significant bit first
+    #[derive(Default)]
+    struct BitWriter {
+        data: Vec<u8>,
+        pos: usize,
+    }
+
+    impl BitWriter {
+        fn put(&mut self, value: u32, count: u32) {
+            for i in 0..count {
+                if self.pos.is_multiple_of(8) {
+                    self.data.push(0);
+                }
+                self.data[self.pos / 8] |= ((value >> i & 1) as u8) << (self.pos % 8);
+                self.pos += 1;
+            }
+        }
+
+        /// Huffman code with code lengths of 0 or 1
+        fn huffman(&mut self, sizes: &[u8]) {
+            self.put(sizes.len() as u32, 14);
+            if sizes.is_empty() {
+                return
+            }
+            // code length codes: '0' for a length of 0, '1' for 1
+            self.put(19, 5);
+            for &code in &CODE_LENGTH_CODE_ORDER[..19] {
+                self.put((code <= 1) as u32, 3);
+            }
+            for &size in sizes {
+                self.put(u32::from(size), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn etc1s() {
+        let mut endpoints = BitWriter::default();
+        endpoints.huffman(&[]);
+        endpoints.huffman(&[1, 0, 0, 0, 1]);
+        endpoints.huffman(&[]);
+        endpoints.huffman(&[0, 0, 0, 1]);
+        endpoints.put(0, 1);
+        // intensity 3, color (20, 16, 20)
+        endpoints.put(0b1010, 4);
+
+        let mut selectors = BitWriter::default();
+        selectors.put(0b100, 3);
+        for &row in &[0b11100100, 0, 0xff, 0x55] {
+            selectors.put(row, 8);
+        }
+
+        let mut tables = BitWriter::default();
+        tables.huffman(&[0, 0, 0, 1]);
+        tables.huffman(&[1]);
+        tables.huffman(&[1]);
+        tables.huffman(&[]);
+        tables.put(0, 13);
+
+        let mut global_data = vec![1, 0, 1, 0];
+        for &length in &[endpoints.data.len(), selectors.data.len(), tables.data.len(), 0, 0, 0, 1, 0, 0] {
+            global_data.extend_from_slice(&(length as u32).to_le_bytes());
+        }
+        for section in &[endpoints.data, selectors.data, tables.data] {
+            global_data.extend_from_slice(section);
+        }
+
+        let (format, levels) = transcode_etc1s(&global_data, &[&[0]], 4, 4, Target::Rgba8).unwrap();
+        assert_eq!(format, Format::R8G8B8A8_UNORM);
+        let pixel = |x: usize, y: usize| &levels[0][(y * 4 + x) * 4..][..4];
+        assert_eq!(pixel(0, 0), [123, 90, 123, 255]);
+        assert_eq!(pixel(1, 0), [152, 119, 152, 255]);
+        assert_eq!(pixel(2, 0), [178, 145, 178, 255]);
+        assert_eq!(pixel(3, 0), [207, 174, 207, 255]);
+        assert_eq!(pixel(2, 1), [123, 90, 123, 255]);
+        assert_eq!(pixel(1, 2), [207, 174, 207, 255]);
+        assert_eq!(pixel(3, 3), [152, 119, 152, 255]);
+
+        let (format, levels) = transcode_etc1s(&global_data, &[&[0]], 4, 4, Target::Etc2).unwrap();
+        assert_eq!(format, Format::ETC2_R8G8B8_UNORM_BLOCK);
+        assert_eq!(levels, [[160, 128, 160, 0b0110_1111, 170, 187, 118, 103]]);
+
+        assert!(transcode_etc1s(&global_data[..30], &[&[0]], 4, 4, Target::Rgba8).is_err());
+    }
+
+    #[test]
+    fn uastc_solid_color() {
+        let block = (0x17u128 | 10 << 5 | 20 << 13 | 30 << 21 | 40 << 29).to_le_bytes();
+        let (format, levels) = transcode_uastc(&[&block], 2, 2, Target::Rgba8).unwrap();
+        assert_eq!(format, Format::R8G8B8A8_UNORM);
+        assert_eq!(levels[0], [10, 20, 30, 40].repeat(4));
+        assert!(transcode_uastc(&[&block[..8]], 2, 2, Target::Rgba8).is_err());
+    }
+
+    #[test]
+    fn uastc_gradient() {
+        // mode 1: RGB endpoints (0, 0, 0) and (255, 255, 255) with 2 bit weights
+        let mut writer = BitWriter::default();
+        writer.put(0x35, 6);
+        writer.put(0, 15);
+        for &value in &[0, 255, 0, 255, 0, 255] {
+            writer.put(value, 8);
+        }
+        writer.put(0, 1);
+        for i in 1..16 {
+            writer.put(i % 4, 2);
+        }
+        writer.data.resize(16, 0);
+        let pixels = decode_uastc(&writer.data).unwrap();
+        for (i, pixel) in pixels.iter().enumerate() {
+            let value = [0, 84, 171, 255][i % 4];
+            assert_eq!(*pixel, [value, value, value, 255]);
+        }
+    }
+
+    #[test]
+    fn astc_partitions() {
+        // first 2 subset BC7 partition: two columns per subset
+        let subsets: Vec<_> = (0..16).map(|i| astc_partition(PARTITIONS2[0], i % 4, i / 4, 2)).collect();
+        let columns: Vec<_> = (0..16).map(|i| (i % 4 / 2) as usize).collect();
+        let inverted: Vec<_> = columns.iter().map(|s| 1 - s).collect();
+        assert!(subsets == columns || subsets == inverted);
+    }
+
+    #[test]
+    fn bc7() {
+        let mut pixels = [[0; 4]; 16];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = [(i * 16) as u8, (255 - i * 16) as u8, 100, 200];
+        }
+        let bits = u128::from_le_bytes(encode_bc7(&pixels));
+        assert_eq!(bits & 0x7f, 1 << 6);
+        let field = |pos: u32, count: u32| (bits >> pos & ((1 << count) - 1)) as u32;
+        let endpoint = |e: u32| [0, 1, 2, 3].map(|c| field(7 + 14 * c + 7 * e, 7) << 1 | field(63 + e, 1));
+        let (e0, e1) = (endpoint(0), endpoint(1));
+        let mut pos = 65;
+        for (i, pixel) in pixels.iter().enumerate() {
+            let count = if i == 0 { 3 } else { 4 };
+            let w = BC7_WEIGHTS4[field(pos, count) as usize];
+            pos += count;
+            for c in 0..4 {
+                let value = ((64 - w) * e0[c] + w * e1[c] + 32) >> 6;
+                assert!((value as i32 - i32::from(pixel[c])).abs() <= 4, "pixel {} {:?}", i, pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn eac() {
+        let decode = |values: &[u8; 16]| {
+            let bits = u64::from_be_bytes(encode_eac(values));
+            let (base, multiplier, table) = ((bits >> 56) as i32, (bits >> 52 & 15) as i32, (bits >> 48 & 15) as usize);
+            (0..16).map(|i| {
+                let index = (bits >> (45 - 3 * ((i % 4) * 4 + i / 4)) & 7) as usize;
+                (base + EAC_MODIFIERS[table][index] * multiplier).clamp(0, 255)
+            }).collect::<Vec<_>>()
+        };
+        let values = [0, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120, 130, 140, 150];
+        for (&value, decoded) in values.iter().zip(decode(&values)) {
+            assert!((decoded - i32::from(value)).abs() <= 12, "value {}: {}", value, decoded);
+        }
+        assert_eq!(decode(&[255; 16]), [255; 16]);
+        assert_eq!(decode(&[7; 16]), [7; 16]);
+    }
+}
@@ -0,0 +1,2066 @@
+//! Decoder for `KHR_draco_mesh_compression` buffer views (Draco bitstream 2.2 triangle meshes).
+//! See https://github.com/KhronosGroup/glTF/tree/master/extensions/2.0/Khronos/KHR_draco_mesh_compression
+//! and the bitstream specification at https://google.github.io/draco/spec/
+
+/// Reads the little endian values of the bitstream
+struct Buffer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Buffer<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Buffer { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.pos < len {
+            return Err("unexpected end of data".to_owned())
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    /// LEB128 encoded unsigned integer
+    fn varint(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value)
+            }
+        }
+        Err("invalid varint".to_owned())
+    }
+
+    fn varint_usize(&mut self) -> Result<usize, String> {
+        self.varint().map(|value| value as usize)
+    }
+}
+
+/// Decoded mesh: triangle list indices and one value per point for each attribute
+pub struct Mesh {
+    pub indices: Vec<u32>,
+    pub attributes: Vec<Attribute>,
+}
+
+pub struct Attribute {
+    /// Id referenced by the `attributes` of the extension
+    pub unique_id: u32,
+    pub components: usize,
+    pub values: Values,
+}
+
+/// Attribute values in their original format: floats (possibly dequantized) or integers
+pub enum Values {
+    Float(Vec<f32>),
+    Int(Vec<i32>),
+}
+
+const METHOD_SEQUENTIAL: u8 = 0;
+const METHOD_EDGEBREAKER: u8 = 1;
+const FLAG_METADATA: u16 = 0x8000;
+
+/// Decodes a Draco triangle mesh
+pub fn decode(data: &[u8]) -> Result<Mesh, String> {
+    let mut buffer = Buffer::new(data);
+    if buffer.bytes(5)? != b"DRACO" {
+        return Err("not a Draco bitstream".to_owned())
+    }
+    let (major, minor) = (buffer.u8()?, buffer.u8()?);
+    if (major, minor) != (2, 2) {
+        return Err(format!("unsupported Draco version {}.{} (only 2.2 is supported)", major, minor))
+    }
+    if buffer.u8()? != 1 {
+        return Err("not a Draco triangle mesh (point clouds are not supported)".to_owned())
+    }
+    let method = buffer.u8()?;
+    let flags = buffer.u16()?;
+    if flags & FLAG_METADATA != 0 {
+        skip_metadata(&mut buffer)?;
+    }
+    let mut connectivity = match method {
+        METHOD_SEQUENTIAL => decode_sequential_connectivity(&mut buffer)?,
+        METHOD_EDGEBREAKER => decode_edgebreaker_connectivity(&mut buffer)?,
+        _ => return Err(format!("unknown Draco encoding method {}", method)),
+    };
+    let attributes = decode_attributes(&mut buffer, method, &mut connectivity)?;
+    Ok(Mesh { indices: connectivity.indices, attributes })
+}
+
+fn skip_metadata(buffer: &mut Buffer<'_>) -> Result<(), String> {
+    let num_attribute_metadata = buffer.varint()?;
+    for _ in 0..num_attribute_metadata {
+        buffer.varint()?; // attribute id
+        skip_metadata_element(buffer)?;
+    }
+    skip_metadata_element(buffer)
+}
+
+fn skip_metadata_element(buffer: &mut Buffer<'_>) -> Result<(), String> {
+    let num_entries = buffer.varint()?;
+    for _ in 0..num_entries {
+        let key_len = buffer.u8()?;
+        buffer.bytes(key_len as usize)?;
+        let value_len = buffer.u8()?;
+        buffer.bytes(value_len as usize)?;
+    }
+    let num_sub_metadata = buffer.varint()?;
+    for _ in 0..num_sub_metadata {
+        let key_len = buffer.u8()?;
+        buffer.bytes(key_len as usize)?;
+        skip_metadata_element(buffer)?;
+    }
+    Ok(())
+}
+
+/// rANS decoder of symbols with the probability table of `RAnsSymbolDecoder`
+struct RAnsSymbols<'a> {
+    precision: u32,
+    /// (probability, cumulative probability) of each symbol
+    probabilities: Vec<(u32, u32)>,
+    lookup: Vec<u32>,
+    data: &'a [u8],
+    offset: usize,
+    state: u32,
+}
+
+impl<'a> RAnsSymbols<'a> {
+    /// Reads the probability table and the encoded data
+    fn new(buffer: &mut Buffer<'a>, precision_bits: u32) -> Result<Self, String> {
+        let precision = 1 << precision_bits;
+        let num_symbols = buffer.varint_usize()?;
+        let mut probabilities = Vec::with_capacity(num_symbols.min(1 << 20));
+        while probabilities.len() < num_symbols {
+            let prob_data = buffer.u8()?;
+            if prob_data & 3 == 3 {
+                let zeros = (prob_data >> 2) as usize + 1;
+                if probabilities.len() + zeros > num_symbols {
+                    return Err("invalid rANS probability table".to_owned())
+                }
+                probabilities.resize(probabilities.len() + zeros, 0);
+            } else {
+                let mut prob = u32::from(prob_data >> 2);
+                for b in 0..(prob_data & 3) as u32 {
+                    prob |= u32::from(buffer.u8()?) << (8 * (b + 1) - 2);
+                }
+                probabilities.push(prob);
+            }
+        }
+
+        let mut lookup = Vec::with_capacity(precision as usize);
+        let mut cum_prob = 0;
+        let probabilities = probabilities.into_iter().enumerate()
+            .map(|(symbol, prob)| {
+                let entry = (prob, cum_prob);
+                cum_prob += prob;
+                if cum_prob <= precision {
+                    lookup.resize(cum_prob as usize, symbol as u32);
+                }
+                entry
+            })
+            .collect();
+        if cum_prob != precision {
+            return Err("invalid rANS probability table".to_owned())
+        }
+
+        let len = buffer.varint_usize()?;
+        let data = buffer.bytes(len)?;
+        let mut symbols = RAnsSymbols { precision, probabilities, lookup, data, offset: 0, state: 0 };
+        symbols.read_init()?;
+        Ok(symbols)
+    }
+
+    fn l_base(&self) -> u32 {
+        self.precision * 4
+    }
+
+    fn read_init(&mut self) -> Result<(), String> {
+        let data = self.data;
+        let offset = data.len();
+        if offset < 1 {
+            return Err("empty rANS data".to_owned())
+        }
+        let le = |bytes: &[u8]| bytes.iter().rev().fold(0u32, |value, &b| value << 8 | u32::from(b));
+        let len = (data[offset - 1] >> 6) as usize + 1;
+        if offset < len {
+            return Err("invalid rANS data".to_owned())
+        }
+        self.offset = offset - len;
+        self.state = le(&data[self.offset..offset]) & ((1 << (8 * len - 2)) - 1);
+        self.state += self.l_base();
+        if u64::from(self.state) >= u64::from(self.l_base()) * 256 {
+            return Err("invalid rANS data".to_owned())
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> u32 {
+        while self.state < self.l_base() && self.offset > 0 {
+            self.offset -= 1;
+            self.state = self.state * 256 + u32::from(self.data[self.offset]);
+        }
+        let quo = self.state / self.precision;
+        let rem = self.state % self.precision;
+        let symbol = self.lookup[rem as usize];
+        let (prob, cum_prob) = self.probabilities[symbol as usize];
+        self.state = quo * prob + rem - cum_prob;
+        symbol
+    }
+}
+
+/// Reads single bits (least significant bit of each byte first)
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn bit(&mut self) -> u32 {
+        let byte = self.data.get(self.bit >> 3).copied().unwrap_or(0);
+        let bit = (byte >> (self.bit & 7)) & 1;
+        self.bit += 1;
+        u32::from(bit)
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |value, i| value | self.bit() << i)
+    }
+
+    fn bytes_read(&self) -> usize {
+        self.bit.div_ceil(8)
+    }
+}
+
+const SYMBOLS_TAGGED: u8 = 0;
+const SYMBOLS_RAW: u8 = 1;
+
+/// Decodes entropy coded symbols (`DecodeSymbols` of the specification)
+fn decode_symbols(buffer: &mut Buffer<'_>, count: usize, components: usize) -> Result<Vec<u32>, String> {
+    if count == 0 {
+        return Ok(vec![])
+    }
+    let mut values = Vec::with_capacity(count);
+    match buffer.u8()? {
+        SYMBOLS_TAGGED => {
+            let mut tags = RAnsSymbols::new(buffer, 12)?;
+            let mut bits = BitReader { data: &buffer.data[buffer.pos..], bit: 0 };
+            while values.len() < count {
+                let bit_length = tags.read();
+                for _ in 0..components {
+                    values.push(bits.bits(bit_length));
+                }
+            }
+            values.truncate(count);
+            buffer.pos += bits.bytes_read();
+        }
+        SYMBOLS_RAW => {
+            let max_bit_length = u32::from(buffer.u8()?);
+            if max_bit_length == 0 || max_bit_length > 18 {
+                return Err("invalid symbol bit length".to_owned())
+            }
+            let precision_bits = (3 * max_bit_length / 2).clamp(12, 20);
+            let mut symbols = RAnsSymbols::new(buffer, precision_bits)?;
+            values.extend((0..count).map(|_| symbols.read()));
+        }
+        scheme => return Err(format!("unknown symbol coding {}", scheme)),
+    }
+    Ok(values)
+}
+
+/// Inverse of the zigzag-like mapping of signed values to symbols
+fn symbol_to_signed(value: u32) -> i32 {
+    if value & 1 == 0 { (value >> 1) as i32 } else { -((value >> 1) as i32) - 1 }
+}
+
+const INVALID: u32 = u32::MAX;
+
+fn next(corner: u32) -> u32 {
+    match corner {
+        INVALID => INVALID,
+        c if c % 3 == 2 => c - 2,
+        c => c + 1,
+    }
+}
+
+fn previous(corner: u32) -> u32 {
+    match corner {
+        INVALID => INVALID,
+        c if c % 3 == 0 => c + 2,
+        c => c - 1,
+    }
+}
+
+/// Corner table of the triangles (opposite corners and a vertex per corner). Also used for the
+/// connectivity of attributes with seams (opposite corners across seams removed).
+#[derive(Clone)]
+struct CornerTable {
+    corner_to_vertex: Vec<u32>,
+    opposite: Vec<u32>,
+    /// Left most corner of each vertex (`INVALID` for isolated vertices)
+    vertex_corners: Vec<u32>,
+}
+
+impl CornerTable {
+    fn new(num_faces: usize) -> Self {
+        CornerTable {
+            corner_to_vertex: vec![INVALID; 3 * num_faces],
+            opposite: vec![INVALID; 3 * num_faces],
+            vertex_corners: vec![],
+        }
+    }
+
+    fn num_faces(&self) -> usize {
+        self.corner_to_vertex.len() / 3
+    }
+
+    fn num_vertices(&self) -> usize {
+        self.vertex_corners.len()
+    }
+
+    fn vertex(&self, corner: u32) -> u32 {
+        self.corner_to_vertex.get(corner as usize).copied().unwrap_or(INVALID)
+    }
+
+    fn opposite(&self, corner: u32) -> u32 {
+        self.opposite.get(corner as usize).copied().unwrap_or(INVALID)
+    }
+
+    fn left_most_corner(&self, vertex: u32) -> u32 {
+        self.vertex_corners.get(vertex as usize).copied().unwrap_or(INVALID)
+    }
+
+    fn swing_left(&self, corner: u32) -> u32 {
+        next(self.opposite(next(corner)))
+    }
+
+    fn swing_right(&self, corner: u32) -> u32 {
+        previous(self.opposite(previous(corner)))
+    }
+
+    fn right_corner(&self, corner: u32) -> u32 {
+        self.opposite(previous(corner))
+    }
+
+    fn left_corner(&self, corner: u32) -> u32 {
+        self.opposite(next(corner))
+    }
+
+    fn is_on_boundary(&self, vertex: u32) -> bool {
+        self.swing_left(self.left_most_corner(vertex)) == INVALID
+    }
+
+    fn set_opposite(&mut self, a: u32, b: u32) {
+        self.opposite[a as usize] = b;
+        self.opposite[b as usize] = a;
+    }
+
+    fn map_corner(&mut self, corner: u32, vertex: u32) {
+        self.corner_to_vertex[corner as usize] = vertex;
+    }
+
+    fn set_left_most_corner(&mut self, vertex: u32, corner: u32) {
+        self.vertex_corners[vertex as usize] = corner;
+    }
+
+    fn add_vertex(&mut self) -> u32 {
+        self.vertex_corners.push(INVALID);
+        self.vertex_corners.len() as u32 - 1
+    }
+
+    /// Corners around the vertex of `corner` (counter-clockwise, then clockwise from a boundary)
+    fn vertex_corners(&self, corner: u32) -> Vec<u32> {
+        let mut corners = vec![corner];
+        let mut c = self.swing_left(corner);
+        while c != INVALID && c != corner {
+            corners.push(c);
+            c = self.swing_left(c);
+        }
+        if c == INVALID {
+            c = self.swing_right(corner);
+            while c != INVALID && c != corner {
+                corners.push(c);
+                c = self.swing_right(c);
+            }
+        }
+        corners
+    }
+}
+
+/// Faces of the decoded points and the corner tables needed for decoding the attributes
+struct Connectivity {
+    /// Point of each corner
+    indices: Vec<u32>,
+    num_points: usize,
+    /// `None` for the sequential encoding
+    edgebreaker: Option<Edgebreaker>,
+}
+
+fn decode_sequential_connectivity(buffer: &mut Buffer<'_>) -> Result<Connectivity, String> {
+    let num_faces = buffer.varint_usize()?;
+    let num_points = buffer.varint_usize()?;
+    let num_indices = num_faces.checked_mul(3).ok_or("too many faces")?;
+    let indices = match buffer.u8()? {
+        0 => {
+            let symbols = decode_symbols(buffer, num_indices, 1)?;
+            let mut last = 0i64;
+            symbols.into_iter()
+                .map(|symbol| {
+                    let diff = i64::from(symbol >> 1);
+                    last += if symbol & 1 == 1 { -diff } else { diff };
+                    last as u32
+                })
+                .collect()
+        }
+        1 => (0..num_indices)
+            .map(|_| match num_points {
+                n if n < 1 << 8 => buffer.u8().map(u32::from),
+                n if n < 1 << 16 => buffer.u16().map(u32::from),
+                n if n < 1 << 21 => buffer.varint(),
+                _ => buffer.u32(),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        method => return Err(format!("unknown sequential connectivity method {}", method)),
+    };
+    if indices.iter().any(|&i| i as usize >= num_points) {
+        return Err("index out of range".to_owned())
+    }
+    Ok(Connectivity { indices, num_points, edgebreaker: None })
+}
+
+/// rANS decoder of single bits with a fixed probability of zero (`RAnsBitDecoder`)
+struct RAnsBits<'a> {
+    prob_zero: u32,
+    data: &'a [u8],
+    offset: usize,
+    state: u32,
+}
+
+const ANS_L_BASE: u32 = 4096;
+
+impl<'a> RAnsBits<'a> {
+    fn new(buffer: &mut Buffer<'a>) -> Result<Self, String> {
+        let prob_zero = u32::from(buffer.u8()?);
+        let len = buffer.varint_usize()?;
+        let data = buffer.bytes(len)?;
+        let invalid = || "invalid rANS bit data".to_owned();
+        let last = *data.last().ok_or_else(invalid)?;
+        let (offset, state) = match last >> 6 {
+            0 => (len - 1, u32::from(last & 0x3f)),
+            1 if len >= 2 => (len - 2, u32::from(u16::from_le_bytes([data[len - 2], last])) & 0x3fff),
+            2 if len >= 3 => (len - 3, (u32::from(data[len - 3]) | u32::from(data[len - 2]) << 8
+                | u32::from(last) << 16) & 0x3f_ffff),
+            _ => return Err(invalid()),
+        };
+        let state = state + ANS_L_BASE;
+        if state >= ANS_L_BASE * 256 {
+            return Err(invalid())
+        }
+        Ok(RAnsBits { prob_zero, data, offset, state })
+    }
+
+    fn read(&mut self) -> bool {
+        let p = 256 - self.prob_zero;
+        if self.state < ANS_L_BASE && self.offset > 0 {
+            self.offset -= 1;
+            self.state = self.state * 256 + u32::from(self.data[self.offset]);
+        }
+        let quot = self.state / 256;
+        let rem = self.state % 256;
+        let xn = quot * p;
+        if rem < p {
+            self.state = xn + rem;
+            true
+        } else {
+            self.state -= xn + p;
+            false
+        }
+    }
+}
+
+const TOPOLOGY_C: u32 = 0;
+const TOPOLOGY_S: u32 = 1;
+const TOPOLOGY_L: u32 = 3;
+const TOPOLOGY_R: u32 = 5;
+const TOPOLOGY_E: u32 = 7;
+
+const TRAVERSAL_STANDARD: u8 = 0;
+const TRAVERSAL_VALENCE: u8 = 2;
+
+/// Source of the edgebreaker symbols, start face configurations and attribute seams
+struct Traversal<'a> {
+    symbols: Symbols<'a>,
+    start_faces: RAnsBits<'a>,
+    seams: Vec<RAnsBits<'a>>,
+    last_symbol: u32,
+}
+
+enum Symbols<'a> {
+    /// Symbols coded with 1 or 3 bits
+    Standard(BitReader<'a>),
+    /// Symbols predicted from the valence of the vertices, decoded from the back for each context
+    Valence {
+        contexts: Vec<Vec<u32>>,
+        active_context: Option<usize>,
+        valences: Vec<i32>,
+    },
+}
+
+const MIN_VALENCE: i32 = 2;
+const MAX_VALENCE: i32 = 7;
+
+impl<'a> Traversal<'a> {
+    fn new(buffer: &mut Buffer<'a>, method: u8, num_vertices: usize, num_faces: usize,
+           num_attribute_data: usize) -> Result<Self, String> {
+        let mut symbols = match method {
+            TRAVERSAL_STANDARD => {
+                let len = buffer.varint_usize()?;
+                Symbols::Standard(BitReader { data: buffer.bytes(len)?, bit: 0 })
+            }
+            TRAVERSAL_VALENCE => Symbols::Valence { contexts: vec![], active_context: None, valences: vec![] },
+            _ => return Err(format!("unsupported edgebreaker traversal {}", method)),
+        };
+        let start_faces = RAnsBits::new(buffer)?;
+        let seams = (0..num_attribute_data)
+            .map(|_| RAnsBits::new(buffer))
+            .collect::<Result<_, _>>()?;
+        if let Symbols::Valence { ref mut contexts, ref mut valences, .. } = symbols {
+            if buffer.varint_usize()? >= num_vertices {
+                return Err("invalid number of split symbols".to_owned())
+            }
+            if buffer.u8()? != 0 {
+                return Err("unsupported valence mode".to_owned())
+            }
+            for _ in MIN_VALENCE..=MAX_VALENCE {
+                let count = buffer.varint_usize()?;
+                if count > num_faces {
+                    return Err("invalid number of valence symbols".to_owned())
+                }
+                contexts.push(decode_symbols(buffer, count, 1)?);
+            }
+            *valences = vec![0; num_vertices];
+        }
+        Ok(Traversal { symbols, start_faces, seams, last_symbol: TOPOLOGY_E })
+    }
+
+    fn symbol(&mut self) -> Result<u32, String> {
+        self.last_symbol = match self.symbols {
+            Symbols::Standard(ref mut bits) => match bits.bit() {
+                0 => TOPOLOGY_C,
+                _ => 1 | bits.bits(2) << 1,
+            },
+            Symbols::Valence { ref mut contexts, active_context: Some(context), .. } => {
+                const SYMBOLS: [u32; 5] = [TOPOLOGY_C, TOPOLOGY_S, TOPOLOGY_L, TOPOLOGY_R, TOPOLOGY_E];
+                let symbol = contexts[context].pop().ok_or("missing valence symbol")?;
+                *SYMBOLS.get(symbol as usize).ok_or("invalid valence symbol")?
+            }
+            Symbols::Valence { active_context: None, .. } => TOPOLOGY_E,
+        };
+        Ok(self.last_symbol)
+    }
+
+    /// Updates the valences for predicting the next symbol
+    fn new_active_corner(&mut self, table: &CornerTable, corner: u32) {
+        if let Symbols::Valence { ref mut active_context, ref mut valences, .. } = self.symbols {
+            let vertices = [table.vertex(corner), table.vertex(next(corner)), table.vertex(previous(corner))];
+            let increments = match self.last_symbol {
+                TOPOLOGY_C | TOPOLOGY_S => [0, 1, 1],
+                TOPOLOGY_R => [1, 1, 2],
+                TOPOLOGY_L => [1, 2, 1],
+                _ => [2, 2, 2],
+            };
+            for (&vertex, &increment) in vertices.iter().zip(&increments) {
+                if let Some(valence) = valences.get_mut(vertex as usize) {
+                    *valence += increment;
+                }
+            }
+            let valence = valences.get(vertices[1] as usize).copied().unwrap_or(0);
+            *active_context = Some((valence.clamp(MIN_VALENCE, MAX_VALENCE) - MIN_VALENCE) as usize);
+        }
+    }
+
+    fn merge_vertices(&mut self, dest: u32, source: u32) {
+        if let Symbols::Valence { ref mut valences, .. } = self.symbols {
+            valences[dest as usize] += valences[source as usize];
+        }
+    }
+}
+
+struct TopologySplit {
+    source_symbol: u32,
+    split_symbol: u32,
+    /// 0 for the left edge, 1 for the right edge of the source face
+    source_edge: u32,
+}
+
+fn decode_topology_splits(buffer: &mut Buffer<'_>, num_faces: usize) -> Result<Vec<TopologySplit>, String> {
+    let count = buffer.varint_usize()?;
+    if count > num_faces {
+        return Err("invalid number of topology splits".to_owned())
+    }
+    let mut splits = Vec::with_capacity(count);
+    let mut last_source_symbol = 0u32;
+    for _ in 0..count {
+        let source_symbol = last_source_symbol.checked_add(buffer.varint()?).ok_or("invalid topology split")?;
+        let split_symbol = source_symbol.checked_sub(buffer.varint()?).ok_or("invalid topology split")?;
+        splits.push(TopologySplit { source_symbol, split_symbol, source_edge: 0 });
+        last_source_symbol = source_symbol;
+    }
+    let mut bits = BitReader { data: &buffer.data[buffer.pos..], bit: 0 };
+    for split in &mut splits {
+        split.source_edge = bits.bit();
+    }
+    buffer.pos += bits.bytes_read();
+    Ok(splits)
+}
+
+/// Reverse decoding of the edgebreaker symbols into the corner table (`DecodeConnectivity` of
+/// `MeshEdgebreakerDecoderImpl`). Returns the number of vertices.
+fn decode_traversal(table: &mut CornerTable, traversal: &mut Traversal<'_>, mut splits: Vec<TopologySplit>,
+                    num_symbols: usize, is_vert_hole: &mut [bool], remove_invalid_vertices: bool)
+                    -> Result<usize, String> {
+    let invalid = || "invalid edgebreaker connectivity".to_owned();
+    let max_num_vertices = is_vert_hole.len();
+    let mut active_corners: Vec<u32> = vec![];
+    let mut split_active_corners = std::collections::HashMap::new();
+    let mut invalid_vertices = vec![];
+    let mut num_faces = 0;
+    for symbol_id in 0..num_symbols {
+        let corner = 3 * num_faces as u32;
+        num_faces += 1;
+        let mut check_topology_split = false;
+        match traversal.symbol()? {
+            TOPOLOGY_C => {
+                let corner_a = *active_corners.last().ok_or_else(invalid)?;
+                let vertex_x = table.vertex(next(corner_a));
+                let corner_b = next(table.left_most_corner(vertex_x));
+                if corner_b == INVALID || corner_a == corner_b || table.opposite(corner_a) != INVALID || table.opposite(corner_b) != INVALID {
+                    return Err(invalid())
+                }
+                table.set_opposite(corner_a, corner + 1);
+                table.set_opposite(corner_b, corner + 2);
+                let vert_a_prev = table.vertex(previous(corner_a));
+                let vert_b_next = table.vertex(next(corner_b));
+                if vertex_x == vert_a_prev || vertex_x == vert_b_next {
+                    return Err(invalid())
+                }
+                table.map_corner(corner, vertex_x);
+                table.map_corner(corner + 1, vert_b_next);
+                table.map_corner(corner + 2, vert_a_prev);
+                table.set_left_most_corner(vert_a_prev, corner + 2);
+                is_vert_hole[vertex_x as usize] = false;
+                *active_corners.last_mut().unwrap() = corner;
+            }
+            symbol @ TOPOLOGY_R | symbol @ TOPOLOGY_L => {
+                let corner_a = *active_corners.last().ok_or_else(invalid)?;
+                if table.opposite(corner_a) != INVALID {
+                    return Err(invalid())
+                }
+                let (opp_corner, corner_l, corner_r) = if symbol == TOPOLOGY_R {
+                    (corner + 2, corner + 1, corner)
+                } else {
+                    (corner + 1, corner, corner + 2)
+                };
+                table.set_opposite(opp_corner, corner_a);
+                let new_vertex = table.add_vertex();
+                if table.num_vertices() > max_num_vertices {
+                    return Err(invalid())
+                }
+                table.map_corner(opp_corner, new_vertex);
+                table.set_left_most_corner(new_vertex, opp_corner);
+                let vertex_r = table.vertex(previous(corner_a));
+                table.map_corner(corner_r, vertex_r);
+                table.set_left_most_corner(vertex_r, corner_r);
+                let vertex_l = table.vertex(next(corner_a));
+                table.map_corner(corner_l, vertex_l);
+                *active_corners.last_mut().unwrap() = corner;
+                check_topology_split = true;
+            }
+            TOPOLOGY_S => {
+                let corner_b = active_corners.pop().ok_or_else(invalid)?;
+                if let Some(split_corner) = split_active_corners.remove(&symbol_id) {
+                    active_corners.push(split_corner);
+                }
+                let corner_a = *active_corners.last().ok_or_else(invalid)?;
+                if corner_a == corner_b || table.opposite(corner_a) != INVALID || table.opposite(corner_b) != INVALID {
+                    return Err(invalid())
+                }
+                table.set_opposite(corner_a, corner + 2);
+                table.set_opposite(corner_b, corner + 1);
+                let vertex_p = table.vertex(previous(corner_a));
+                table.map_corner(corner, vertex_p);
+                let vertex_a_next = table.vertex(next(corner_a));
+                table.map_corner(corner + 1, vertex_a_next);
+                let vert_b_prev = table.vertex(previous(corner_b));
+                table.map_corner(corner + 2, vert_b_prev);
+                table.set_left_most_corner(vert_b_prev, corner + 2);
+                let mut corner_n = next(corner_b);
+                let vertex_n = table.vertex(corner_n);
+                if vertex_p == INVALID || vertex_n == INVALID {
+                    return Err(invalid())
+                }
+                traversal.merge_vertices(vertex_p, vertex_n);
+                let left_most = table.left_most_corner(vertex_n);
+                table.set_left_most_corner(vertex_p, left_most);
+                let first_corner = corner_n;
+                while corner_n != INVALID {
+                    table.map_corner(corner_n, vertex_p);
+                    corner_n = table.swing_left(corner_n);
+                    if corner_n == first_corner {
+                        return Err(invalid())
+                    }
+                }
+                table.set_left_most_corner(vertex_n, INVALID);
+                if remove_invalid_vertices {
+                    invalid_vertices.push(vertex_n);
+                }
+                *active_corners.last_mut().unwrap() = corner;
+            }
+            TOPOLOGY_E => {
+                let first_vertex = table.add_vertex();
+                table.add_vertex();
+                table.add_vertex();
+                if table.num_vertices() > max_num_vertices {
+                    return Err(invalid())
+                }
+                for i in 0..3 {
+                    table.map_corner(corner + i, first_vertex + i);
+                    table.set_left_most_corner(first_vertex + i, corner + i);
+                }
+                active_corners.push(corner);
+                check_topology_split = true;
+            }
+            _ => return Err(invalid()),
+        }
+        let active_corner = *active_corners.last().ok_or_else(invalid)?;
+        traversal.new_active_corner(table, active_corner);
+
+        if check_topology_split {
+            // symbol ids of the encoder are reversed
+            let encoder_symbol_id = (num_symbols - symbol_id - 1) as u32;
+            while let Some(split) = splits.last() {
+                if split.source_symbol > encoder_symbol_id {
+                    return Err(invalid())
+                }
+                if split.source_symbol != encoder_symbol_id {
+                    break
+                }
+                let new_active_corner = if split.source_edge == 1 { next(active_corner) } else { previous(active_corner) };
+                let decoder_split_symbol_id = num_symbols.checked_sub(split.split_symbol as usize + 1).ok_or_else(invalid)?;
+                split_active_corners.insert(decoder_split_symbol_id, new_active_corner);
+                splits.pop();
+            }
+        }
+    }
+    if table.num_vertices() > max_num_vertices {
+        return Err(invalid())
+    }
+
+    // start faces, connected to the remaining active edges
+    while let Some(corner) = active_corners.pop() {
+        if !traversal.start_faces.read() {
+            continue
+        }
+        if num_faces >= table.num_faces() {
+            return Err(invalid())
+        }
+        let vert_n = table.vertex(next(corner));
+        let corner_b = next(table.left_most_corner(vert_n));
+        let vert_x = table.vertex(next(corner_b));
+        let corner_c = next(table.left_most_corner(vert_x));
+        if corner_b == INVALID || corner_c == INVALID || corner == corner_b || corner == corner_c || corner_b == corner_c
+            || table.opposite(corner) != INVALID || table.opposite(corner_b) != INVALID || table.opposite(corner_c) != INVALID {
+            return Err(invalid())
+        }
+        let vert_p = table.vertex(next(corner_c));
+        let new_corner = 3 * num_faces as u32;
+        num_faces += 1;
+        table.set_opposite(new_corner, corner);
+        table.set_opposite(new_corner + 1, corner_b);
+        table.set_opposite(new_corner + 2, corner_c);
+        table.map_corner(new_corner, vert_x);
+        table.map_corner(new_corner + 1, vert_p);
+        table.map_corner(new_corner + 2, vert_n);
+        for &vertex in &[vert_x, vert_p, vert_n] {
+            is_vert_hole[vertex as usize] = false;
+        }
+    }
+    if num_faces != table.num_faces() {
+        return Err(invalid())
+    }
+
+    // move the last valid vertices to the isolated ones
+    let mut num_vertices = table.num_vertices();
+    for invalid_vertex in invalid_vertices {
+        let mut src_vertex = num_vertices as u32 - 1;
+        while table.left_most_corner(src_vertex) == INVALID {
+            num_vertices -= 1;
+            src_vertex = num_vertices as u32 - 1;
+        }
+        if src_vertex < invalid_vertex {
+            continue
+        }
+        for corner in table.vertex_corners(table.left_most_corner(src_vertex)) {
+            if table.vertex(corner) != src_vertex {
+                return Err(invalid())
+            }
+            table.map_corner(corner, invalid_vertex);
+        }
+        let left_most = table.left_most_corner(src_vertex);
+        table.set_left_most_corner(invalid_vertex, left_most);
+        table.set_left_most_corner(src_vertex, INVALID);
+        is_vert_hole[invalid_vertex as usize] = is_vert_hole[src_vertex as usize];
+        is_vert_hole[src_vertex as usize] = false;
+        num_vertices -= 1;
+    }
+    Ok(num_vertices)
+}
+
+/// Corner table and traversal state of the mesh (and of attributes with their own connectivity)
+struct Edgebreaker {
+    table: CornerTable,
+    attribute_data: Vec<AttributeData>,
+}
+
+struct AttributeData {
+    /// Connectivity with the attribute seams cut open (`MeshAttributeCornerTable`)
+    table: CornerTable,
+    /// Whether each vertex of the mesh lies on a seam
+    vertex_on_seam: Vec<bool>,
+    /// Attributes decoder using this connectivity
+    decoder_id: Option<usize>,
+    /// `false` if the attributes are decoded per vertex (with the mesh connectivity)
+    connectivity_used: bool,
+}
+
+impl AttributeData {
+    /// Builds the attribute connectivity from the seam edges (`RecomputeVertices`)
+    fn new(table: &CornerTable, seam_corners: &[u32]) -> Result<Self, String> {
+        let mut opposite = table.opposite.clone();
+        let mut vertex_on_seam = vec![false; table.num_vertices()];
+        for &corner in seam_corners {
+            let opp_corner = table.opposite(corner);
+            for &c in &[corner, opp_corner] {
+                if c != INVALID {
+                    opposite[c as usize] = INVALID;
+                    vertex_on_seam[table.vertex(next(c)) as usize] = true;
+                    vertex_on_seam[table.vertex(previous(c)) as usize] = true;
+                }
+            }
+        }
+        let is_seam = |c: u32| opposite[c as usize] == INVALID;
+        let mut attribute_table = CornerTable {
+            corner_to_vertex: vec![INVALID; table.corner_to_vertex.len()],
+            opposite: opposite.clone(),
+            vertex_corners: vec![],
+        };
+        for v in 0..table.num_vertices() as u32 {
+            let c = table.left_most_corner(v);
+            if c == INVALID {
+                continue
+            }
+            let mut vertex = attribute_table.add_vertex();
+            let mut first_c = c;
+            if vertex_on_seam[v as usize] {
+                let mut act_c = attribute_table.swing_left(first_c);
+                while act_c != INVALID {
+                    first_c = act_c;
+                    act_c = attribute_table.swing_left(act_c);
+                    if act_c == c {
+                        return Err("invalid attribute seams".to_owned())
+                    }
+                }
+            }
+            attribute_table.map_corner(first_c, vertex);
+            attribute_table.set_left_most_corner(vertex, first_c);
+            let mut act_c = table.swing_right(first_c);
+            while act_c != INVALID && act_c != first_c {
+                if is_seam(next(act_c)) {
+                    vertex = attribute_table.add_vertex();
+                    attribute_table.set_left_most_corner(vertex, act_c);
+                }
+                attribute_table.map_corner(act_c, vertex);
+                act_c = table.swing_right(act_c);
+            }
+        }
+        Ok(AttributeData { table: attribute_table, vertex_on_seam, decoder_id: None, connectivity_used: true })
+    }
+}
+
+fn decode_edgebreaker_connectivity(buffer: &mut Buffer<'_>) -> Result<Connectivity, String> {
+    let invalid = |what: &str| format!("invalid edgebreaker header: {}", what);
+    let traversal_method = buffer.u8()?;
+    let num_encoded_vertices = buffer.varint_usize()?;
+    let num_faces = buffer.varint_usize()?;
+    if num_faces > (u32::MAX / 3) as usize {
+        return Err(invalid("number of faces"))
+    }
+    let num_attribute_data = buffer.u8()? as usize;
+    let num_symbols = buffer.varint_usize()?;
+    if num_faces < num_symbols || num_faces > num_symbols + num_symbols / 3 {
+        return Err(invalid("number of symbols"))
+    }
+    let num_split_symbols = buffer.varint_usize()?;
+    if num_split_symbols > num_symbols {
+        return Err(invalid("number of split symbols"))
+    }
+    let connectivity_size = buffer.varint_usize()?;
+    if connectivity_size == 0 || connectivity_size > buffer.data.len() - buffer.pos {
+        return Err(invalid("connectivity size"))
+    }
+    let mut events = Buffer { data: buffer.data, pos: buffer.pos + connectivity_size };
+    let splits = decode_topology_splits(&mut events, num_faces)?;
+    let split_bytes = events.pos - (buffer.pos + connectivity_size);
+
+    let num_vertices = num_encoded_vertices + num_split_symbols;
+    let mut traversal = Traversal::new(buffer, traversal_method, num_vertices, num_faces, num_attribute_data)?;
+    let mut table = CornerTable::new(num_faces);
+    let mut is_vert_hole = vec![true; num_vertices];
+    let num_connectivity_vertices = decode_traversal(&mut table, &mut traversal, splits, num_symbols,
+        &mut is_vert_hole, num_attribute_data == 0)?;
+    buffer.bytes(split_bytes)?;
+
+    // attribute seams: boundary edges and decoded interior edges
+    let mut seam_corners = vec![vec![]; num_attribute_data];
+    for corner in 0..table.corner_to_vertex.len() as u32 {
+        let opp_corner = table.opposite(corner);
+        if opp_corner == INVALID {
+            seam_corners.iter_mut().for_each(|corners| corners.push(corner));
+        } else if opp_corner / 3 >= corner / 3 {
+            for (corners, seams) in seam_corners.iter_mut().zip(&mut traversal.seams) {
+                if seams.read() {
+                    corners.push(corner);
+                }
+            }
+        }
+    }
+    let attribute_data = seam_corners.iter()
+        .map(|corners| AttributeData::new(&table, corners))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (indices, num_points) = if attribute_data.is_empty() {
+        (table.corner_to_vertex.clone(), num_connectivity_vertices)
+    } else {
+        assign_points_to_corners(&table, &attribute_data, &is_vert_hole)?
+    };
+    Ok(Connectivity { indices, num_points, edgebreaker: Some(Edgebreaker { table, attribute_data }) })
+}
+
+/// Splits the vertices into points where any attribute has a seam (`AssignPointsToCorners`)
+fn assign_points_to_corners(table: &CornerTable, attribute_data: &[AttributeData], is_vert_hole: &[bool])
+                            -> Result<(Vec<u32>, usize), String> {
+    let mut corner_to_point = vec![0; table.corner_to_vertex.len()];
+    let mut num_points = 0;
+    for v in 0..table.num_vertices() {
+        let c = table.left_most_corner(v as u32);
+        if c == INVALID {
+            continue
+        }
+        let mut first_corner = c;
+        if is_vert_hole.get(v) != Some(&true) {
+            // start at a seam (of any attribute)
+            for data in attribute_data {
+                if !data.vertex_on_seam[table.vertex(c) as usize] {
+                    continue
+                }
+                let vertex = data.table.vertex(c);
+                let mut act_c = table.swing_right(c);
+                let mut seam_found = false;
+                while act_c != c {
+                    if act_c == INVALID {
+                        return Err("invalid attribute seams".to_owned())
+                    }
+                    if data.table.vertex(act_c) != vertex {
+                        first_corner = act_c;
+                        seam_found = true;
+                        break
+                    }
+                    act_c = table.swing_right(act_c);
+                }
+                if seam_found {
+                    break
+                }
+            }
+        }
+
+        corner_to_point[first_corner as usize] = num_points;
+        num_points += 1;
+        let mut prev_c = first_corner;
+        let mut c = table.swing_right(first_corner);
+        while c != INVALID && c != first_corner {
+            let attribute_seam = attribute_data.iter().any(|data| data.table.vertex(c) != data.table.vertex(prev_c));
+            if attribute_seam {
+                corner_to_point[c as usize] = num_points;
+                num_points += 1;
+            } else {
+                corner_to_point[c as usize] = corner_to_point[prev_c as usize];
+            }
+            prev_c = c;
+            c = table.swing_right(c);
+        }
+    }
+    Ok((corner_to_point, num_points as usize))
+}
+
+/// Order of the attribute values of a mesh attributes decoder (`MeshAttributeIndicesEncodingData`)
+struct Sequence {
+    /// Point of each value
+    point_ids: Vec<u32>,
+    /// Corner at which each value was reached
+    value_to_corner: Vec<u32>,
+    vertex_to_value: Vec<u32>,
+}
+
+impl Sequence {
+    fn visit(&mut self, indices: &[u32], vertex: u32, corner: u32) {
+        self.vertex_to_value[vertex as usize] = self.point_ids.len() as u32;
+        self.point_ids.push(indices[corner as usize]);
+        self.value_to_corner.push(corner);
+    }
+}
+
+const TRAVERSAL_DEPTH_FIRST: u8 = 0;
+const TRAVERSAL_PREDICTION_DEGREE: u8 = 1;
+
+/// Visits the vertices of all faces with the given traversal method
+fn traverse(table: &CornerTable, indices: &[u32], method: u8) -> Result<Sequence, String> {
+    let invalid = || "invalid mesh traversal".to_owned();
+    let num_vertices = table.num_vertices();
+    let mut sequence = Sequence {
+        point_ids: Vec::with_capacity(num_vertices),
+        value_to_corner: Vec::with_capacity(num_vertices),
+        vertex_to_value: vec![INVALID; num_vertices],
+    };
+    let mut visited_faces = vec![false; table.num_faces()];
+    let mut visited_vertices = vec![false; num_vertices];
+    let face_visited = |visited_faces: &[bool], corner: u32| corner == INVALID || visited_faces[(corner / 3) as usize];
+    let mut prediction_degree = vec![0; num_vertices];
+    let mut stack = vec![];
+    let mut priority_stacks: [Vec<u32>; 3] = Default::default();
+
+    for first_corner in (0..table.num_faces() as u32).map(|f| 3 * f) {
+        if face_visited(&visited_faces, first_corner) {
+            continue
+        }
+        let mut corners = vec![next(first_corner), previous(first_corner)];
+        if method == TRAVERSAL_PREDICTION_DEGREE {
+            corners.push(first_corner);
+        }
+        for corner in corners {
+            let vertex = table.vertex(corner);
+            if vertex == INVALID {
+                return Err(invalid())
+            }
+            if !visited_vertices[vertex as usize] {
+                visited_vertices[vertex as usize] = true;
+                sequence.visit(indices, vertex, corner);
+            }
+        }
+
+        if method == TRAVERSAL_DEPTH_FIRST {
+            stack.push(first_corner);
+            while let Some(&top) = stack.last() {
+                let mut corner = top;
+                if face_visited(&visited_faces, corner) {
+                    stack.pop();
+                    continue
+                }
+                loop {
+                    visited_faces[(corner / 3) as usize] = true;
+                    let vertex = table.vertex(corner);
+                    if vertex == INVALID {
+                        return Err(invalid())
+                    }
+                    if !visited_vertices[vertex as usize] {
+                        let on_boundary = table.is_on_boundary(vertex);
+                        visited_vertices[vertex as usize] = true;
+                        sequence.visit(indices, vertex, corner);
+                        if !on_boundary {
+                            corner = table.right_corner(corner);
+                            continue
+                        }
+                    }
+                    let right_corner = table.right_corner(corner);
+                    let left_corner = table.left_corner(corner);
+                    match (face_visited(&visited_faces, right_corner), face_visited(&visited_faces, left_corner)) {
+                        (true, true) => {
+                            stack.pop();
+                            break
+                        }
+                        (true, false) => corner = left_corner,
+                        (false, true) => corner = right_corner,
+                        (false, false) => {
+                            *stack.last_mut().unwrap() = left_corner;
+                            stack.push(right_corner);
+                            break
+                        }
+                    }
+                }
+            }
+        } else {
+            let mut best_priority = 0;
+            priority_stacks[0].push(first_corner);
+            loop {
+                let popped = (best_priority..3).find_map(|i| priority_stacks[i].pop().map(|c| (i, c)));
+                let mut corner = match popped {
+                    Some((priority, corner)) => {
+                        best_priority = priority;
+                        corner
+                    }
+                    None => break,
+                };
+                if face_visited(&visited_faces, corner) {
+                    continue
+                }
+                'face: loop {
+                    visited_faces[(corner / 3) as usize] = true;
+                    let vertex = table.vertex(corner);
+                    if vertex == INVALID {
+                        return Err(invalid())
+                    }
+                    if !visited_vertices[vertex as usize] {
+                        visited_vertices[vertex as usize] = true;
+                        sequence.visit(indices, vertex, corner);
+                    }
+                    let right_corner = table.right_corner(corner);
+                    let left_corner = table.left_corner(corner);
+                    let right_visited = face_visited(&visited_faces, right_corner);
+                    for &(next_corner, other_visited) in &[(left_corner, right_visited), (right_corner, true)] {
+                        if face_visited(&visited_faces, next_corner) {
+                            continue
+                        }
+                        let tip = table.vertex(next_corner) as usize;
+                        let priority = if visited_vertices[tip] {
+                            0
+                        } else {
+                            prediction_degree[tip] += 1;
+                            if prediction_degree[tip] > 1 { 1 } else { 2 }
+                        };
+                        if other_visited && priority <= best_priority {
+                            corner = next_corner;
+                            continue 'face
+                        }
+                        priority_stacks[priority].push(next_corner);
+                        best_priority = best_priority.min(priority);
+                    }
+                    break
+                }
+            }
+        }
+    }
+    Ok(sequence)
+}
+
+/// Octahedral coordinates of normals (`OctahedronToolBox`)
+#[derive(Copy, Clone)]
+struct Octahedron {
+    max_quantized_value: i32,
+    max_value: i32,
+    center_value: i32,
+}
+
+impl Octahedron {
+    fn new(quantization_bits: u32) -> Result<Self, String> {
+        if !(2..=30).contains(&quantization_bits) {
+            return Err(format!("invalid normal quantization bits {}", quantization_bits))
+        }
+        let max_quantized_value = (1 << quantization_bits) - 1;
+        let max_value = max_quantized_value - 1;
+        Ok(Octahedron { max_quantized_value, max_value, center_value: max_value / 2 })
+    }
+
+    fn to_unit_vector(self, s: i32, t: i32) -> [f32; 3] {
+        let scale = 2.0 / self.max_value as f32;
+        let mut y = s as f32 * scale - 1.0;
+        let mut z = t as f32 * scale - 1.0;
+        let x = 1.0 - y.abs() - z.abs();
+        let x_offset = (-x).max(0.0);
+        y += if y < 0.0 { x_offset } else { -x_offset };
+        z += if z < 0.0 { x_offset } else { -x_offset };
+        let norm_squared = x * x + y * y + z * z;
+        if norm_squared < 1e-6 {
+            return [0.0; 3]
+        }
+        let d = 1.0 / norm_squared.sqrt();
+        [x * d, y * d, z * d]
+    }
+
+    fn is_in_diamond(&self, s: i32, t: i32) -> bool {
+        s.unsigned_abs() + t.unsigned_abs() <= self.center_value as u32
+    }
+
+    fn invert_diamond(&self, s: &mut i32, t: &mut i32) {
+        let (sign_s, sign_t) = if *s >= 0 && *t >= 0 {
+            (1, 1)
+        } else if *s <= 0 && *t <= 0 {
+            (-1, -1)
+        } else {
+            (if *s > 0 { 1 } else { -1 }, if *t > 0 { 1 } else { -1 })
+        };
+        let corner_s = (sign_s * self.center_value) as u32;
+        let corner_t = (sign_t * self.center_value) as u32;
+        let mut us = (*s as u32).wrapping_mul(2).wrapping_sub(corner_s);
+        let mut ut = (*t as u32).wrapping_mul(2).wrapping_sub(corner_t);
+        if sign_s * sign_t >= 0 {
+            let temp = us;
+            us = ut.wrapping_neg();
+            ut = temp.wrapping_neg();
+        } else {
+            std::mem::swap(&mut us, &mut ut);
+        }
+        *s = us.wrapping_add(corner_s) as i32 / 2;
+        *t = ut.wrapping_add(corner_t) as i32 / 2;
+    }
+
+    fn mod_max(&self, x: i32) -> i32 {
+        if x > self.center_value {
+            x - self.max_quantized_value
+        } else if x < -self.center_value {
+            x + self.max_quantized_value
+        } else {
+            x
+        }
+    }
+
+    fn canonicalize_vector(&self, v: &mut [i64; 3]) {
+        let abs_sum = v[0].abs() + v[1].abs() + v[2].abs();
+        let center = i64::from(self.center_value);
+        if abs_sum == 0 {
+            v[0] = center;
+        } else {
+            v[0] = v[0] * center / abs_sum;
+            v[1] = v[1] * center / abs_sum;
+            let z = center - v[0].abs() - v[1].abs();
+            v[2] = if v[2] >= 0 { z } else { -z };
+        }
+    }
+
+    fn vector_to_coords(&self, v: [i64; 3]) -> [i32; 2] {
+        let (center, max) = (self.center_value, self.max_value);
+        let [x, y, z] = [v[0] as i32, v[1] as i32, v[2] as i32];
+        let (mut s, mut t) = if x >= 0 {
+            (y + center, z + center)
+        } else {
+            (if y < 0 { z.abs() } else { max - z.abs() }, if z < 0 { y.abs() } else { max - y.abs() })
+        };
+        if (s == 0 && (t == 0 || t == max)) || (s == max && t == 0) {
+            s = max;
+            t = max;
+        } else if s == 0 && t > center {
+            t = center - (t - center);
+        } else if s == max && t < center {
+            t = center + (center - t);
+        } else if t == max && s < center {
+            s = center + (center - s);
+        } else if t == 0 && s > center {
+            s = center - (s - center);
+        }
+        [s, t]
+    }
+}
+
+const TRANSFORM_WRAP: i8 = 1;
+const TRANSFORM_NORMAL_OCTAHEDRON: i8 = 2;
+const TRANSFORM_NORMAL_OCTAHEDRON_CANONICALIZED: i8 = 3;
+
+/// Transform between the predicted values, the corrections and the original values
+enum Transform {
+    Wrap { min: i32, max: i32, max_dif: i32 },
+    Octahedron { octahedron: Octahedron, canonicalized: bool },
+}
+
+impl Transform {
+    fn decode(buffer: &mut Buffer<'_>, transform_type: i8) -> Result<Self, String> {
+        let min = buffer.u32()? as i32;
+        let max = buffer.u32()? as i32;
+        if transform_type == TRANSFORM_WRAP {
+            let dif = i64::from(max) - i64::from(min);
+            if dif < 0 || dif >= i64::from(i32::MAX) {
+                return Err("invalid wrap transform".to_owned())
+            }
+            return Ok(Transform::Wrap { min, max, max_dif: 1 + dif as i32 })
+        }
+        // max quantized value and center value
+        let max_quantized_value = min;
+        if max_quantized_value <= 0 || max_quantized_value % 2 == 0 {
+            return Err("invalid normal octahedron transform".to_owned())
+        }
+        let octahedron = Octahedron::new(32 - max_quantized_value.leading_zeros())?;
+        Ok(Transform::Octahedron { octahedron, canonicalized: transform_type == TRANSFORM_NORMAL_OCTAHEDRON_CANONICALIZED })
+    }
+
+    fn original(&self, predicted: &[i32], corrections: &[i32], original: &mut [i32]) {
+        match *self {
+            Transform::Wrap { min, max, max_dif } => {
+                for ((&p, &c), o) in predicted.iter().zip(corrections).zip(original) {
+                    let value = p.max(min).min(max).wrapping_add(c);
+                    *o = if value > max {
+                        value - max_dif
+                    } else if value < min {
+                        value + max_dif
+                    } else {
+                        value
+                    };
+                }
+            }
+            Transform::Octahedron { ref octahedron, canonicalized } => {
+                let center = octahedron.center_value;
+                let mut pred = [predicted[0] - center, predicted[1] - center];
+                let in_diamond = octahedron.is_in_diamond(pred[0], pred[1]);
+                if !in_diamond {
+                    let [ref mut s, ref mut t] = pred;
+                    octahedron.invert_diamond(s, t);
+                }
+                let rotation = if canonicalized && !is_in_bottom_left(pred) { rotation_count(pred) } else { 0 };
+                pred = rotate(pred, rotation);
+                let mut orig = [
+                    octahedron.mod_max(pred[0].wrapping_add(corrections[0])),
+                    octahedron.mod_max(pred[1].wrapping_add(corrections[1])),
+                ];
+                orig = rotate(orig, (4 - rotation) % 4);
+                if !in_diamond {
+                    let [ref mut s, ref mut t] = orig;
+                    octahedron.invert_diamond(s, t);
+                }
+                original[0] = orig[0] + center;
+                original[1] = orig[1] + center;
+            }
+        }
+    }
+}
+
+fn is_in_bottom_left(p: [i32; 2]) -> bool {
+    (p[0] == 0 && p[1] == 0) || (p[0] < 0 && p[1] <= 0)
+}
+
+fn rotation_count(p: [i32; 2]) -> u32 {
+    match (p[0].signum(), p[1]) {
+        (0, 0) => 0,
+        (0, y) if y > 0 => 3,
+        (0, _) => 1,
+        (1, y) if y >= 0 => 2,
+        (1, _) => 1,
+        (_, y) if y <= 0 => 0,
+        _ => 3,
+    }
+}
+
+fn rotate(p: [i32; 2], rotation_count: u32) -> [i32; 2] {
+    match rotation_count {
+        1 => [p[1], -p[0]],
+        2 => [-p[0], -p[1]],
+        3 => [-p[1], p[0]],
+        _ => p,
+    }
+}
+
+const PREDICTION_NONE: i8 = -2;
+const PREDICTION_DIFFERENCE: i8 = 0;
+const PREDICTION_PARALLELOGRAM: i8 = 1;
+const PREDICTION_MULTI_PARALLELOGRAM: i8 = 2;
+const PREDICTION_CONSTRAINED_MULTI_PARALLELOGRAM: i8 = 4;
+const PREDICTION_TEX_COORDS_PORTABLE: i8 = 5;
+const PREDICTION_GEOMETRIC_NORMAL: i8 = 6;
+
+/// Connectivity used by the mesh prediction schemes of an attribute
+struct MeshData<'m> {
+    table: &'m CornerTable,
+    sequence: &'m Sequence,
+}
+
+/// Portable (integer) values of a decoded attribute, used as parent attribute of predictions
+struct Portable {
+    attribute_type: u8,
+    components: usize,
+    values: Vec<i32>,
+    point_to_value: Vec<u32>,
+}
+
+impl Portable {
+    fn position(&self, point: u32) -> [i64; 3] {
+        let start = self.point_to_value[point as usize] as usize * self.components;
+        let v = &self.values[start..start + 3];
+        [i64::from(v[0]), i64::from(v[1]), i64::from(v[2])]
+    }
+}
+
+enum Scheme<'a> {
+    Difference,
+    Parallelogram,
+    MultiParallelogram,
+    ConstrainedMultiParallelogram { crease_edges: Vec<Vec<bool>> },
+    TexCoordsPortable { orientations: Vec<bool> },
+    GeometricNormal { flips: RAnsBits<'a> },
+}
+
+/// Reads the transform and the data of the prediction scheme (`DecodePredictionData`)
+fn decode_prediction_data<'a>(buffer: &mut Buffer<'a>, method: i8, transform_type: i8, num_corners: usize)
+                              -> Result<(Scheme<'a>, Transform), String> {
+    Ok(match method {
+        PREDICTION_CONSTRAINED_MULTI_PARALLELOGRAM => {
+            let mut crease_edges = vec![];
+            for _ in 0..4 {
+                let num_flags = buffer.varint_usize()?;
+                if num_flags > num_corners {
+                    return Err("invalid number of crease edges".to_owned())
+                }
+                let mut flags = vec![];
+                if num_flags > 0 {
+                    let mut bits = RAnsBits::new(buffer)?;
+                    flags.extend((0..num_flags).map(|_| bits.read()));
+                }
+                crease_edges.push(flags);
+            }
+            let transform = Transform::decode(buffer, transform_type)?;
+            (Scheme::ConstrainedMultiParallelogram { crease_edges }, transform)
+        }
+        PREDICTION_TEX_COORDS_PORTABLE => {
+            let num_orientations = buffer.u32()? as i32;
+            if num_orientations < 0 {
+                return Err("invalid number of orientations".to_owned())
+            }
+            let mut bits = RAnsBits::new(buffer)?;
+            let mut last = true;
+            let orientations = (0..num_orientations)
+                .map(|_| {
+                    if !bits.read() {
+                        last = !last;
+                    }
+                    last
+                })
+                .collect();
+            let transform = Transform::decode(buffer, transform_type)?;
+            (Scheme::TexCoordsPortable { orientations }, transform)
+        }
+        PREDICTION_GEOMETRIC_NORMAL => {
+            let transform = Transform::decode(buffer, transform_type)?;
+            (Scheme::GeometricNormal { flips: RAnsBits::new(buffer)? }, transform)
+        }
+        PREDICTION_PARALLELOGRAM => (Scheme::Parallelogram, Transform::decode(buffer, transform_type)?),
+        PREDICTION_MULTI_PARALLELOGRAM => (Scheme::MultiParallelogram, Transform::decode(buffer, transform_type)?),
+        _ => (Scheme::Difference, Transform::decode(buffer, transform_type)?),
+    })
+}
+
+/// Reverts the prediction (`ComputeOriginalValues` of the prediction schemes)
+fn compute_original_values(scheme: &mut Scheme<'_>, transform: &Transform, corrections: &[i32], components: usize,
+                           mesh: Option<&MeshData<'_>>, point_ids: &[u32], positions: Option<&Portable>)
+                           -> Result<Vec<i32>, String> {
+    let mut values = vec![0; corrections.len()];
+    let zeros = vec![0; components];
+    let mesh = match (&*scheme, mesh) {
+        (Scheme::Difference, _) | (_, None) => {
+            for i in (0..values.len()).step_by(components) {
+                let (decoded, rest) = values.split_at_mut(i);
+                let predicted = if i == 0 { &zeros[..] } else { &decoded[i - components..] };
+                transform.original(predicted, &corrections[i..i + components], &mut rest[..components]);
+            }
+            return Ok(values)
+        }
+        (_, Some(mesh)) => mesh,
+    };
+    let value_of_vertex = |vertex: u32| mesh.sequence.vertex_to_value.get(vertex as usize).copied().unwrap_or(INVALID);
+    let value_of_corner = |corner: u32| value_of_vertex(mesh.table.vertex(corner));
+    let num_values = mesh.sequence.value_to_corner.len().min(values.len() / components.max(1));
+    let positions = |scheme: &str| positions.ok_or_else(|| format!("missing positions for the {} prediction", scheme));
+
+    // parallelogram prediction of the value at the corner from the opposite face
+    let parallelogram = |values: &[i32], entry: u32, corner: u32| -> Option<Vec<i32>> {
+        let opp_corner = mesh.table.opposite(corner);
+        if opp_corner == INVALID {
+            return None
+        }
+        let opp = value_of_corner(opp_corner) as usize;
+        let next = value_of_corner(next(opp_corner)) as usize;
+        let prev = value_of_corner(previous(opp_corner)) as usize;
+        if [opp, next, prev].iter().any(|&v| v >= entry as usize) {
+            return None
+        }
+        Some((0..components)
+            .map(|c| values[next * components + c].wrapping_add(values[prev * components + c])
+                .wrapping_sub(values[opp * components + c]))
+            .collect())
+    };
+
+    match scheme {
+        Scheme::Difference => unreachable!(),
+        Scheme::Parallelogram | Scheme::MultiParallelogram | Scheme::ConstrainedMultiParallelogram { .. } => {
+            let mut crease_edge_pos = [0; 4];
+            transform.original(&zeros, &corrections[..components], &mut values[..components]);
+            for p in 1..num_values {
+                let start_corner = mesh.sequence.value_to_corner[p];
+                let predictions = match scheme {
+                    Scheme::Parallelogram => parallelogram(&values, p as u32, start_corner).into_iter().collect(),
+                    Scheme::MultiParallelogram => {
+                        let mut predictions = vec![];
+                        let mut corner = start_corner;
+                        while corner != INVALID {
+                            predictions.extend(parallelogram(&values, p as u32, corner));
+                            corner = mesh.table.swing_right(corner);
+                            if corner == start_corner {
+                                break
+                            }
+                        }
+                        predictions
+                    }
+                    Scheme::ConstrainedMultiParallelogram { crease_edges } => {
+                        let mut predictions = vec![];
+                        let mut corner = start_corner;
+                        let mut first_pass = true;
+                        while corner != INVALID {
+                            predictions.extend(parallelogram(&values, p as u32, corner));
+                            if predictions.len() == 4 {
+                                break
+                            }
+                            corner = if first_pass { mesh.table.swing_left(corner) } else { mesh.table.swing_right(corner) };
+                            if corner == start_corner {
+                                break
+                            }
+                            if corner == INVALID && first_pass {
+                                first_pass = false;
+                                corner = mesh.table.swing_right(start_corner);
+                            }
+                        }
+                        let context = predictions.len().saturating_sub(1);
+                        let mut used = vec![];
+                        for prediction in predictions {
+                            let pos = crease_edge_pos[context];
+                            crease_edge_pos[context] += 1;
+                            let is_crease = *crease_edges[context].get(pos).ok_or("missing crease edge flags")?;
+                            if !is_crease {
+                                used.push(prediction);
+                            }
+                        }
+                        used
+                    }
+                    _ => unreachable!(),
+                };
+                let (decoded, rest) = values.split_at_mut(p * components);
+                let predicted = if predictions.is_empty() {
+                    decoded[(p - 1) * components..].to_vec()
+                } else {
+                    let count = predictions.len() as i32;
+                    (0..components)
+                        .map(|c| predictions.iter().fold(0i32, |sum, prediction| sum.wrapping_add(prediction[c])) / count)
+                        .collect()
+                };
+                transform.original(&predicted, &corrections[p * components..(p + 1) * components], &mut rest[..components]);
+            }
+        }
+        Scheme::TexCoordsPortable { orientations } => {
+            if components != 2 {
+                return Err("texture coordinates prediction needs 2 components".to_owned())
+            }
+            let positions = positions("texture coordinates")?;
+            for p in 0..num_values {
+                let corner = mesh.sequence.value_to_corner[p];
+                let predicted = predict_tex_coord(&values, p, corner, mesh, point_ids, positions, orientations)?;
+                let offset = p * 2;
+                let (_, rest) = values.split_at_mut(offset);
+                transform.original(&predicted, &corrections[offset..offset + 2], &mut rest[..2]);
+            }
+        }
+        Scheme::GeometricNormal { flips } => {
+            let octahedron = match *transform {
+                Transform::Octahedron { octahedron, .. } => octahedron,
+                _ => return Err("geometric normal prediction needs an octahedron transform".to_owned()),
+            };
+            let positions = positions("normal")?;
+            let position_of_corner = |corner: u32| {
+                let value = value_of_corner(corner);
+                point_ids.get(value as usize).map(|&point| positions.position(point)).unwrap_or([0; 3])
+            };
+            for p in 0..num_values {
+                let corner = mesh.sequence.value_to_corner[p];
+                let center = position_of_corner(corner);
+                let mut normal = [0i64; 3];
+                for c in mesh.table.vertex_corners(corner) {
+                    let pos_next = position_of_corner(next(c));
+                    let pos_prev = position_of_corner(previous(c));
+                    let a = [pos_next[0] - center[0], pos_next[1] - center[1], pos_next[2] - center[2]];
+                    let b = [pos_prev[0] - center[0], pos_prev[1] - center[1], pos_prev[2] - center[2]];
+                    let cross = [
+                        a[1].wrapping_mul(b[2]).wrapping_sub(a[2].wrapping_mul(b[1])),
+                        a[2].wrapping_mul(b[0]).wrapping_sub(a[0].wrapping_mul(b[2])),
+                        a[0].wrapping_mul(b[1]).wrapping_sub(a[1].wrapping_mul(b[0])),
+                    ];
+                    for i in 0..3 {
+                        normal[i] = normal[i].wrapping_add(cross[i]);
+                    }
+                }
+                let abs_sum = normal.iter().fold(0i64, |sum, n| sum.wrapping_add(n.wrapping_abs()));
+                const UPPER_BOUND: i64 = 1 << 29;
+                if abs_sum > UPPER_BOUND {
+                    let quotient = abs_sum / UPPER_BOUND;
+                    normal.iter_mut().for_each(|n| *n /= quotient);
+                }
+                let mut predicted = [i64::from(normal[0] as i32), i64::from(normal[1] as i32), i64::from(normal[2] as i32)];
+                octahedron.canonicalize_vector(&mut predicted);
+                if flips.read() {
+                    predicted.iter_mut().for_each(|n| *n = -*n);
+                }
+                let predicted = octahedron.vector_to_coords(predicted);
+                let offset = p * 2;
+                let (_, rest) = values.split_at_mut(offset);
+                transform.original(&predicted, &corrections[offset..offset + 2], &mut rest[..2]);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Predicts the texture coordinate from the positions and texture coordinates of the triangle
+/// (`MeshPredictionSchemeTexCoordsPortablePredictor`)
+fn predict_tex_coord(values: &[i32], data_id: usize, corner: u32, mesh: &MeshData<'_>, point_ids: &[u32],
+                     positions: &Portable, orientations: &mut Vec<bool>) -> Result<[i32; 2], String> {
+    let value_of_corner = |corner: u32| {
+        let vertex = mesh.table.vertex(corner);
+        mesh.sequence.vertex_to_value.get(vertex as usize).copied().unwrap_or(INVALID) as usize
+    };
+    let next_data_id = value_of_corner(next(corner));
+    let prev_data_id = value_of_corner(previous(corner));
+    let tex_coord = |id: usize| [i64::from(values[2 * id]), i64::from(values[2 * id + 1])];
+    let position = |id: usize| positions.position(point_ids[id]);
+
+    if prev_data_id < data_id && next_data_id < data_id {
+        let n_uv = tex_coord(next_data_id);
+        let p_uv = tex_coord(prev_data_id);
+        if p_uv == n_uv {
+            return Ok([p_uv[0] as i32, p_uv[1] as i32])
+        }
+        let tip_pos = position(data_id);
+        let next_pos = position(next_data_id);
+        let prev_pos = position(prev_data_id);
+        let pn = [prev_pos[0] - next_pos[0], prev_pos[1] - next_pos[1], prev_pos[2] - next_pos[2]];
+        let pn_norm2_squared = pn[0] * pn[0] + pn[1] * pn[1] + pn[2] * pn[2];
+        if pn_norm2_squared != 0 {
+            let cn = [tip_pos[0] - next_pos[0], tip_pos[1] - next_pos[1], tip_pos[2] - next_pos[2]];
+            let cn_dot_pn = pn[0] * cn[0] + pn[1] * cn[1] + pn[2] * cn[2];
+            let pn_uv = [p_uv[0] - n_uv[0], p_uv[1] - n_uv[1]];
+            let x_uv = [
+                n_uv[0] * pn_norm2_squared + cn_dot_pn * pn_uv[0],
+                n_uv[1] * pn_norm2_squared + cn_dot_pn * pn_uv[1],
+            ];
+            let pn_absmax_element = pn[0].abs().max(pn[1].abs()).max(pn[2].abs());
+            if cn_dot_pn > i64::MAX / pn_absmax_element {
+                return Err("texture coordinates prediction overflow".to_owned())
+            }
+            let x_pos = [
+                next_pos[0] + cn_dot_pn * pn[0] / pn_norm2_squared,
+                next_pos[1] + cn_dot_pn * pn[1] / pn_norm2_squared,
+                next_pos[2] + cn_dot_pn * pn[2] / pn_norm2_squared,
+            ];
+            let cx = [tip_pos[0] - x_pos[0], tip_pos[1] - x_pos[1], tip_pos[2] - x_pos[2]];
+            let cx_norm2_squared = (cx[0] * cx[0] + cx[1] * cx[1] + cx[2] * cx[2]) as u64;
+            let norm_squared = int_sqrt(cx_norm2_squared.wrapping_mul(pn_norm2_squared as u64)) as i64;
+            let cx_uv = [pn_uv[1] * norm_squared, -pn_uv[0] * norm_squared];
+            let orientation = orientations.pop().ok_or("missing texture coordinates orientation")?;
+            let predicted = if orientation {
+                [(x_uv[0] + cx_uv[0]) / pn_norm2_squared, (x_uv[1] + cx_uv[1]) / pn_norm2_squared]
+            } else {
+                [(x_uv[0] - cx_uv[0]) / pn_norm2_squared, (x_uv[1] - cx_uv[1]) / pn_norm2_squared]
+            };
+            return Ok([predicted[0] as i32, predicted[1] as i32])
+        }
+    }
+
+    let offset = if next_data_id < data_id {
+        next_data_id
+    } else if data_id > 0 {
+        data_id - 1
+    } else {
+        return Ok([0, 0])
+    };
+    let uv = tex_coord(offset);
+    Ok([uv[0] as i32, uv[1] as i32])
+}
+
+/// Floor of the square root
+fn int_sqrt(number: u64) -> u64 {
+    if number == 0 {
+        return 0
+    }
+    let mut act_number = number;
+    let mut square_root = 1u64;
+    while act_number >= 2 {
+        square_root *= 2;
+        act_number /= 4;
+    }
+    loop {
+        square_root = (square_root + number / square_root) / 2;
+        if square_root.checked_mul(square_root).is_some_and(|square| square <= number) {
+            return square_root
+        }
+    }
+}
+
+const ATTRIBUTE_POSITION: u8 = 0;
+
+const DECODER_GENERIC: u8 = 0;
+const DECODER_INTEGER: u8 = 1;
+const DECODER_QUANTIZATION: u8 = 2;
+const DECODER_NORMALS: u8 = 3;
+
+const DATA_TYPE_FLOAT32: u8 = 9;
+
+const MESH_VERTEX_ATTRIBUTE: u8 = 0;
+
+struct AttributeInfo {
+    attribute_type: u8,
+    data_type: u8,
+    components: usize,
+    unique_id: u32,
+    decoder_type: u8,
+}
+
+/// Size of a component of the data type (`DataTypeLength`)
+fn data_type_size(data_type: u8) -> Result<usize, String> {
+    match data_type {
+        1 | 2 | 11 => Ok(1),
+        3 | 4 => Ok(2),
+        5 | 6 | 9 => Ok(4),
+        7 | 8 | 10 => Ok(8),
+        _ => Err(format!("invalid attribute data type {}", data_type)),
+    }
+}
+
+fn decode_attributes(buffer: &mut Buffer<'_>, method: u8, connectivity: &mut Connectivity) -> Result<Vec<Attribute>, String> {
+    let num_decoders = buffer.u8()? as usize;
+    // (attribute data id, decoder type, traversal method) of each attributes decoder of edgebreaker meshes
+    let mut traversals = vec![];
+    if method == METHOD_EDGEBREAKER {
+        let edgebreaker = connectivity.edgebreaker.as_mut().unwrap();
+        for decoder_id in 0..num_decoders {
+            let data_id = buffer.u8()? as i8;
+            let decoder_type = buffer.u8()?;
+            let traversal_method = buffer.u8()?;
+            let data = if data_id >= 0 {
+                let data = edgebreaker.attribute_data.get_mut(data_id as usize).ok_or("invalid attribute data id")?;
+                data.decoder_id = Some(decoder_id);
+                if decoder_type == MESH_VERTEX_ATTRIBUTE {
+                    data.connectivity_used = false;
+                }
+                Some(data_id as usize)
+            } else {
+                None
+            };
+            if decoder_type != MESH_VERTEX_ATTRIBUTE && (data.is_none() || traversal_method != TRAVERSAL_DEPTH_FIRST) {
+                return Err("invalid attributes decoder".to_owned())
+            }
+            traversals.push((data, decoder_type, traversal_method));
+        }
+    }
+
+    let mut decoders = vec![];
+    for _ in 0..num_decoders {
+        let num_attributes = buffer.varint_usize()?;
+        if num_attributes == 0 {
+            return Err("attributes decoder without attributes".to_owned())
+        }
+        let mut infos = vec![];
+        for _ in 0..num_attributes {
+            let attribute_type = buffer.u8()?;
+            let data_type = buffer.u8()?;
+            data_type_size(data_type)?;
+            let components = buffer.u8()? as usize;
+            buffer.u8()?; // normalized
+            let unique_id = buffer.varint()?;
+            if components == 0 {
+                return Err("attribute without components".to_owned())
+            }
+            infos.push(AttributeInfo { attribute_type, data_type, components, unique_id, decoder_type: 0 });
+        }
+        for info in &mut infos {
+            info.decoder_type = buffer.u8()?;
+        }
+        decoders.push(infos);
+    }
+
+    // positions are the parent attribute of the texture coordinates and normal predictions
+    let first_position = decoders.iter().flatten().position(|info| info.attribute_type == ATTRIBUTE_POSITION);
+    let mut attributes = vec![];
+    let mut portables: Vec<Option<Portable>> = vec![];
+    for (decoder_id, infos) in decoders.iter().enumerate() {
+        let connectivity = &*connectivity;
+        let (sequence, table) = match connectivity.edgebreaker {
+            Some(ref edgebreaker) => {
+                let (data_id, decoder_type, traversal_method) = traversals[decoder_id];
+                let table = match data_id {
+                    Some(id) if decoder_type != MESH_VERTEX_ATTRIBUTE => &edgebreaker.attribute_data[id].table,
+                    _ => &edgebreaker.table,
+                };
+                (traverse(table, &connectivity.indices, traversal_method)?, Some(table))
+            }
+            None => {
+                let num_points = connectivity.num_points as u32;
+                let sequence = Sequence {
+                    point_ids: (0..num_points).collect(),
+                    value_to_corner: vec![],
+                    vertex_to_value: (0..num_points).collect(),
+                };
+                (sequence, None)
+            }
+        };
+        // value of each point
+        let point_to_value = match table {
+            Some(table) => {
+                let mut point_to_value = vec![INVALID; connectivity.num_points];
+                for (corner, &point) in connectivity.indices.iter().enumerate() {
+                    let value = sequence.vertex_to_value.get(table.vertex(corner as u32) as usize).copied().unwrap_or(INVALID);
+                    if value == INVALID {
+                        return Err("point without attribute value".to_owned())
+                    }
+                    point_to_value[point as usize] = value;
+                }
+                point_to_value
+            }
+            None => sequence.vertex_to_value.clone(),
+        };
+        let mesh = table.map(|table| MeshData { table, sequence: &sequence });
+
+        let num_entries = sequence.point_ids.len();
+        let mut stages = vec![];
+        for info in infos {
+            let values = match info.decoder_type {
+                DECODER_GENERIC => {
+                    let size = data_type_size(info.data_type)?;
+                    let len = num_entries.checked_mul(info.components * size).ok_or("too many values")?;
+                    let bytes = buffer.bytes(len)?;
+                    Some(raw_values(bytes, info.data_type)?)
+                }
+                DECODER_INTEGER | DECODER_QUANTIZATION | DECODER_NORMALS => None,
+                decoder_type => return Err(format!("unknown attribute decoder {}", decoder_type)),
+            };
+            let portable = if values.is_none() {
+                let normals = info.decoder_type == DECODER_NORMALS;
+                if info.decoder_type != DECODER_INTEGER && info.data_type != DATA_TYPE_FLOAT32 {
+                    return Err("quantized attributes need to be floats".to_owned())
+                }
+                if normals && info.components != 3 {
+                    return Err("normals need 3 components".to_owned())
+                }
+                let components = if normals { 2 } else { info.components };
+                let positions = first_position.and_then(|index| portables.get(index)).and_then(Option::as_ref);
+                let values = decode_integer_values(buffer, components, normals, mesh.as_ref(), &sequence.point_ids, positions)?;
+                Some(Portable { attribute_type: info.attribute_type, components, values, point_to_value: point_to_value.clone() })
+            } else {
+                None
+            };
+            stages.push(values);
+            portables.push(portable);
+        }
+
+        // transform data and conversion to the original format
+        let first = portables.len() - infos.len();
+        for ((info, stage), portable) in infos.iter().zip(stages).zip(&portables[first..]) {
+            let values = match (stage, portable) {
+                (Some(values), _) => values,
+                (None, Some(portable)) => match info.decoder_type {
+                    DECODER_QUANTIZATION => {
+                        let min = (0..info.components).map(|_| buffer.f32()).collect::<Result<Vec<_>, _>>()?;
+                        let range = buffer.f32()?;
+                        let bits = u32::from(buffer.u8()?);
+                        if !(1..=30).contains(&bits) {
+                            return Err(format!("invalid quantization bits {}", bits))
+                        }
+                        let delta = range / ((1u32 << bits) - 1) as f32;
+                        Values::Float(portable.values.iter().enumerate()
+                            .map(|(i, &value)| value as f32 * delta + min[i % info.components])
+                            .collect())
+                    }
+                    DECODER_NORMALS => {
+                        let octahedron = Octahedron::new(u32::from(buffer.u8()?))?;
+                        Values::Float(portable.values.chunks_exact(2)
+                            .flat_map(|st| octahedron.to_unit_vector(st[0], st[1]).to_vec())
+                            .collect())
+                    }
+                    _ if info.data_type == DATA_TYPE_FLOAT32 => {
+                        Values::Float(portable.values.iter().map(|&value| value as f32).collect())
+                    }
+                    _ => Values::Int(portable.values.clone()),
+                },
+                (None, None) => unreachable!(),
+            };
+            attributes.push(Attribute {
+                unique_id: info.unique_id,
+                components: info.components,
+                values: values.per_point(&point_to_value, info.components)?,
+            });
+        }
+    }
+    Ok(attributes)
+}
+
+impl Values {
+    /// Resolves the values of each point
+    fn per_point(&self, point_to_value: &[u32], components: usize) -> Result<Values, String> {
+        fn gather<T: Copy>(values: &[T], point_to_value: &[u32], components: usize) -> Result<Vec<T>, String> {
+            let mut result = Vec::with_capacity(point_to_value.len() * components);
+            for &value in point_to_value {
+                let start = value as usize * components;
+                result.extend_from_slice(values.get(start..start + components).ok_or("missing attribute value")?);
+            }
+            Ok(result)
+        }
+        Ok(match self {
+            Values::Float(values) => Values::Float(gather(values, point_to_value, components)?),
+            Values::Int(values) => Values::Int(gather(values, point_to_value, components)?),
+        })
+    }
+}
+
+/// Attribute values of the generic decoder in their data type
+fn raw_values(bytes: &[u8], data_type: u8) -> Result<Values, String> {
+    let size = data_type_size(data_type)?;
+    let ints = |f: fn(&[u8]) -> i32| Values::Int(bytes.chunks_exact(size).map(f).collect());
+    Ok(match data_type {
+        1 => ints(|b| i32::from(b[0] as i8)),
+        2 | 11 => ints(|b| i32::from(b[0])),
+        3 => ints(|b| i32::from(i16::from_le_bytes([b[0], b[1]]))),
+        4 => ints(|b| i32::from(u16::from_le_bytes([b[0], b[1]]))),
+        5 | 6 => ints(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        DATA_TYPE_FLOAT32 => Values::Float(bytes.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()),
+        _ => return Err(format!("unsupported attribute data type {}", data_type)),
+    })
+}
+
+/// Decodes the portable integer values of an attribute (`SequentialIntegerAttributeDecoder`)
+fn decode_integer_values(buffer: &mut Buffer<'_>, components: usize, normals: bool, mesh: Option<&MeshData<'_>>,
+                         point_ids: &[u32], positions: Option<&Portable>) -> Result<Vec<i32>, String> {
+    let method = buffer.u8()? as i8;
+    let transform_type = if method != PREDICTION_NONE { buffer.u8()? as i8 } else { -1 };
+    if !(PREDICTION_NONE..=PREDICTION_GEOMETRIC_NORMAL).contains(&method) || !(-1..=3).contains(&transform_type) {
+        return Err("invalid prediction scheme".to_owned())
+    }
+    let has_scheme = method != PREDICTION_NONE && if normals {
+        transform_type == TRANSFORM_NORMAL_OCTAHEDRON || transform_type == TRANSFORM_NORMAL_OCTAHEDRON_CANONICALIZED
+    } else {
+        transform_type == TRANSFORM_WRAP
+    };
+    // schemes without a mesh prediction for the attribute predict the difference to the previous value
+    let method = match (mesh, method) {
+        (None, _) => PREDICTION_DIFFERENCE,
+        (_, PREDICTION_GEOMETRIC_NORMAL) if normals => method,
+        (_, PREDICTION_PARALLELOGRAM) | (_, PREDICTION_MULTI_PARALLELOGRAM)
+            | (_, PREDICTION_CONSTRAINED_MULTI_PARALLELOGRAM) | (_, PREDICTION_TEX_COORDS_PORTABLE) if !normals => method,
+        (_, 3) if has_scheme => return Err("unsupported deprecated texture coordinates prediction".to_owned()),
+        _ => PREDICTION_DIFFERENCE,
+    };
+    if has_scheme && (method == PREDICTION_TEX_COORDS_PORTABLE || method == PREDICTION_GEOMETRIC_NORMAL)
+        && positions.is_none_or(|positions| positions.components != 3 || positions.attribute_type != ATTRIBUTE_POSITION) {
+        return Err("missing positions for the prediction".to_owned())
+    }
+
+    let num_values = point_ids.len().checked_mul(components).ok_or("too many values")?;
+    let mut values: Vec<i32> = if buffer.u8()? > 0 {
+        decode_symbols(buffer, num_values, components)?.into_iter().map(|value| value as i32).collect()
+    } else {
+        let num_bytes = buffer.u8()? as usize;
+        if num_bytes > 4 {
+            return Err("invalid number of bytes per value".to_owned())
+        }
+        let bytes = buffer.bytes(num_values.checked_mul(num_bytes).ok_or("too many values")?)?;
+        if num_bytes == 0 {
+            vec![0; num_values]
+        } else {
+            bytes.chunks_exact(num_bytes)
+                .map(|b| b.iter().rev().fold(0u32, |value, &byte| value << 8 | u32::from(byte)) as i32)
+                .collect()
+        }
+    };
+    // corrections of the octahedron transforms are positive
+    if num_values > 0 && !(has_scheme && normals) {
+        values.iter_mut().for_each(|value| *value = symbol_to_signed(*value as u32));
+    }
+    if has_scheme {
+        let num_corners = mesh.map_or(0, |mesh| mesh.table.corner_to_vertex.len());
+        let (mut scheme, transform) = decode_prediction_data(buffer, method, transform_type, num_corners)?;
+        if num_values > 0 {
+            values = compute_original_values(&mut scheme, &transform, &values, components, mesh, point_ids, positions)?;
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Header of a Draco 2.2 mesh without metadata
+    fn header(method: u8) -> Vec<u8> {
+        let mut data = b"DRACO".to_vec();
+        data.extend_from_slice(&[2, 2, 1, method, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn sequential_mesh() {
+        let mut data = header(METHOD_SEQUENTIAL);
+        // 1 face, 3 points, uncompressed indices
+        data.extend_from_slice(&[1, 3, 1, 0, 2, 1]);
+        // 1 decoder with UINT16 texture coordinates (unique id 5), integer decoder
+        data.extend_from_slice(&[1, 1, 3, 4, 2, 0, 5, DECODER_INTEGER]);
+        // difference prediction with wrap transform, uncompressed 1 byte corrections
+        data.extend_from_slice(&[PREDICTION_DIFFERENCE as u8, TRANSFORM_WRAP as u8, 0, 1, 2, 4, 4, 6, 5, 2]);
+        // wrap range 0..=5
+        data.extend_from_slice(&[0, 0, 0, 0, 5, 0, 0, 0]);
+
+        let mesh = decode(&data).unwrap();
+        assert_eq!(mesh.indices, [0, 2, 1]);
+        assert_eq!(mesh.attributes.len(), 1);
+        let attribute = &mesh.attributes[0];
+        assert_eq!((attribute.unique_id, attribute.components), (5, 2));
+        match attribute.values {
+            Values::Int(ref values) => assert_eq!(values, &[1, 2, 3, 5, 0, 0]),
+            _ => panic!("expected integer values"),
+        }
+        assert!(decode(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn edgebreaker_triangle() {
+        let mut data = header(METHOD_EDGEBREAKER);
+        data.extend_from_slice(&[
+            TRAVERSAL_STANDARD,
+            3, 1, 0, 1, 0, // vertices, faces, attribute data, symbols, split symbols
+            5, // size of the traversal data
+            1, 0b111, // TOPOLOGY_E
+            255, 1, 1, // start face on a boundary
+            0, // no topology splits
+        ]);
+        // positions (generic decoder) in traversal order: corners 1, 2, 0
+        data.extend_from_slice(&[1, 0xff, MESH_VERTEX_ATTRIBUTE, TRAVERSAL_DEPTH_FIRST, 1, 0, 9, 3, 0, 0, DECODER_GENERIC]);
+        for value in &[1.0f32, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mesh = decode(&data).unwrap();
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        match mesh.attributes[0].values {
+            Values::Float(ref values) => assert_eq!(values, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]),
+            _ => panic!("expected float values"),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! EGL context without any surface for headless rendering on Linux.
+//! Works without a display server, e.g. with Mesa's llvmpipe in CI containers.
+use std::os::raw::c_void;
+use std::ptr;
+
+use khronos_egl as egl;
+use log::debug;
+
+/// From `EGL_MESA_platform_surfaceless` (not part of `khronos_egl`)
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+pub struct EglContext {
+    egl: egl::DynamicInstance<egl::EGL1_4>,
+    display: egl::Display,
+    context: egl::Context,
+}
+
+impl EglContext {
+    /// Creates an OpenGL 3.3 core context and makes it current.
+    /// Rendering needs to go to a framebuffer object since there is no default framebuffer.
+    pub fn new() -> Result<EglContext, String> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_4>::load_required() }
+            .map_err(|err| format!("Failed to load libEGL: {}", err))?;
+
+        let display = Self::get_display(&egl)?;
+        let (major, minor) = egl.initialize(display)
+            .map_err(|err| format!("eglInitialize failed: {}", err))?;
+        debug!("EGL version  : {}.{}", major, minor);
+
+        let extensions = egl.query_string(Some(display), egl::EXTENSIONS)
+            .map_err(|err| format!("Failed to query EGL extensions: {}", err))?
+            .to_string_lossy();
+        if !extensions.split(' ').any(|ext| ext == "EGL_KHR_surfaceless_context") {
+            return Err("EGL_KHR_surfaceless_context is not supported by the EGL implementation".into())
+        }
+
+        egl.bind_api(egl::OPENGL_API)
+            .map_err(|err| format!("Failed to bind OpenGL API: {}", err))?;
+        // NOTE: SURFACE_TYPE defaults to WINDOW_BIT, which surfaceless displays don't offer
+        let config_attributes = [
+            egl::SURFACE_TYPE, egl::PBUFFER_BIT,
+            egl::RENDERABLE_TYPE, egl::OPENGL_BIT,
+            egl::NONE
+        ];
+        let config = egl.choose_first_config(display, &config_attributes)
+            .map_err(|err| format!("eglChooseConfig failed: {}", err))?
+            .ok_or_else(|| "No EGL config supporting OpenGL found".to_string())?;
+
+        let context_attributes = [
+            egl::CONTEXT_MAJOR_VERSION, 3,
+            egl::CONTEXT_MINOR_VERSION, 3,
+            egl::CONTEXT_OPENGL_PROFILE_MASK, egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            egl::NONE
+        ];
+        let context = egl.create_context(display, config, None, &context_attributes)
+            .map_err(|err| format!("Failed to create OpenGL 3.3 core context: {}", err))?;
+        egl.make_current(display, None, None, Some(context))
+            .map_err(|err| format!("eglMakeCurrent failed: {}", err))?;
+
+        Ok(EglContext { egl, display, context })
+    }
+
+    /// Prefers Mesa's surfaceless platform, so no connection to X11/Wayland is attempted
+    fn get_display(egl: &egl::DynamicInstance<egl::EGL1_4>) -> Result<egl::Display, String> {
+        // NOTE: querying client extensions fails if EGL_EXT_client_extensions is not supported
+        let client_extensions = egl.query_string(None, egl::EXTENSIONS)
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if client_extensions.split(' ').any(|ext| ext == "EGL_MESA_platform_surfaceless") {
+            if let Some(egl) = egl.upcast::<egl::EGL1_5>() {
+                debug!("Using EGL_MESA_platform_surfaceless");
+                return egl.get_platform_display(PLATFORM_SURFACELESS_MESA, ptr::null_mut(), &[egl::ATTRIB_NONE])
+                    .map_err(|err| format!("eglGetPlatformDisplay failed: {}", err))
+            }
+        }
+
+        egl.get_display(egl::DEFAULT_DISPLAY)
+            .ok_or_else(|| "No EGL display available".to_string())
+    }
+
+    pub fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        self.egl.get_proc_address(symbol)
+            .map_or(ptr::null(), |f| f as *const c_void)
+    }
+}
+
+impl Drop for EglContext {
+    fn drop(&mut self) {
+        let _ = self.egl.make_current(self.display, None, None, None);
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.terminate(self.display);
+    }
+}
@@ -4,26 +4,36 @@ use gl;
 
 #[derive(Debug)]
 pub struct Framebuffer {
-    pub id: u32
+    pub id: u32,
+    pub color_texture: u32,
+    /// Textures of `COLOR_ATTACHMENT1..n`
+    pub extra_color_textures: Vec<u32>,
+    depth_stencil_buffer: u32,
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32) -> Framebuffer {
+        Self::with_format(width, height, gl::RGBA8, gl::UNSIGNED_BYTE)
+    }
+
+    /// Creates a framebuffer with the given internal format of the color attachment
+    /// (e.g. `gl::RGBA16F` with `gl::FLOAT` for HDR rendering)
+    pub fn with_format(width: u32, height: u32, internal_format: u32, type_: u32) -> Framebuffer {
         let mut framebuffer = 0;
+        let mut texture_colorbuffer = 0;
+        let mut rbo = 0;
         unsafe {
             gl::GenFramebuffers(1, &mut framebuffer);
             gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
             // create a color attachment texture
-            let mut texture_colorbuffer = 0;
             gl::GenTextures(1, &mut texture_colorbuffer);
             gl::BindTexture(gl::TEXTURE_2D, texture_colorbuffer);
-            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, width as i32, height as i32,
-                0, gl::RGBA, gl::UNSIGNED_BYTE, ptr::null());
+            gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as i32, width as i32, height as i32,
+                0, gl::RGBA, type_, ptr::null());
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
             gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture_colorbuffer, 0);
             // create a renderbuffer object for depth and stencil attachment (we won't be sampling these)
-            let mut rbo = 0;
             gl::GenRenderbuffers(1, &mut rbo);
             gl::BindRenderbuffer(gl::RENDERBUFFER, rbo);
             gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width as i32, height as i32); // use a single renderbuffer object for both a depth AND stencil buffer.
@@ -36,10 +46,37 @@ impl Framebuffer {
         }
 
         Framebuffer {
-            id: framebuffer
+            id: framebuffer,
+            color_texture: texture_colorbuffer,
+            extra_color_textures: Vec::new(),
+            depth_stencil_buffer: rbo,
         }
     }
 
+    /// Adds a color attachment texture (`COLOR_ATTACHMENT1` and up) and draws into all attachments
+    pub fn add_color_attachment(&mut self, width: u32, height: u32, internal_format: u32, format: u32, type_: u32) -> u32 {
+        let mut texture = 0;
+        let attachment = gl::COLOR_ATTACHMENT1 + self.extra_color_textures.len() as u32;
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as i32, width as i32, height as i32,
+                0, format, type_, ptr::null());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0);
+            let draw_buffers: Vec<_> = (gl::COLOR_ATTACHMENT0..=attachment).collect();
+            gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                panic!("ERROR::FRAMEBUFFER:: Framebuffer is not complete!");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        self.extra_color_textures.push(texture);
+        texture
+    }
+
     pub fn bind(&self) {
         unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, self.id) }
     }
@@ -49,3 +86,14 @@ impl Framebuffer {
         unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) }
     }
 }
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+            gl::DeleteTextures(1, &self.color_texture);
+            gl::DeleteTextures(self.extra_color_textures.len() as i32, self.extra_color_textures.as_ptr());
+            gl::DeleteRenderbuffers(1, &self.depth_stencil_buffer);
+        }
+    }
+}
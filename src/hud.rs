@@ -0,0 +1,284 @@
+//! On-screen statistics overlay (toggled with F1), rendered with a built-in 5x7 bitmap font.
+use std::mem::size_of;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::shader::Shader;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// Size of a character cell in the font texture (glyph + 1 pixel spacing)
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: usize = GLYPH_HEIGHT + 1;
+
+const FIRST_CHAR: u8 = b' ';
+const LAST_CHAR: u8 = b'~';
+/// Index of a filled cell after the printable characters (used for the background)
+const BLOCK_GLYPH: usize = (LAST_CHAR - FIRST_CHAR + 1) as usize;
+const NUM_GLYPHS: usize = BLOCK_GLYPH + 1;
+
+/// 5x7 glyphs for ASCII 32-126; one byte per row, bit 4 is the leftmost pixel
+#[rustfmt::skip]
+const FONT: [[u8; GLYPH_HEIGHT]; BLOCK_GLYPH] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // !
+    [0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A], // #
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04], // $
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // %
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D], // &
+    [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // '
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // (
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // )
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00], // *
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ,
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // .
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // /
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // 0
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // 1
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // 2
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // 3
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // 4
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // 5
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // 6
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // 7
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // 8
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08], // ;
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // <
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // =
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // >
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // ?
+    [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E], // @
+    [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11], // A
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // B
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // C
+    [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C], // D
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // E
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // F
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // G
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // H
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // I
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // J
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // K
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // L
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // M
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // N
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // O
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // P
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // Q
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // R
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // S
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // T
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // U
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // V
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // W
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // X
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04], // Y
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // Z
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // [
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // \
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // ]
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // _
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F], // a
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E], // b
+    [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E], // c
+    [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F], // d
+    [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E], // e
+    [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08], // f
+    [0x00, 0x0F, 0x11, 0x11, 0x0F, 0x01, 0x0E], // g
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // h
+    [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E], // i
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0C], // j
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // k
+    [0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // l
+    [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11], // m
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // n
+    [0x00, 0x00, 0x0E, 0x11, 0x11, 0x11, 0x0E], // o
+    [0x00, 0x00, 0x1E, 0x11, 0x1E, 0x10, 0x10], // p
+    [0x00, 0x00, 0x0D, 0x13, 0x0F, 0x01, 0x01], // q
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // r
+    [0x00, 0x00, 0x0E, 0x10, 0x0E, 0x01, 0x1E], // s
+    [0x08, 0x08, 0x1C, 0x08, 0x08, 0x09, 0x06], // t
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0D], // u
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0A, 0x04], // v
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0A], // w
+    [0x00, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11], // x
+    [0x00, 0x00, 0x11, 0x11, 0x0F, 0x01, 0x0E], // y
+    [0x00, 0x00, 0x1F, 0x02, 0x04, 0x08, 0x1F], // z
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // {
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // |
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // }
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // ~
+];
+
+/// Margin around the text in font pixels
+const PADDING: f32 = 2.0;
+
+pub struct Hud {
+    shader: Shader,
+    u_screen_size: i32,
+    u_color: i32,
+    font_texture: u32,
+    vao: u32,
+    vbo: u32,
+    /// Size of a font pixel in screen pixels
+    pub scale: f32,
+}
+
+impl Hud {
+    pub fn new(scale: f32) -> Hud {
+        let mut shader = Shader::from_source(
+            include_str!("shaders/hud-vert.glsl"),
+            include_str!("shaders/hud-frag.glsl"),
+            &[]);
+        let (u_screen_size, u_color) = unsafe {
+            let u_font = shader.uniform_location("u_Font");
+            shader.use_program();
+            shader.set_int(u_font, 0);
+            (shader.uniform_location("u_ScreenSize"), shader.uniform_location("u_Color"))
+        };
+
+        let mut hud = Hud {
+            shader,
+            u_screen_size,
+            u_color,
+            font_texture: 0,
+            vao: 0,
+            vbo: 0,
+            scale,
+        };
+        unsafe {
+            hud.create_font_texture();
+            hud.setup_buffers();
+        }
+        hud
+    }
+
+    /// Creates a single-row atlas with one cell per glyph (1 byte per pixel)
+    unsafe fn create_font_texture(&mut self) {
+        let width = NUM_GLYPHS * CELL_WIDTH;
+        let mut pixels = vec![0u8; width * CELL_HEIGHT];
+        for (i, glyph) in FONT.iter().enumerate() {
+            for (y, row) in glyph.iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if row & (0x10 >> x) != 0 {
+                        pixels[y * width + i * CELL_WIDTH + x] = 255;
+                    }
+                }
+            }
+        }
+        for y in 0..CELL_HEIGHT {
+            for x in 0..CELL_WIDTH {
+                pixels[y * width + BLOCK_GLYPH * CELL_WIDTH + x] = 255;
+            }
+        }
+
+        gl::GenTextures(1, &mut self.font_texture);
+        gl::BindTexture(gl::TEXTURE_2D, self.font_texture);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R8 as i32, width as i32, CELL_HEIGHT as i32,
+            0, gl::RED, gl::UNSIGNED_BYTE, pixels.as_ptr() as *const c_void);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+    }
+
+    unsafe fn setup_buffers(&mut self) {
+        gl::GenVertexArrays(1, &mut self.vao);
+        gl::GenBuffers(1, &mut self.vbo);
+        gl::BindVertexArray(self.vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+        let stride = 4 * size_of::<f32>() as i32;
+        // position (pixels)
+        gl::EnableVertexAttribArray(0);
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+        // uv
+        gl::EnableVertexAttribArray(1);
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (2 * size_of::<f32>()) as *const c_void);
+        gl::BindVertexArray(0);
+    }
+
+    /// Draws the lines of text in the top left corner on a translucent background.
+    /// Characters without a glyph are drawn as `?`.
+    pub unsafe fn draw(&self, lines: &[String], screen_width: f32, screen_height: f32) {
+        let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let mut vertices: Vec<f32> = Vec::with_capacity((columns * lines.len() + 1) * 24);
+
+        // background
+        let px = self.scale;
+        let box_width = (columns * CELL_WIDTH) as f32 + PADDING * 2.0 - 1.0;
+        let box_height = (lines.len() * CELL_HEIGHT) as f32 + PADDING * 2.0 - 1.0;
+        push_quad(&mut vertices, 0.0, 0.0, box_width * px, box_height * px, BLOCK_GLYPH);
+
+        for (row, line) in lines.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let glyph = match c as u32 {
+                    c @ 33..=126 => (c - u32::from(FIRST_CHAR)) as usize,
+                    32 => continue,
+                    _ => (b'?' - FIRST_CHAR) as usize,
+                };
+                let x = (PADDING + (column * CELL_WIDTH) as f32) * px;
+                let y = (PADDING + (row * CELL_HEIGHT) as f32) * px;
+                push_quad(&mut vertices, x, y, CELL_WIDTH as f32 * px, CELL_HEIGHT as f32 * px, glyph);
+            }
+        }
+
+        gl::Disable(gl::DEPTH_TEST);
+        gl::Disable(gl::CULL_FACE);
+        gl::Enable(gl::BLEND);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+        self.shader.use_program();
+        self.shader.set_vec2(self.u_screen_size, screen_width, screen_height);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.font_texture);
+
+        gl::BindVertexArray(self.vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+        gl::BufferData(gl::ARRAY_BUFFER, (vertices.len() * size_of::<f32>()) as isize,
+            vertices.as_ptr() as *const c_void, gl::STREAM_DRAW);
+
+        self.shader.set_vector4(self.u_color, &[0.0, 0.0, 0.0, 0.6].into());
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        self.shader.set_vector4(self.u_color, &[1.0, 1.0, 1.0, 1.0].into());
+        gl::DrawArrays(gl::TRIANGLES, 6, (vertices.len() / 4 - 6) as i32);
+
+        gl::BindVertexArray(0);
+        gl::Disable(gl::BLEND);
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+/// Appends two triangles (position + uv) showing the given glyph cell
+fn push_quad(vertices: &mut Vec<f32>, x: f32, y: f32, width: f32, height: f32, glyph: usize) {
+    let u0 = (glyph * CELL_WIDTH) as f32 / (NUM_GLYPHS * CELL_WIDTH) as f32;
+    let u1 = ((glyph + 1) * CELL_WIDTH) as f32 / (NUM_GLYPHS * CELL_WIDTH) as f32;
+    let (x1, y1) = (x + width, y + height);
+    vertices.extend_from_slice(&[
+        x, y, u0, 0.0,
+        x, y1, u0, 1.0,
+        x1, y1, u1, 1.0,
+        x, y, u0, 0.0,
+        x1, y1, u1, 1.0,
+        x1, y, u1, 0.0,
+    ]);
+}
+
+impl Drop for Hud {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteTextures(1, &self.font_texture);
+            gl::DeleteProgram(self.shader.id);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Image comparison for render diffs (`gltf-viewer diff`), also used by the golden image tests.
+use std::fmt;
+
+use clap::ArgMatches;
+use image::{Rgba, RgbaImage};
+
+/// Exit code if the images differ more than the threshold allows
+pub const EXIT_ABOVE_THRESHOLD: i32 = 1;
+/// Exit code if the images can't be compared at all (I/O error, size mismatch, empty images)
+pub const EXIT_ERROR: i32 = 2;
+
+pub struct DiffStats {
+    /// Root-mean-square error over all RGBA channels (0-255)
+    pub rmse: f64,
+    /// Peak signal-to-noise ratio in dB (infinite for identical images)
+    pub psnr: f64,
+    pub max_channel_error: u8,
+    pub differing_pixels: u32,
+    pub total_pixels: u32,
+}
+
+impl DiffStats {
+    pub fn differing_percentage(&self) -> f64 {
+        f64::from(self.differing_pixels) * 100.0 / f64::from(self.total_pixels)
+    }
+}
+
+impl fmt::Display for DiffStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "RMSE:              {:.4}", self.rmse)?;
+        writeln!(f, "PSNR:              {:.2} dB", self.psnr)?;
+        writeln!(f, "Max channel error: {}", self.max_channel_error)?;
+        write!(f, "Differing pixels:  {:.3}% ({} of {})",
+            self.differing_percentage(), self.differing_pixels, self.total_pixels)
+    }
+}
+
+/// Compares two images of the same size.
+/// Pixels count as differing if their perceptual color difference exceeds `pixel_threshold` (0..1).
+/// Returns the statistics and a diff image with differing pixels in red on top of a
+/// faded grayscale version of `a`.
+/// Fails if the sizes differ or the images are empty.
+pub fn compare(a: &RgbaImage, b: &RgbaImage, pixel_threshold: f64) -> Result<(DiffStats, RgbaImage), String> {
+    if a.dimensions() != b.dimensions() {
+        return Err(format!("Image sizes differ: {:?} vs. {:?}", a.dimensions(), b.dimensions()))
+    }
+    if a.width() == 0 || a.height() == 0 {
+        return Err(format!("Images are empty: {:?}", a.dimensions()))
+    }
+
+    let mut squared_error_sum = 0.0;
+    let mut max_channel_error = 0;
+    let mut differing_pixels = 0;
+    let mut diff = RgbaImage::new(a.width(), a.height());
+    for (x, y, pixel_a) in a.enumerate_pixels() {
+        let pixel_b = b.get_pixel(x, y);
+        for (&ca, &cb) in pixel_a.data.iter().zip(pixel_b.data.iter()) {
+            let error = ca.abs_diff(cb);
+            squared_error_sum += f64::from(error) * f64::from(error);
+            max_channel_error = max_channel_error.max(error);
+        }
+
+        if color_delta(pixel_a, pixel_b) > pixel_threshold {
+            differing_pixels += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            let [luma, _, _] = yiq(pixel_a);
+            let faded = (255.0 - 0.1 * (255.0 - luma)) as u8;
+            diff.put_pixel(x, y, Rgba([faded, faded, faded, 255]));
+        }
+    }
+
+    let total_pixels = a.width() * a.height();
+    let rmse = (squared_error_sum / (f64::from(total_pixels) * 4.0)).sqrt();
+    let psnr = 20.0 * (255.0 / rmse).log10();
+    let stats = DiffStats {
+        rmse,
+        psnr,
+        max_channel_error,
+        differing_pixels,
+        total_pixels,
+    };
+    Ok((stats, diff))
+}
+
+/// Perceptual color difference (0..1) in YIQ space, after blending both pixels with white
+/// (inspired by `pixelmatch`)
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f64 {
+    // maximum possible value of the weighted YIQ difference
+    const MAX_DELTA: f64 = 35215.0;
+    let [y1, i1, q1] = yiq(a);
+    let [y2, i2, q2] = yiq(b);
+    let (dy, di, dq) = (y1 - y2, i1 - i2, q1 - q2);
+    (0.5053 * dy * dy + 0.299 * di * di + 0.1957 * dq * dq) / MAX_DELTA
+}
+
+fn yiq(pixel: &Rgba<u8>) -> [f64; 3] {
+    let alpha = f64::from(pixel[3]) / 255.0;
+    let blend = |c: u8| 255.0 + (f64::from(c) - 255.0) * alpha;
+    let (r, g, b) = (blend(pixel[0]), blend(pixel[1]), blend(pixel[2]));
+    [
+        r * 0.298_895_31 + g * 0.586_622_47 + b * 0.114_482_23,
+        r * 0.595_977_99 - g * 0.274_176_10 - b * 0.321_801_89,
+        r * 0.211_470_17 - g * 0.522_617_61 + b * 0.311_147_44,
+    ]
+}
+
+/// Runs the `diff` subcommand and returns the exit code
+pub fn run(args: &ArgMatches<'_>) -> i32 {
+    let open = |path: &str| image::open(path)
+        .map(|img| img.to_rgba())
+        .map_err(|err| eprintln!("Failed to open {}: {}", path, err));
+    let (a, b) = match (open(args.value_of("A").unwrap()), open(args.value_of("B").unwrap())) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return EXIT_ERROR,
+    };
+    let pixel_threshold: f64 = args.value_of("PIXEL-THRESHOLD").unwrap().parse().unwrap();
+    let threshold: f64 = args.value_of("THRESHOLD").unwrap().parse().unwrap();
+
+    let (stats, diff) = match compare(&a, &b, pixel_threshold) {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("{}", err);
+            return EXIT_ERROR
+        }
+    };
+    println!("{}", stats);
+
+    if let Some(out) = args.value_of("OUT") {
+        if let Err(err) = diff.save(out) {
+            eprintln!("Failed to save {}: {}", out, err);
+            return EXIT_ERROR
+        }
+    }
+
+    if stats.differing_percentage() > threshold {
+        println!("FAILED: more than {}% of pixels differ", threshold);
+        return EXIT_ABOVE_THRESHOLD
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_images() {
+        let img = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let (stats, _) = compare(&img, &img, 0.1).unwrap();
+        assert_eq!(stats.rmse, 0.0);
+        assert!(stats.psnr.is_infinite());
+        assert_eq!(stats.max_channel_error, 0);
+        assert_eq!(stats.differing_pixels, 0);
+    }
+
+    #[test]
+    fn differing_pixel() {
+        let a = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 255]));
+        let mut b = a.clone();
+        b.put_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        // barely visible change
+        b.put_pixel(0, 1, Rgba([2, 2, 2, 255]));
+
+        let (stats, diff) = compare(&a, &b, 0.1).unwrap();
+        assert_eq!(stats.max_channel_error, 255);
+        assert_eq!(stats.differing_pixels, 1);
+        assert_eq!(stats.differing_percentage(), 25.0);
+        // 3 channels of 1 pixel maxed out, 3 channels of another differ by 2
+        let expected_rmse = ((3.0 * 255.0 * 255.0 + 3.0 * 4.0) / 16.0f64).sqrt();
+        assert!((stats.rmse - expected_rmse).abs() < 1e-9);
+        assert_eq!(*diff.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+        assert_ne!(*diff.get_pixel(0, 1), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn empty_images() {
+        let img = RgbaImage::new(0, 0);
+        assert!(compare(&img, &img, 0.1).is_err());
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let a = RgbaImage::new(2, 2);
+        let b = RgbaImage::new(2, 3);
+        assert!(compare(&a, &b, 0.1).is_err());
+    }
+}
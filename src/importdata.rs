@@ -1,9 +1,217 @@
-use gltf;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use gltf::buffer::Source;
+use serde_json::Value;
+
+use crate::meshopt;
 
 /// Helps to simplify the signature of import related functions.
 pub struct ImportData {
     pub doc: gltf::Document,
     pub buffers: Vec<gltf::buffer::Data>,
-    pub images: Vec<gltf::image::Data>,
+    /// The raw JSON, for extensions not supported by the `gltf` crate
+    pub json: Value,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Gltf(gltf::Error),
+    /// Invalid data URI of a buffer
+    Base64(base64::DecodeError),
+    /// Decoding an `EXT_meshopt_compression` buffer view failed
+    Meshopt { view: usize, error: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Gltf(err) => write!(f, "{:?}", err),
+            ImportError::Base64(err) => write!(f, "invalid data URI: {}", err),
+            ImportError::Meshopt { view, error } =>
+                write!(f, "failed to decode compressed buffer view {}: {}", view, error),
+        }
+    }
+}
+
+impl From<gltf::Error> for ImportError {
+    fn from(err: gltf::Error) -> Self {
+        ImportError::Gltf(err)
+    }
+}
+
+impl ImportData {
+    /// Imports the document and buffers. Images are loaded by `Texture::from_gltf`.
+    /// Buffer views compressed with `EXT_meshopt_compression` are decoded into their (fallback) buffers.
+    pub fn import(path: &Path) -> Result<ImportData, ImportError> {
+        let data = fs::read(path).map_err(gltf::Error::Io)?;
+        // the raw JSON is kept, since the `gltf` crate ignores unknown extensions
+        let (mut json, mut blob) = parse(&data)?;
+        add_texture_sources(&mut json);
+        let root: gltf::json::Root = gltf::json::deserialize::from_value(json.clone()).map_err(gltf::Error::Deserialize)?;
+        let doc = match gltf::Document::from_json(root.clone()) {
+            // accessors of primitives compressed with `KHR_draco_mesh_compression` may omit the buffer view
+            Err(gltf::Error::Validation(ref errors)) if only_draco_views_missing(&json, errors) =>
+                gltf::Document::from_json_without_validation(root),
+            result => result?,
+        };
+
+        let base = path.parent().unwrap_or_else(|| Path::new("./"));
+        let mut buffers = Vec::new();
+        for buffer in doc.buffers() {
+            let extension = &json["buffers"][buffer.index()]["extensions"]["EXT_meshopt_compression"];
+            let mut data = match buffer.source() {
+                // filled with the decoded buffer views below
+                _ if extension["fallback"] == true => vec![0; buffer.length()],
+                Source::Uri(uri) => read_uri(base, uri)?,
+                Source::Bin => blob.take().ok_or(gltf::Error::MissingBlob)?,
+            };
+            if data.len() < buffer.length() {
+                return Err(gltf::Error::BufferLength {
+                    buffer: buffer.index(),
+                    expected: buffer.length(),
+                    actual: data.len(),
+                }.into())
+            }
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            buffers.push(gltf::buffer::Data(data));
+        }
+
+        for view in doc.views() {
+            let extension = &json["bufferViews"][view.index()]["extensions"]["EXT_meshopt_compression"];
+            if extension.is_null() {
+                continue
+            }
+            let decoded = decode_meshopt(extension, view.length(), &buffers)
+                .map_err(|error| ImportError::Meshopt { view: view.index(), error })?;
+            let begin = view.offset();
+            let target = buffers[view.buffer().index()].0.get_mut(begin..begin + decoded.len())
+                .ok_or_else(|| ImportError::Meshopt { view: view.index(), error: "decoded data exceeds buffer".to_owned() })?;
+            target.copy_from_slice(&decoded);
+        }
+
+        Ok(ImportData { doc, buffers, json })
+    }
+
+    /// Names of the `KHR_materials_variants` variants (by variant index)
+    pub fn variant_names(&self) -> Vec<String> {
+        self.json["extensions"]["KHR_materials_variants"]["variants"].as_array()
+            .into_iter().flatten()
+            .enumerate()
+            .map(|(i, variant)| variant["name"].as_str().map_or_else(|| format!("variant {}", i), |name| name.to_owned()))
+            .collect()
+    }
 }
 
+/// Reads the raw JSON of a glTF or GLB file, without validating it
+pub fn read_json(path: &Path) -> Result<Value, gltf::Error> {
+    let data = fs::read(path).map_err(gltf::Error::Io)?;
+    Ok(parse(&data)?.0)
+}
+
+/// Splits the file into the raw JSON and the binary chunk (GLB only)
+fn parse(data: &[u8]) -> Result<(Value, Option<Vec<u8>>), gltf::Error> {
+    let (json, blob) = if data.starts_with(b"glTF") {
+        let glb = gltf::Glb::from_slice(data)?;
+        (serde_json::from_slice(&glb.json), glb.bin.map(|bin| bin.into_owned()))
+    } else {
+        (serde_json::from_slice(data), None)
+    };
+    Ok((json.map_err(gltf::Error::Deserialize)?, blob))
+}
+
+/// Textures may only have an image source in an extension if it is required
+/// (e.g. `KHR_texture_basisu` or `EXT_texture_webp`), but the `gltf` crate needs a source.
+fn add_texture_sources(json: &mut Value) {
+    let textures = match json.get_mut("textures").and_then(Value::as_array_mut) {
+        Some(textures) => textures,
+        None => return,
+    };
+    for texture in textures {
+        if !texture["source"].is_null() {
+            continue
+        }
+        let source = ["KHR_texture_basisu", "EXT_texture_webp"].iter()
+            .map(|extension| &texture["extensions"][extension]["source"])
+            .find(|source| !source.is_null())
+            .cloned();
+        if let Some(source) = source {
+            texture["source"] = source;
+        }
+    }
+}
+
+/// Whether all validation errors are missing buffer views of accessors used by Draco compressed primitives
+fn only_draco_views_missing(json: &Value, errors: &[(gltf::json::Path, gltf::json::validation::Error)]) -> bool {
+    let mut accessors = vec![];
+    for mesh in json["meshes"].as_array().into_iter().flatten() {
+        for primitive in mesh["primitives"].as_array().into_iter().flatten() {
+            let extension = &primitive["extensions"]["KHR_draco_mesh_compression"];
+            if extension.is_object() {
+                accessors.extend(primitive["attributes"].as_object().into_iter().flatten().filter_map(|(_, a)| a.as_u64()));
+                accessors.extend(primitive["indices"].as_u64());
+            }
+        }
+    }
+    errors.iter().all(|(path, error)| {
+        *error == gltf::json::validation::Error::Missing
+            && accessors.iter().any(|a| path.as_str() == format!("accessors[{}].bufferView", a))
+    })
+}
+
+/// Reads a buffer or image URI: a data URI, a `file:` URI or a path relative to `base`
+pub fn read_uri(base: &Path, uri: &str) -> Result<Vec<u8>, ImportError> {
+    if uri.starts_with("data:") {
+        let encoded = uri.split(',').nth(1).unwrap_or("");
+        base64::decode(&encoded).map_err(ImportError::Base64)
+    } else if let Some(path) = uri.strip_prefix("file:") {
+        // NOTE: like the `gltf` crate, no authority support
+        let path = path.strip_prefix("//").unwrap_or(path);
+        Ok(fs::read(path).map_err(gltf::Error::Io)?)
+    } else if uri.contains(':') {
+        Err(gltf::Error::UnsupportedScheme.into())
+    } else {
+        Ok(fs::read(base.join(uri)).map_err(gltf::Error::Io)?)
+    }
+}
+
+fn decode_meshopt(extension: &Value, view_length: usize, buffers: &[gltf::buffer::Data]) -> Result<Vec<u8>, String> {
+    let number = |name: &str| extension[name].as_u64().map(|n| n as usize);
+    let source_buffer = number("buffer").and_then(|index| buffers.get(index))
+        .ok_or("invalid buffer")?;
+    let offset = number("byteOffset").unwrap_or(0);
+    let length = number("byteLength").ok_or("missing byteLength")?;
+    let source = source_buffer.0.get(offset..offset + length)
+        .ok_or("byteOffset/byteLength exceed the buffer")?;
+    let stride = number("byteStride").ok_or("missing byteStride")?;
+    let count = number("count").ok_or("missing count")?;
+    if count * stride != view_length {
+        return Err(format!("count * byteStride ({}) differs from the byteLength of the buffer view ({})",
+            count * stride, view_length))
+    }
+    let mode = extension["mode"].as_str().unwrap_or("").parse()?;
+    let filter = extension["filter"].as_str().unwrap_or("NONE").parse()?;
+    meshopt::decode(source, count, stride, mode, filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_schemes() {
+        let base = Path::new("src/data");
+        let data = read_uri(base, "Box0.bin").unwrap();
+        let absolute = fs::canonicalize("src/data/Box0.bin").unwrap();
+        assert_eq!(read_uri(base, &format!("file://{}", absolute.display())).unwrap(), data);
+        assert_eq!(read_uri(base, &format!("file:{}", absolute.display())).unwrap(), data);
+        assert_eq!(read_uri(base, "data:application/octet-stream;base64,AQID").unwrap(), [1, 2, 3]);
+        match read_uri(base, "http://example.com/Box0.bin") {
+            Err(ImportError::Gltf(gltf::Error::UnsupportedScheme)) => {},
+            _ => panic!("expected UnsupportedScheme"),
+        }
+    }
+}
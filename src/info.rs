@@ -0,0 +1,436 @@
+//! Model inspection report (`--info`): facts about a glTF file without creating a window or OpenGL context.
+use std::fmt;
+use std::fmt::Write;
+use std::path::Path;
+
+use gltf::texture;
+use serde::Serialize;
+
+use crate::importdata::ImportData;
+use crate::render::{Camera, Scene};
+use crate::render::math::*;
+use crate::viewer::check_required_extensions;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    pub version: String,
+    pub generator: Option<String>,
+    pub extensions_used: Vec<String>,
+    pub extensions_required: Vec<String>,
+    pub scenes: Vec<SceneInfo>,
+    pub meshes: Vec<MeshInfo>,
+    pub materials: Vec<MaterialInfo>,
+    pub cameras: Vec<CameraInfo>,
+    pub animations: Vec<AnimationInfo>,
+    pub skins: Vec<SkinInfo>,
+    /// `KHR_materials_variants`
+    pub variants: Vec<VariantInfo>,
+}
+
+#[derive(Serialize)]
+pub struct VariantInfo {
+    pub index: usize,
+    pub name: String,
+    /// Materials of the variant's mappings
+    pub materials: Vec<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SceneInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    /// Same as `Scene::bounds` of the loaded scene (`None` if there are no positions)
+    pub bounds: Option<BoundsInfo>,
+    pub nodes: Vec<NodeInfo>,
+}
+
+#[derive(Serialize)]
+pub struct BoundsInfo {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+#[derive(Serialize)]
+pub struct NodeInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub translation: [f32; 3],
+    /// Quaternion (x, y, z, w)
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+    pub skin: Option<usize>,
+    pub children: Vec<NodeInfo>,
+}
+
+#[derive(Serialize)]
+pub struct MeshInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub primitives: Vec<PrimitiveInfo>,
+}
+
+#[derive(Serialize)]
+pub struct PrimitiveInfo {
+    pub mode: String,
+    pub vertex_count: usize,
+    pub index_count: Option<usize>,
+    pub attributes: Vec<String>,
+    pub material: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct MaterialInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub alpha_mode: String,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+    pub textures: Vec<TextureRefInfo>,
+}
+
+#[derive(Serialize)]
+pub struct TextureRefInfo {
+    pub slot: &'static str,
+    pub texture: usize,
+    pub tex_coord: u32,
+}
+
+#[derive(Serialize)]
+pub struct CameraInfo {
+    pub index: usize,
+    pub description: String,
+}
+
+#[derive(Serialize)]
+pub struct AnimationInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub channels: Vec<ChannelInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ChannelInfo {
+    pub node: usize,
+    pub property: String,
+    pub interpolation: String,
+    pub keyframes: usize,
+}
+
+#[derive(Serialize)]
+pub struct SkinInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub joints: Vec<usize>,
+    pub skeleton: Option<usize>,
+}
+
+impl Info {
+    pub fn from_gltf(imp: &ImportData) -> Info {
+        let doc = &imp.doc;
+        let asset = doc.clone().into_json().asset;
+        Info {
+            version: asset.version,
+            generator: asset.generator,
+            extensions_used: doc.extensions_used().map(|ext| ext.to_owned()).collect(),
+            extensions_required: doc.extensions_required().map(|ext| ext.to_owned()).collect(),
+            scenes: doc.scenes().map(|g_scene| SceneInfo {
+                index: g_scene.index(),
+                name: g_scene.name().map(|s| s.to_owned()),
+                bounds: Scene::bounds_from_gltf(&g_scene).map(BoundsInfo::from),
+                nodes: g_scene.nodes().map(|g_node| NodeInfo::from_gltf(&g_node)).collect(),
+            }).collect(),
+            meshes: doc.meshes().map(|g_mesh| MeshInfo {
+                index: g_mesh.index(),
+                name: g_mesh.name().map(|s| s.to_owned()),
+                primitives: g_mesh.primitives().map(|g_primitive| PrimitiveInfo {
+                    mode: format!("{:?}", g_primitive.mode()),
+                    vertex_count: g_primitive.get(&gltf::Semantic::Positions).map_or(0, |a| a.count()),
+                    index_count: g_primitive.indices().map(|a| a.count()),
+                    attributes: g_primitive.attributes().map(|(semantic, _)| semantic.to_string()).collect(),
+                    material: g_primitive.material().index(),
+                }).collect(),
+            }).collect(),
+            materials: doc.materials().map(|g_material| MaterialInfo::from_gltf(&g_material)).collect(),
+            cameras: doc.cameras().map(|g_camera| CameraInfo {
+                index: g_camera.index(),
+                description: Camera::from_gltf(&g_camera).description(),
+            }).collect(),
+            animations: doc.animations().map(|g_animation| AnimationInfo {
+                index: g_animation.index(),
+                name: g_animation.name().map(|s| s.to_owned()),
+                channels: g_animation.channels().map(|channel| ChannelInfo {
+                    node: channel.target().node().index(),
+                    property: format!("{:?}", channel.target().property()),
+                    interpolation: format!("{:?}", channel.sampler().interpolation()),
+                    keyframes: channel.sampler().input().count(),
+                }).collect(),
+            }).collect(),
+            skins: doc.skins().map(|g_skin| SkinInfo {
+                index: g_skin.index(),
+                name: g_skin.name().map(|s| s.to_owned()),
+                joints: g_skin.joints().map(|joint| joint.index()).collect(),
+                skeleton: g_skin.skeleton().map(|node| node.index()),
+            }).collect(),
+            variants: imp.variant_names().into_iter().enumerate().map(|(index, name)| VariantInfo {
+                index,
+                name,
+                materials: variant_materials(&imp.json, index),
+            }).collect(),
+        }
+    }
+}
+
+impl NodeInfo {
+    fn from_gltf(g_node: &gltf::Node<'_>) -> NodeInfo {
+        let (translation, rotation, scale) = g_node.transform().decomposed();
+        NodeInfo {
+            index: g_node.index(),
+            name: g_node.name().map(|s| s.to_owned()),
+            translation,
+            rotation,
+            scale,
+            mesh: g_node.mesh().map(|mesh| mesh.index()),
+            camera: g_node.camera().map(|camera| camera.index()),
+            skin: g_node.skin().map(|skin| skin.index()),
+            children: g_node.children().map(|child| NodeInfo::from_gltf(&child)).collect(),
+        }
+    }
+}
+
+impl MaterialInfo {
+    fn from_gltf(g_material: &gltf::Material<'_>) -> MaterialInfo {
+        let pbr = g_material.pbr_metallic_roughness();
+        let mut textures = vec![];
+        let mut add = |slot, texture: Option<(texture::Texture<'_>, u32)>| {
+            if let Some((texture, tex_coord)) = texture {
+                textures.push(TextureRefInfo { slot, texture: texture.index(), tex_coord });
+            }
+        };
+        add("baseColor", pbr.base_color_texture().map(|info| (info.texture(), info.tex_coord())));
+        add("metallicRoughness", pbr.metallic_roughness_texture().map(|info| (info.texture(), info.tex_coord())));
+        add("normal", g_material.normal_texture().map(|info| (info.texture(), info.tex_coord())));
+        add("occlusion", g_material.occlusion_texture().map(|info| (info.texture(), info.tex_coord())));
+        add("emissive", g_material.emissive_texture().map(|info| (info.texture(), info.tex_coord())));
+
+        MaterialInfo {
+            index: g_material.index().unwrap(),
+            name: g_material.name().map(|s| s.to_owned()),
+            alpha_mode: format!("{:?}", g_material.alpha_mode()),
+            alpha_cutoff: g_material.alpha_cutoff(),
+            double_sided: g_material.double_sided(),
+            textures,
+        }
+    }
+}
+
+impl From<Aabb3> for BoundsInfo {
+    fn from(bounds: Aabb3) -> Self {
+        BoundsInfo { min: bounds.min.into(), max: bounds.max.into() }
+    }
+}
+
+/// Materials mapped to the given variant by any primitive
+fn variant_materials(json: &serde_json::Value, variant: usize) -> Vec<usize> {
+    let mut materials: Vec<usize> = json["meshes"].as_array().into_iter().flatten()
+        .flat_map(|mesh| mesh["primitives"].as_array().into_iter().flatten())
+        .flat_map(|primitive| primitive["extensions"]["KHR_materials_variants"]["mappings"].as_array().into_iter().flatten())
+        .filter(|mapping| mapping["variants"].as_array().is_some_and(|variants| variants.contains(&variant.into())))
+        .filter_map(|mapping| mapping["material"].as_u64().map(|material| material as usize))
+        .collect();
+    materials.sort_unstable();
+    materials.dedup();
+    materials
+}
+
+fn name(name: &Option<String>) -> String {
+    name.as_ref().map_or_else(String::new, |name| format!(" \"{}\"", name))
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() { "-".into() } else { items.join(", ") }
+}
+
+impl NodeInfo {
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let mut refs = String::new();
+        if let Some(mesh) = self.mesh { write!(refs, " mesh={}", mesh)?; }
+        if let Some(camera) = self.camera { write!(refs, " camera={}", camera)?; }
+        if let Some(skin) = self.skin { write!(refs, " skin={}", skin)?; }
+        writeln!(f, "{:indent$}Node {}{}: T={:?} R={:?} S={:?}{}", "",
+            self.index, name(&self.name), self.translation, self.rotation, self.scale, refs,
+            indent = 4 + depth * 2)?;
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "glTF {}{}", self.version,
+            self.generator.as_ref().map_or_else(String::new, |g| format!(" (generator: {})", g)))?;
+        writeln!(f, "Extensions used: {}", list(&self.extensions_used))?;
+        writeln!(f, "Extensions required: {}", list(&self.extensions_required))?;
+
+        writeln!(f, "Scenes ({}):", self.scenes.len())?;
+        for scene in &self.scenes {
+            match &scene.bounds {
+                Some(bounds) => writeln!(f, "  Scene {}{}: bounds {:?} - {:?}", scene.index, name(&scene.name),
+                    bounds.min, bounds.max)?,
+                None => writeln!(f, "  Scene {}{}: no bounds", scene.index, name(&scene.name))?,
+            }
+            for node in &scene.nodes {
+                node.write_tree(f, 0)?;
+            }
+        }
+
+        writeln!(f, "Meshes ({}):", self.meshes.len())?;
+        for mesh in &self.meshes {
+            writeln!(f, "  Mesh {}{}:", mesh.index, name(&mesh.name))?;
+            for primitive in &mesh.primitives {
+                let indices = primitive.index_count.map_or_else(|| "not indexed".into(), |n| format!("{} indices", n));
+                let material = primitive.material.map_or_else(|| "default".into(), |m| m.to_string());
+                writeln!(f, "    {}: {} vertices, {}, material {}, attributes: {}", primitive.mode,
+                    primitive.vertex_count, indices, material, list(&primitive.attributes))?;
+            }
+        }
+
+        writeln!(f, "Materials ({}):", self.materials.len())?;
+        for material in &self.materials {
+            let textures: Vec<_> = material.textures.iter()
+                .map(|t| format!("{}={} (TEXCOORD_{})", t.slot, t.texture, t.tex_coord))
+                .collect();
+            let cutoff = if material.alpha_mode == "Mask" { format!(" (cutoff {})", material.alpha_cutoff) } else { String::new() };
+            writeln!(f, "  Material {}{}: alpha mode {}{}{}, textures: {}", material.index, name(&material.name),
+                material.alpha_mode, cutoff, if material.double_sided { ", double sided" } else { "" }, list(&textures))?;
+        }
+
+        writeln!(f, "Cameras ({}):", self.cameras.len())?;
+        for camera in &self.cameras {
+            writeln!(f, "  Camera {}", camera.description)?;
+        }
+
+        writeln!(f, "Animations ({}):", self.animations.len())?;
+        for animation in &self.animations {
+            writeln!(f, "  Animation {}{}: {} channel(s)", animation.index, name(&animation.name), animation.channels.len())?;
+            for channel in &animation.channels {
+                writeln!(f, "    node {} {}: {} keyframes, {}", channel.node, channel.property,
+                    channel.keyframes, channel.interpolation)?;
+            }
+        }
+
+        writeln!(f, "Skins ({}):", self.skins.len())?;
+        for skin in &self.skins {
+            let skeleton = skin.skeleton.map_or_else(String::new, |s| format!(", skeleton {}", s));
+            writeln!(f, "  Skin {}{}: {} joint(s){}", skin.index, name(&skin.name), skin.joints.len(), skeleton)?;
+        }
+
+        writeln!(f, "Material variants ({}):", self.variants.len())?;
+        for variant in &self.variants {
+            let materials: Vec<_> = variant.materials.iter().map(|m| m.to_string()).collect();
+            writeln!(f, "  Variant {} \"{}\": materials {}", variant.index, variant.name, list(&materials))?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the report for the given file and returns the exit code
+pub fn run(source: &str, format: Format) -> i32 {
+    if let Err(err) = check_required_extensions(source) {
+        eprintln!("{}", err);
+        return 1
+    }
+    let imp = match ImportData::import(Path::new(source)) {
+        Ok(imp) => imp,
+        Err(err) => {
+            eprintln!("glTF import failed: {}", err);
+            return 1
+        }
+    };
+    let info = Info::from_gltf(&imp);
+    match format {
+        Format::Text => print!("{}", info),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&info).unwrap()),
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_info() {
+        let imp = ImportData::import(Path::new("src/data/Box.gltf")).unwrap();
+        let info = Info::from_gltf(&imp);
+        assert_eq!(info.scenes.len(), 1);
+        assert_eq!(info.meshes.len(), 1);
+        let primitive = &info.meshes[0].primitives[0];
+        assert_eq!(primitive.mode, "Triangles");
+        assert_eq!(primitive.vertex_count, 24);
+        assert_eq!(primitive.index_count, Some(36));
+        let bounds = info.scenes[0].bounds.as_ref().unwrap();
+        assert_eq!(bounds.min, [-0.5, -0.5, -0.5]);
+        assert_eq!(bounds.max, [0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn bounds_skip_primitives_without_positions() {
+        // NOTE: rejected by the validation of the `gltf` crate, but valid according to the spec
+        let json = serde_json::json!({
+            "asset": { "version": "2.0" },
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0, "translation": [1.0, 0.0, 0.0] }],
+            "meshes": [{ "primitives": [
+                { "attributes": { "POSITION": 0 }, "mode": 0 },
+                { "attributes": { "NORMAL": 0 }, "mode": 0 },
+            ]}],
+            "accessors": [{ "componentType": 5126, "count": 1, "type": "VEC3",
+                "min": [1.0, 2.0, 3.0], "max": [1.0, 2.0, 3.0] }],
+        });
+        let gltf = gltf::Gltf::from_slice_without_validation(json.to_string().as_bytes()).unwrap();
+        let bounds = Scene::bounds_from_gltf(&gltf.scenes().next().unwrap()).unwrap();
+        assert_eq!(bounds.min, Point3::new(2.0, 2.0, 3.0));
+        assert_eq!(bounds.max, Point3::new(2.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn unsupported_required_extension() {
+        let json = serde_json::json!({
+            "asset": { "version": "2.0" },
+            "extensionsUsed": ["EXT_unknown"],
+            "extensionsRequired": ["EXT_unknown"],
+        });
+        let path = std::env::temp_dir().join("gltf-viewer-info-unsupported.gltf");
+        std::fs::write(&path, json.to_string()).unwrap();
+        assert_eq!(run(path.to_str().unwrap(), Format::Text), 1);
+    }
+
+    #[test]
+    fn variant_materials_of_mappings() {
+        let json = serde_json::json!({
+            "meshes": [{ "primitives": [
+                { "extensions": { "KHR_materials_variants": { "mappings": [
+                    { "material": 2, "variants": [0, 1] },
+                    { "material": 1, "variants": [1] },
+                ]}}},
+                { "extensions": { "KHR_materials_variants": { "mappings": [
+                    { "material": 2, "variants": [1] },
+                ]}}},
+                {},
+            ]}]
+        });
+        assert_eq!(variant_materials(&json, 0), vec![2]);
+        assert_eq!(variant_materials(&json, 1), vec![1, 2]);
+        assert!(variant_materials(&json, 2).is_empty());
+    }
+}
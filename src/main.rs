@@ -3,25 +3,35 @@
 // #![feature(test)]
 #![allow(clippy::cast_lossless, clippy::cyclomatic_complexity)]
 
+use std::process;
+
 use clap::crate_version;
 use cgmath;
 use cgmath::Deg;
 
-use clap::{Arg, App, AppSettings};
+use clap::{Arg, App, AppSettings, SubCommand};
 
-use log::warn;
+use log::{warn, error};
 
 use simplelog::{TermLogger, LevelFilter, ConfigBuilder as LogConfigBuilder, TerminalMode};
 
 mod utils;
 mod viewer;
-use crate::viewer::{GltfViewer, CameraOptions};
+use crate::viewer::{GltfViewer, CameraOptions, RenderOptions, Backend};
 
 mod shader;
 mod controls;
+#[cfg(target_os = "linux")]
+mod egl_context;
 mod framebuffer;
-mod macros;
+mod hud;
+mod postprocess;
+mod imagediff;
+mod info;
 mod importdata;
+mod basisu;
+mod draco;
+mod meshopt;
 // TODO!: adapt Source...
 // mod http_source;
 // use http_source::HttpSource;
@@ -33,8 +43,10 @@ pub fn main() {
         .version(option_env!("VERSION").unwrap_or(crate_version!()))
         .setting(AppSettings::UnifiedHelpMessage)
         .setting(AppSettings::DeriveDisplayOrder)
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .setting(AppSettings::VersionlessSubcommands)
         .before_help("glTF 2.0 viewer\n\nNavigate with the mouse (left/right click + drag, mouse wheel) \
-                    or WASD/cursor keys.")
+                    or WASD/cursor keys. Toggle statistics with F1, cycle material variants with V.")
         .arg(Arg::with_name("FILE") // TODO!: re-add URL when fixed...
             .required(true)
             .takes_value(true)
@@ -67,9 +79,67 @@ pub fn main() {
             .default_value("1")
             .help("Saves N screenshots of size WxH, rotating evenly spaced around the object")
             .validator(|value| value.parse::<u32>().map(|_| ()).map_err(|err| err.to_string())))
+        .arg(Arg::with_name("backend")
+            .long("backend")
+            .takes_value(true)
+            .possible_values(&["window", "egl", "osmesa"])
+            .default_value("window")
+            .help("OpenGL context backend. `egl` and `osmesa` render headless and only support screenshots. \n\
+                `egl` needs no display server (Linux only, e.g. Mesa llvmpipe), \
+                `osmesa` uses glutin's headless renderer."))
         .arg(Arg::with_name("headless")
             .long("headless")
-            .help("Use real headless rendering for screenshots (default is a hidden window) [EXPERIMENTAL - see README for details]"))
+            .conflicts_with("backend")
+            .help("Same as `--backend osmesa` [EXPERIMENTAL - see README for details]"))
+        .arg(Arg::with_name("tonemap")
+            .long("tonemap")
+            .takes_value(true)
+            .possible_values(&["aces", "reinhard", "filmic", "none"])
+            .default_value("aces")
+            .help("Tone mapping operator for the HDR scene color"))
+        .arg(Arg::with_name("EXPOSURE")
+            .long("exposure")
+            .takes_value(true)
+            .default_value("1.0")
+            .help("Exposure (linear multiplier of the scene color before tone mapping)")
+            .validator(|value| value.parse::<f32>().map(|_| ()).map_err(|err| err.to_string())))
+        .arg(Arg::with_name("POINT-SIZE")
+            .long("point-size")
+            .takes_value(true)
+            .default_value("10")
+            .help("Size of points (POINTS primitives) in pixels")
+            .validator(|value| value.parse::<f32>().map(|_| ()).map_err(|err| err.to_string())))
+        .arg(Arg::with_name("LINE-WIDTH")
+            .long("line-width")
+            .takes_value(true)
+            .default_value("1")
+            .help("Width of lines (LINES primitives) in pixels")
+            .validator(|value| value.parse::<f32>().map(|_| ()).map_err(|err| err.to_string())))
+        .arg(Arg::with_name("ANISOTROPY")
+            .long("anisotropy")
+            .takes_value(true)
+            .default_value("1")
+            .help("Maximum degree of anisotropic texture filtering (e.g. 16). 1 disables it. \
+                Needs GL_EXT_texture_filter_anisotropic.")
+            .validator(|value| match value.parse::<f32>() {
+                Ok(value) if value.is_finite() && value >= 1.0 => Ok(()),
+                Ok(_) => Err("must be a finite number of at least 1".to_owned()),
+                Err(err) => Err(err.to_string()),
+            }))
+        .arg(Arg::with_name("VARIANT")
+            .long("variant")
+            .takes_value(true)
+            .help("Select the material variant with the given name (KHR_materials_variants)"))
+        .arg(Arg::with_name("info")
+            .long("info")
+            .help("Print information about the model (scenes, nodes, meshes, materials, ...) and exit. \
+                Doesn't open a window."))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .takes_value(true)
+            .possible_values(&["text", "json"])
+            .requires("info")
+            .help("Output format for --info [default: text]"))
         .arg(Arg::with_name("straight")
             .long("straight")
             .help("Position camera in front of model if using default camera (i.e. glTF doesn't contain a camera or `--cam-index -1` is passed)"))
@@ -104,15 +174,59 @@ pub fn main() {
             .default_value("75")
             .help("Vertical field of view ('zoom') in degrees.")
             .validator(|value| value.parse::<u32>().map(|_| ()).map_err(|err| err.to_string())))
+        .subcommand(SubCommand::with_name("diff")
+            .about("Compare two images (e.g. screenshots of different viewer versions). \
+                Prints RMSE, PSNR, max channel error and the percentage of differing pixels. \
+                Exits with 1 if more pixels differ than allowed by --threshold, 2 on errors.")
+            .arg(Arg::with_name("A")
+                .required(true)
+                .help("First image (e.g. reference)"))
+            .arg(Arg::with_name("B")
+                .required(true)
+                .help("Second image"))
+            .arg(Arg::with_name("OUT")
+                .long("out")
+                .short("o")
+                .takes_value(true)
+                .help("Write a diff image (differing pixels in red) to the given file"))
+            .arg(Arg::with_name("THRESHOLD")
+                .long("threshold")
+                .default_value("0")
+                .help("Maximum percentage of differing pixels")
+                .validator(|value| value.parse::<f64>().map(|_| ()).map_err(|err| err.to_string())))
+            .arg(Arg::with_name("PIXEL-THRESHOLD")
+                .long("pixel-threshold")
+                .default_value("0.1")
+                .help("Perceptual color difference (0-1) above which a pixel counts as differing")
+                .validator(|value| value.parse::<f64>().map(|_| ()).map_err(|err| err.to_string()))))
         .get_matches();
+
+    if let Some(diff_args) = args.subcommand_matches("diff") {
+        process::exit(imagediff::run(diff_args))
+    }
+
     let source = args.value_of("FILE").unwrap();
 
+    if args.is_present("info") {
+        let format = match args.value_of("format") {
+            Some("json") => info::Format::Json,
+            _ => info::Format::Text,
+        };
+        process::exit(info::run(source, format))
+    }
+
     let width: u32 = args.value_of("WIDTH").unwrap().parse().unwrap();
     let height: u32 = args.value_of("HEIGHT").unwrap().parse().unwrap();
     let count: u32 = args.value_of("COUNT").unwrap().parse().unwrap();
 
     let scene: usize = args.value_of("scene").unwrap().parse().unwrap();
 
+    let backend = if args.is_present("headless") {
+        Backend::OsMesa
+    } else {
+        args.value_of("backend").unwrap().parse().unwrap()
+    };
+
     let camera_options = CameraOptions {
         index: args.value_of("CAM-INDEX").map(|n| n.parse().unwrap()).unwrap(),
         position: args.value_of("CAM-POS").map(|v| parse_vec3(v).unwrap()),
@@ -121,6 +235,14 @@ pub fn main() {
         straight: args.is_present("straight"),
     };
 
+    let render_options = RenderOptions {
+        tone_mapping: args.value_of("tonemap").unwrap().parse().unwrap(),
+        exposure: args.value_of("EXPOSURE").unwrap().parse().unwrap(),
+        point_size: args.value_of("POINT-SIZE").unwrap().parse().unwrap(),
+        line_width: args.value_of("LINE-WIDTH").unwrap().parse().unwrap(),
+        anisotropy: args.value_of("ANISOTROPY").unwrap().parse().unwrap(),
+    };
+
     let log_level = match args.occurrences_of("verbose") {
         0 => LevelFilter::Warn,
         1 => LevelFilter::Info,
@@ -137,12 +259,25 @@ pub fn main() {
             .build(),
         TerminalMode::Stdout);
 
+    if backend != Backend::Window && !args.is_present("screenshot") {
+        error!("Headless backends can only be used with --screenshot");
+        process::exit(1)
+    }
+
     let mut viewer = GltfViewer::new(source, width, height,
-        args.is_present("headless"),
+        backend,
         !args.is_present("screenshot"),
         camera_options,
+        render_options,
         scene);
 
+    if let Some(variant) = args.value_of("VARIANT") {
+        if let Err(err) = viewer.select_variant(variant) {
+            error!("{}", err);
+            process::exit(2)
+        }
+    }
+
     if args.is_present("screenshot") {
         let filename = args.value_of("screenshot").unwrap();
 
@@ -172,7 +307,6 @@ mod tests {
         println!("Node:      {:>3}", std::mem::size_of::<render::Node>());
         println!("Mesh:      {:>3}", std::mem::size_of::<render::Mesh>());
         println!("Primitive: {:>3}", std::mem::size_of::<render::Primitive>());
-        println!("Vertex:    {:>3}", std::mem::size_of::<render::Vertex>());
         println!();
         println!("Option<String>: {:>3}", std::mem::size_of::<Option<String>>());
         println!("String:         {:>3}", std::mem::size_of::<String>());
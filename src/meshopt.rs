@@ -0,0 +1,524 @@
+//! Decoder for `EXT_meshopt_compression` buffer views (vertex/index codecs and filters of meshoptimizer).
+//! See https://github.com/KhronosGroup/glTF/tree/master/extensions/2.0/Vendor/EXT_meshopt_compression
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    Attributes,
+    Triangles,
+    Indices,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ATTRIBUTES" => Ok(Mode::Attributes),
+            "TRIANGLES" => Ok(Mode::Triangles),
+            "INDICES" => Ok(Mode::Indices),
+            _ => Err(format!("unknown mode: {}", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    None,
+    Octahedral,
+    Quaternion,
+    Exponential,
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NONE" => Ok(Filter::None),
+            "OCTAHEDRAL" => Ok(Filter::Octahedral),
+            "QUATERNION" => Ok(Filter::Quaternion),
+            "EXPONENTIAL" => Ok(Filter::Exponential),
+            _ => Err(format!("unknown filter: {}", s)),
+        }
+    }
+}
+
+const VERTEX_HEADER: u8 = 0xa0;
+const INDEX_HEADER: u8 = 0xe0;
+const SEQUENCE_HEADER: u8 = 0xd0;
+
+const VERTEX_BLOCK_SIZE_BYTES: usize = 8192;
+const VERTEX_BLOCK_MAX_SIZE: usize = 256;
+const BYTE_GROUP_SIZE: usize = 16;
+const TAIL_MAX_SIZE: usize = 32;
+
+/// Decodes `count` elements of `stride` bytes and applies the filter.
+pub fn decode(source: &[u8], count: usize, stride: usize, mode: Mode, filter: Filter) -> Result<Vec<u8>, String> {
+    let mut data = vec![0; count * stride];
+    match mode {
+        Mode::Attributes => {
+            if stride == 0 || stride & 3 != 0 || stride > 256 {
+                return Err(format!("invalid byteStride for ATTRIBUTES: {}", stride))
+            }
+            decode_vertex_buffer(&mut data, count, stride, source)?
+        },
+        Mode::Triangles | Mode::Indices => {
+            if stride != 2 && stride != 4 {
+                return Err(format!("invalid byteStride for {:?}: {}", mode, stride))
+            }
+            if mode == Mode::Triangles {
+                decode_index_buffer(&mut data, count, stride, source)?
+            } else {
+                decode_index_sequence(&mut data, count, stride, source)?
+            }
+        },
+    }
+    match (filter, stride) {
+        (Filter::None, _) => {},
+        (_, _) if mode != Mode::Attributes => return Err("filters are only allowed for ATTRIBUTES".to_owned()),
+        (Filter::Octahedral, 4) | (Filter::Octahedral, 8) => decode_filter_oct(&mut data, stride),
+        (Filter::Quaternion, 8) => decode_filter_quat(&mut data),
+        (Filter::Exponential, _) => decode_filter_exp(&mut data),
+        _ => return Err(format!("invalid byteStride for {:?} filter: {}", filter, stride)),
+    }
+    Ok(data)
+}
+
+fn unexpected_end() -> String {
+    "unexpected end of data".to_owned()
+}
+
+fn vertex_block_size(vertex_size: usize) -> usize {
+    // a block fits into 8 KB and is aligned to the byte group size
+    let result = (VERTEX_BLOCK_SIZE_BYTES / vertex_size) & !(BYTE_GROUP_SIZE - 1);
+    result.min(VERTEX_BLOCK_MAX_SIZE)
+}
+
+fn unzigzag8(v: u8) -> u8 {
+    (v & 1).wrapping_neg() ^ (v >> 1)
+}
+
+fn decode_vertex_buffer(destination: &mut [u8], count: usize, vertex_size: usize, source: &[u8]) -> Result<(), String> {
+    if source.len() < 1 + vertex_size {
+        return Err(unexpected_end())
+    }
+    if source[0] & 0xf0 != VERTEX_HEADER {
+        return Err("invalid vertex buffer header".to_owned())
+    }
+    let version = source[0] & 0x0f;
+    if version > 0 {
+        return Err(format!("unsupported vertex codec version: {}", version))
+    }
+
+    // the first vertex is stored at the end (in the tail) and serves as the baseline for the deltas
+    let mut last_vertex = source[source.len() - vertex_size..].to_vec();
+    let block_size = vertex_block_size(vertex_size);
+    let mut buffer = [0; VERTEX_BLOCK_MAX_SIZE];
+    let mut position = 1;
+    let mut vertex_offset = 0;
+    while vertex_offset < count {
+        let block_count = block_size.min(count - vertex_offset);
+        let aligned_count = (block_count + BYTE_GROUP_SIZE - 1) & !(BYTE_GROUP_SIZE - 1);
+        // bytes are stored transposed: all first bytes of the block's vertices, then all second bytes etc.
+        for k in 0..vertex_size {
+            position = decode_bytes(source, position, &mut buffer[..aligned_count])?;
+            let mut p = last_vertex[k];
+            for (i, &encoded) in buffer[..block_count].iter().enumerate() {
+                p = unzigzag8(encoded).wrapping_add(p);
+                destination[(vertex_offset + i) * vertex_size + k] = p;
+            }
+            last_vertex[k] = p;
+        }
+        vertex_offset += block_count;
+    }
+
+    if source.len() - position != vertex_size.max(TAIL_MAX_SIZE) {
+        return Err("unexpected vertex buffer size".to_owned())
+    }
+    Ok(())
+}
+
+fn decode_bytes(source: &[u8], mut position: usize, buffer: &mut [u8]) -> Result<usize, String> {
+    // 2 bits per byte group in the header, specifying the bits per value
+    let header_size = (buffer.len() / BYTE_GROUP_SIZE).div_ceil(4);
+    if source.len() - position < header_size {
+        return Err(unexpected_end())
+    }
+    let header = position;
+    position += header_size;
+    for (group_index, group) in buffer.chunks_mut(BYTE_GROUP_SIZE).enumerate() {
+        // guarantees that the largest group (16 bytes + 8 header bytes) can be read
+        if source.len() - position < TAIL_MAX_SIZE {
+            return Err(unexpected_end())
+        }
+        let bits_log2 = (source[header + group_index / 4] >> ((group_index % 4) * 2)) & 3;
+        position = decode_bytes_group(source, position, group, bits_log2);
+    }
+    Ok(position)
+}
+
+fn decode_bytes_group(source: &[u8], position: usize, group: &mut [u8], bits_log2: u8) -> usize {
+    match bits_log2 {
+        0 => {
+            for value in group.iter_mut() {
+                *value = 0;
+            }
+            position
+        },
+        1 | 2 => {
+            // packed values, where the maximum value means that the byte is stored explicitly after them
+            let bits = 1 << bits_log2;
+            let max = (1u8 << bits) - 1;
+            let mut explicit = position + BYTE_GROUP_SIZE * bits / 8;
+            for (i, value) in group.iter_mut().enumerate() {
+                let byte = source[position + i * bits / 8];
+                let encoded = (byte >> (8 - bits - (i * bits) % 8)) & max;
+                *value = if encoded == max {
+                    explicit += 1;
+                    source[explicit - 1]
+                } else {
+                    encoded
+                };
+            }
+            explicit
+        },
+        _ => {
+            group.copy_from_slice(&source[position..position + BYTE_GROUP_SIZE]);
+            position + BYTE_GROUP_SIZE
+        },
+    }
+}
+
+fn decode_vbyte(source: &[u8], position: &mut usize) -> u32 {
+    let lead = source[*position];
+    *position += 1;
+    if lead < 128 {
+        return u32::from(lead)
+    }
+    // up to 4 more 7 bit groups
+    let mut result = u32::from(lead & 127);
+    let mut shift = 7;
+    for _ in 0..4 {
+        let group = source[*position];
+        *position += 1;
+        result |= u32::from(group & 127) << shift;
+        shift += 7;
+        if group < 128 {
+            break
+        }
+    }
+    result
+}
+
+fn unzigzag(v: u32) -> u32 {
+    (v >> 1) ^ (v & 1).wrapping_neg()
+}
+
+fn write_index(destination: &mut [u8], i: usize, index_size: usize, index: u32) {
+    if index_size == 2 {
+        destination[i * 2..i * 2 + 2].copy_from_slice(&(index as u16).to_le_bytes());
+    } else {
+        destination[i * 4..i * 4 + 4].copy_from_slice(&index.to_le_bytes());
+    }
+}
+
+/// FIFOs of recently seen vertices and edges, which have to be updated exactly like when encoding
+struct IndexFifos {
+    vertices: [u32; 16],
+    vertex_offset: usize,
+    edges: [(u32, u32); 16],
+    edge_offset: usize,
+}
+
+impl IndexFifos {
+    fn vertex(&self, i: usize) -> u32 {
+        self.vertices[self.vertex_offset.wrapping_sub(i) & 15]
+    }
+
+    fn push_vertex(&mut self, v: u32, cond: bool) {
+        self.vertices[self.vertex_offset] = v;
+        self.vertex_offset = (self.vertex_offset + cond as usize) & 15;
+    }
+
+    fn edge(&self, i: usize) -> (u32, u32) {
+        self.edges[self.edge_offset.wrapping_sub(1 + i) & 15]
+    }
+
+    fn push_edge(&mut self, a: u32, b: u32) {
+        self.edges[self.edge_offset] = (a, b);
+        self.edge_offset = (self.edge_offset + 1) & 15;
+    }
+}
+
+fn decode_index_buffer(destination: &mut [u8], count: usize, index_size: usize, source: &[u8]) -> Result<(), String> {
+    let triangle_count = count / 3;
+    if triangle_count * 3 != count {
+        return Err("index count must be divisible by 3".to_owned())
+    }
+    // the minimum valid encoding is the header, 1 byte per triangle and a 16 byte code table
+    if source.len() < 1 + triangle_count + 16 {
+        return Err(unexpected_end())
+    }
+    if source[0] & 0xf0 != INDEX_HEADER {
+        return Err("invalid index buffer header".to_owned())
+    }
+    let version = source[0] & 0x0f;
+    if version > 1 {
+        return Err(format!("unsupported index codec version: {}", version))
+    }
+    // version 1 encodes last-1 and last+1 as 13 and 14
+    let fec_max = if version >= 1 { 13 } else { 15 };
+
+    let mut fifos = IndexFifos {
+        vertices: [u32::MAX; 16],
+        vertex_offset: 0,
+        edges: [(u32::MAX, u32::MAX); 16],
+        edge_offset: 0,
+    };
+    let mut next = 0u32;
+    let mut last = 0u32;
+
+    let codes = &source[1..1 + triangle_count];
+    let mut data = 1 + triangle_count;
+    let data_safe_end = source.len() - 16;
+    let code_aux_table = &source[data_safe_end..];
+
+    for (triangle, &code_tri) in codes.iter().enumerate() {
+        // a triangle reads at most 16 bytes of data (1 code byte and 3 free indices of 5 bytes)
+        if data > data_safe_end {
+            return Err(unexpected_end())
+        }
+
+        let (a, b, c);
+        if code_tri < 0xf0 {
+            // edge from the FIFO + a new, cached or free vertex
+            let fe = (code_tri >> 4) as usize;
+            let (ea, eb) = fifos.edge(fe);
+            a = ea;
+            b = eb;
+            let fec = (code_tri & 15) as usize;
+            if fec < fec_max {
+                c = if fec == 0 { next } else { fifos.vertex(1 + fec) };
+                next += (fec == 0) as u32;
+                fifos.push_vertex(c, fec == 0);
+            } else {
+                c = if fec != 15 {
+                    // 13 -> last - 1, 14 -> last + 1
+                    last.wrapping_add((fec as u32).wrapping_sub(fec as u32 ^ 3))
+                } else {
+                    last.wrapping_add(unzigzag(decode_vbyte(source, &mut data)))
+                };
+                last = c;
+                fifos.push_vertex(c, true);
+            }
+            fifos.push_edge(c, b);
+            fifos.push_edge(a, c);
+        } else {
+            let (fea, feb, fec) = if code_tri < 0xfe {
+                // the code for the second and third vertex is looked up from the table
+                let code_aux = code_aux_table[(code_tri & 15) as usize];
+                (0, (code_aux >> 4) as usize, (code_aux & 15) as usize)
+            } else {
+                let code_aux = source[data];
+                data += 1;
+                (if code_tri == 0xfe { 0 } else { 15 }, (code_aux >> 4) as usize, (code_aux & 15) as usize)
+            };
+
+            // note: next is incremented for all three vertices before decoding free indices
+            let mut vertex = |fe: usize| if fe == 0 {
+                next += 1;
+                next - 1
+            } else if fe == 15 {
+                0
+            } else {
+                fifos.vertex(fe)
+            };
+            let mut ta = vertex(fea);
+            let mut tb = vertex(feb);
+            let mut tc = vertex(fec);
+            if fea == 15 {
+                last = last.wrapping_add(unzigzag(decode_vbyte(source, &mut data)));
+                ta = last;
+            }
+            if feb == 15 {
+                last = last.wrapping_add(unzigzag(decode_vbyte(source, &mut data)));
+                tb = last;
+            }
+            if fec == 15 {
+                last = last.wrapping_add(unzigzag(decode_vbyte(source, &mut data)));
+                tc = last;
+            }
+            a = ta;
+            b = tb;
+            c = tc;
+
+            fifos.push_vertex(a, true);
+            fifos.push_vertex(b, feb == 0 || feb == 15);
+            fifos.push_vertex(c, fec == 0 || fec == 15);
+            fifos.push_edge(b, a);
+            fifos.push_edge(c, b);
+            fifos.push_edge(a, c);
+        }
+
+        write_index(destination, triangle * 3, index_size, a);
+        write_index(destination, triangle * 3 + 1, index_size, b);
+        write_index(destination, triangle * 3 + 2, index_size, c);
+    }
+
+    // all data has to be consumed, up to the code table
+    if data != data_safe_end {
+        return Err("unexpected index buffer size".to_owned())
+    }
+    Ok(())
+}
+
+fn decode_index_sequence(destination: &mut [u8], count: usize, index_size: usize, source: &[u8]) -> Result<(), String> {
+    // the minimum valid encoding is the header, 1 byte per index and a 4 byte tail
+    if source.len() < 1 + count + 4 {
+        return Err(unexpected_end())
+    }
+    if source[0] & 0xf0 != SEQUENCE_HEADER {
+        return Err("invalid index sequence header".to_owned())
+    }
+    let version = source[0] & 0x0f;
+    if version > 1 {
+        return Err(format!("unsupported index sequence codec version: {}", version))
+    }
+
+    let data_safe_end = source.len() - 4;
+    let mut data = 1;
+    // deltas are relative to one of two baselines
+    let mut last = [0u32; 2];
+    for i in 0..count {
+        if data >= data_safe_end {
+            return Err(unexpected_end())
+        }
+        let v = decode_vbyte(source, &mut data);
+        let baseline = (v & 1) as usize;
+        let index = last[baseline].wrapping_add(unzigzag(v >> 1));
+        last[baseline] = index;
+        write_index(destination, i, index_size, index);
+    }
+
+    if data != data_safe_end {
+        return Err("unexpected index sequence size".to_owned())
+    }
+    Ok(())
+}
+
+fn octahedral(x: f32, y: f32, one: f32, max: f32) -> [f32; 3] {
+    // convert x/y to [-1..1] and reconstruct z
+    let x = x / one;
+    let y = y / one;
+    let z = 1.0 - x.abs() - y.abs();
+    // fix up x/y for the lower hemisphere
+    let t = (-z).max(0.0);
+    let x = x - if x >= 0.0 { t } else { -t };
+    let y = y - if y >= 0.0 { t } else { -t };
+    // renormalize to the signed integer range
+    let h = max / (x * x + y * y + z * z).sqrt();
+    // note: rounds half away from zero like the reference implementation
+    [(x * h).round(), (y * h).round(), (z * h).round()]
+}
+
+/// Octahedral encoded unit vectors: x, y, the value representing 1.0 and a 4th component which is kept.
+fn decode_filter_oct(data: &mut [u8], stride: usize) {
+    if stride == 4 {
+        for v in data.chunks_exact_mut(4) {
+            let n = octahedral(f32::from(v[0] as i8), f32::from(v[1] as i8), f32::from(v[2] as i8), 127.0);
+            for (component, value) in v.iter_mut().zip(n.iter()) {
+                *component = *value as i8 as u8;
+            }
+        }
+    } else {
+        for v in data.chunks_exact_mut(8) {
+            let c = |i: usize| f32::from(i16::from_le_bytes([v[i * 2], v[i * 2 + 1]]));
+            let n = octahedral(c(0), c(1), c(2), 32767.0);
+            for (i, value) in n.iter().enumerate() {
+                v[i * 2..i * 2 + 2].copy_from_slice(&(*value as i16).to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Unit quaternions with 3 components; the 4th component contains the scale and the index of the omitted component.
+fn decode_filter_quat(data: &mut [u8]) {
+    let scale = 1.0 / 2f32.sqrt();
+    for v in data.chunks_exact_mut(8) {
+        let c = |i: usize| i16::from_le_bytes([v[i * 2], v[i * 2 + 1]]);
+        let (c0, c1, c2, c3) = (c(0), c(1), c(2), c(3));
+        let ss = scale / f32::from(c3 | 3);
+        let x = f32::from(c0) * ss;
+        let y = f32::from(c1) * ss;
+        let z = f32::from(c2) * ss;
+        // reconstruct w, clamped to avoid NaN due to precision errors
+        let w = (1.0 - x * x - y * y - z * z).max(0.0).sqrt();
+
+        let qc = (c3 & 3) as usize;
+        let mut out = [0i16; 4];
+        out[(qc + 1) & 3] = (x * 32767.0).round() as i16;
+        out[(qc + 2) & 3] = (y * 32767.0).round() as i16;
+        out[(qc + 3) & 3] = (z * 32767.0).round() as i16;
+        out[qc] = (w * 32767.0).round() as i16;
+        for (i, value) in out.iter().enumerate() {
+            v[i * 2..i * 2 + 2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// 32 bit values with a 24 bit mantissa and an 8 bit exponent, decoded to `f32`
+fn decode_filter_exp(data: &mut [u8]) {
+    for v in data.chunks_exact_mut(4) {
+        let value = i32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+        let mantissa = (value << 8) >> 8;
+        let exponent = value >> 24;
+        // 2^exponent, constructed directly (like the reference implementation)
+        let scale = f32::from_bits(((exponent + 127) as u32).wrapping_shl(23));
+        v.copy_from_slice(&(mantissa as f32 * scale).to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_and_index_codecs() {
+        // encoded with meshoptimizer
+        let mut encoded = vec![
+            0xa0, 0x01, 0x3f, 0x00, 0x00, 0x00, 0xc8, 0xc8, 0xc8, 0x01, 0x02, 0x00, 0x00, 0x00, 0x01, 0x3f,
+            0x00, 0x00, 0x00, 0xc7, 0xc7, 0xc7, 0x01, 0x11];
+        encoded.resize(59, 0);
+        let decoded = decode(&encoded, 4, 4, Mode::Attributes, Filter::None).unwrap();
+        let expected: Vec<u8> = [0i16, 0, 100, -100, 200, -200, 300, -300].iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(decoded, expected);
+
+        let encoded = [
+            0xe0, 0xf0, 0x10, 0x00, 0x76, 0x87, 0x56, 0x67, 0x78, 0xa9, 0x86, 0x65, 0x89, 0x68, 0x98, 0x01,
+            0x69, 0x00, 0x00];
+        let decoded = decode(&encoded, 6, 2, Mode::Triangles, Filter::None).unwrap();
+        assert_eq!(decoded, [0, 0, 1, 0, 2, 0, 2, 0, 1, 0, 3, 0]);
+        assert!(decode(&encoded[..18], 6, 2, Mode::Triangles, Filter::None).is_err());
+    }
+
+    #[test]
+    fn filters() {
+        // (0, 0, 1) and (-1, 0, 0) (lower hemisphere)
+        let mut data = vec![0, 0, 127, 0, 0x81, 0, 127, 0];
+        decode_filter_oct(&mut data, 4);
+        assert_eq!(data, [0, 0, 127, 0, 0x81, 0, 0, 0]);
+
+        // identity, with w (index 3) omitted
+        let mut data = [0i16, 0, 0, (1 << 2) | 3].iter().flat_map(|v| v.to_le_bytes().to_vec()).collect::<Vec<_>>();
+        decode_filter_quat(&mut data);
+        assert_eq!(data, [0, 0, 0, 0, 0, 0, 0xff, 0x7f]);
+
+        // 3 * 2^-1
+        let mut data = (3i32 | (-1 << 24)).to_le_bytes().to_vec();
+        decode_filter_exp(&mut data);
+        assert_eq!(data, 1.5f32.to_le_bytes());
+    }
+}
@@ -0,0 +1,152 @@
+//! Final pass: resolves the linear HDR scene color to the output framebuffer
+//! (exposure, tone mapping and sRGB encoding).
+use std::str::FromStr;
+
+use crate::framebuffer::Framebuffer;
+use crate::shader::Shader;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ToneMapping {
+    Aces,
+    Reinhard,
+    Filmic,
+    None,
+}
+
+impl FromStr for ToneMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "aces" => Ok(ToneMapping::Aces),
+            "reinhard" => Ok(ToneMapping::Reinhard),
+            "filmic" => Ok(ToneMapping::Filmic),
+            "none" => Ok(ToneMapping::None),
+            _ => Err(format!("unknown tone mapping operator: {}", s)),
+        }
+    }
+}
+
+impl ToneMapping {
+    fn define(self) -> Option<String> {
+        match self {
+            ToneMapping::Aces => Some("TONEMAP_ACES".into()),
+            ToneMapping::Reinhard => Some("TONEMAP_REINHARD".into()),
+            ToneMapping::Filmic => Some("TONEMAP_FILMIC".into()),
+            ToneMapping::None => None,
+        }
+    }
+}
+
+pub struct PostProcess {
+    /// Render target for the scene (RGBA16F), with the coverage of unlit materials
+    /// in a second attachment (R8) - they are output without exposure and tone mapping
+    pub hdr_framebuffer: Framebuffer,
+    /// Mip-mapped copy of the scene color for transmissive materials (created when needed)
+    transmission_framebuffer: Option<Framebuffer>,
+    size: (u32, u32),
+    shader: Shader,
+    u_exposure: i32,
+    /// Empty VAO (core profile requires one to be bound for drawing)
+    vao: u32,
+    pub exposure: f32,
+}
+
+impl PostProcess {
+    pub fn new(width: u32, height: u32, tone_mapping: ToneMapping, exposure: f32) -> PostProcess {
+        let defines: Vec<_> = tone_mapping.define().into_iter().collect();
+        let mut shader = Shader::from_source(
+            include_str!("shaders/tonemap-vert.glsl"),
+            include_str!("shaders/tonemap-frag.glsl"),
+            &defines);
+        let mut vao = 0;
+        let u_exposure = unsafe {
+            let u_hdr_color = shader.uniform_location("u_HdrColor");
+            let u_unlit_mask = shader.uniform_location("u_UnlitMask");
+            shader.use_program();
+            shader.set_int(u_hdr_color, 0);
+            shader.set_int(u_unlit_mask, 1);
+            gl::GenVertexArrays(1, &mut vao);
+            shader.uniform_location("u_Exposure")
+        };
+
+        PostProcess {
+            hdr_framebuffer: Self::create_hdr_framebuffer(width, height),
+            transmission_framebuffer: None,
+            size: (width, height),
+            shader,
+            u_exposure,
+            vao,
+            exposure,
+        }
+    }
+
+    fn create_hdr_framebuffer(width: u32, height: u32) -> Framebuffer {
+        let mut framebuffer = Framebuffer::with_format(width, height, gl::RGBA16F, gl::FLOAT);
+        framebuffer.add_color_attachment(width, height, gl::R8, gl::RED, gl::UNSIGNED_BYTE);
+        framebuffer
+    }
+
+    /// Binds and clears the HDR framebuffer (the unlit mask is always cleared to 0)
+    pub unsafe fn clear(&self) {
+        self.hdr_framebuffer.bind();
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        gl::ClearBufferfv(gl::COLOR, 1, [0.0; 4].as_ptr());
+    }
+
+    /// Needs to be called when the output size changes
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.hdr_framebuffer = Self::create_hdr_framebuffer(width, height);
+        self.transmission_framebuffer = None;
+        self.size = (width, height);
+    }
+
+    /// Copies the current scene color into a mip-mapped texture (sampled with roughness-dependent blur
+    /// by transmissive materials) and returns it. The HDR framebuffer stays bound.
+    pub unsafe fn copy_scene_color(&mut self) -> u32 {
+        let (width, height) = self.size;
+        let framebuffer = self.transmission_framebuffer.get_or_insert_with(|| {
+            let framebuffer = Framebuffer::with_format(width, height, gl::RGBA16F, gl::FLOAT);
+            gl::BindTexture(gl::TEXTURE_2D, framebuffer.color_texture);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            framebuffer
+        });
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.hdr_framebuffer.id);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, framebuffer.id);
+        gl::BlitFramebuffer(0, 0, width as i32, height as i32, 0, 0, width as i32, height as i32,
+            gl::COLOR_BUFFER_BIT, gl::NEAREST);
+        gl::BindTexture(gl::TEXTURE_2D, framebuffer.color_texture);
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+        self.hdr_framebuffer.bind();
+        framebuffer.color_texture
+    }
+
+    /// Draws the HDR scene color into the currently bound framebuffer
+    pub unsafe fn draw(&self) {
+        gl::Disable(gl::DEPTH_TEST);
+        gl::Disable(gl::BLEND);
+
+        self.shader.use_program();
+        self.shader.set_float(self.u_exposure, self.exposure);
+        gl::ActiveTexture(gl::TEXTURE1);
+        gl::BindTexture(gl::TEXTURE_2D, self.hdr_framebuffer.extra_color_textures[0]);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.hdr_framebuffer.color_texture);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        gl::BindVertexArray(0);
+
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.shader.id);
+        }
+    }
+}
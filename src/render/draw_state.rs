@@ -0,0 +1,49 @@
+/// State passed through the draw calls of a frame
+#[derive(Default)]
+pub struct DrawState {
+    /// Currently bound shader program (to skip redundant `glUseProgram` calls)
+    pub current_shader: u32,
+    pub stats: FrameStats,
+
+    /// Size of POINTS in pixels
+    pub point_size: f32,
+    /// Width of LINES in pixels
+    pub line_width: f32,
+    /// Size of the render target in pixels (needed for screen-space line widths)
+    pub viewport_size: (f32, f32),
+
+    /// Which primitives are drawn
+    pub pass: DrawPass,
+    /// Mip-mapped copy of the scene color without transmissive primitives (for `DrawPass::Transmission`)
+    pub transmission_texture: u32,
+}
+
+/// Scenes with transmissive materials (`KHR_materials_transmission`) are drawn in two passes,
+/// since those need the scene color behind them
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum DrawPass {
+    #[default]
+    All,
+    /// Everything except transmissive primitives
+    Opaque,
+    /// Only transmissive primitives
+    Transmission,
+}
+
+impl DrawPass {
+    pub fn includes(self, transmissive: bool) -> bool {
+        match self {
+            DrawPass::All => true,
+            DrawPass::Opaque => !transmissive,
+            DrawPass::Transmission => transmissive,
+        }
+    }
+}
+
+/// Counters for a single frame
+#[derive(Default, Copy, Clone, Debug)]
+pub struct FrameStats {
+    pub draw_calls: u32,
+    pub triangles: u64,
+    pub shader_switches: u32,
+}
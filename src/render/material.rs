@@ -2,9 +2,10 @@ use std::rc::Rc;
 use std::path::Path;
 
 use gltf;
+use serde_json::Value;
 
 use crate::render::math::*;
-use crate::render::{ Root };
+use crate::render::{ Root, Sampler, SheenLut };
 use crate::render::texture::Texture;
 use crate::shader::*;
 use crate::importdata::ImportData;
@@ -20,11 +21,17 @@ pub struct Material {
     pub roughness_factor: f32,
     pub metallic_roughness_texture: Option<Rc<Texture>>,
 
+    /// `KHR_materials_pbrSpecularGlossiness`: specular color (rgb) and glossiness (a), replacing
+    /// metallic and roughness. The diffuse factor and texture are stored as base color.
+    pub specular_glossiness_factor: Option<Vector4>,
+    pub specular_glossiness_texture: Option<Rc<Texture>>,
+
     pub normal_texture: Option<Rc<Texture>>,
     pub normal_scale: Option<f32>,
 
     pub occlusion_texture: Option<Rc<Texture>>,
     pub occlusion_strength: f32,
+    /// Including `KHR_materials_emissive_strength` (so it may exceed 1)
     pub emissive_factor: Vector3,
     pub emissive_texture: Option<Rc<Texture>>,
 
@@ -32,7 +39,60 @@ pub struct Material {
     pub alpha_mode: gltf::material::AlphaMode,
 
     pub double_sided: bool,
+    /// `KHR_materials_unlit`: only the base color is used
+    pub unlit: bool,
+
+    // KHR_materials_clearcoat (no clear coat layer if the factor is 0)
+    pub clearcoat_factor: f32,
+    pub clearcoat_texture: Option<Rc<Texture>>,
+    pub clearcoat_roughness_factor: f32,
+    pub clearcoat_roughness_texture: Option<Rc<Texture>>,
+    pub clearcoat_normal_texture: Option<Rc<Texture>>,
+    pub clearcoat_normal_scale: f32,
+
+    /// Index of refraction (`KHR_materials_ior`), determines the reflectance of dielectrics
+    pub ior: f32,
+
+    // KHR_materials_specular: strength (alpha channel of the texture) and color of dielectric reflections
+    pub specular_factor: f32,
+    pub specular_texture: Option<Rc<Texture>>,
+    pub specular_color_factor: Vector3,
+    pub specular_color_texture: Option<Rc<Texture>>,
 
+    // KHR_materials_sheen (no sheen if the color is black)
+    pub sheen_color_factor: Vector3,
+    pub sheen_color_texture: Option<Rc<Texture>>,
+    pub sheen_roughness_factor: f32,
+    pub sheen_roughness_texture: Option<Rc<Texture>>,
+    /// Directional albedo of the sheen (`None` without sheen)
+    pub sheen_lut: Option<Rc<SheenLut>>,
+
+    // KHR_materials_transmission (opaque if the factor is 0)
+    pub transmission_factor: f32,
+    pub transmission_texture: Option<Rc<Texture>>,
+
+    // KHR_materials_volume (thin-walled if the thickness is 0)
+    pub thickness_factor: f32,
+    pub thickness_texture: Option<Rc<Texture>>,
+    pub attenuation_distance: f32,
+    pub attenuation_color: Vector3,
+
+    /// UV transforms of the textures (`KHR_texture_transform`)
+    pub base_color_uv_transform: Matrix3,
+    pub metallic_roughness_uv_transform: Matrix3,
+    pub specular_glossiness_uv_transform: Matrix3,
+    pub normal_uv_transform: Matrix3,
+    pub occlusion_uv_transform: Matrix3,
+    pub emissive_uv_transform: Matrix3,
+    pub clearcoat_uv_transform: Matrix3,
+    pub clearcoat_roughness_uv_transform: Matrix3,
+    pub clearcoat_normal_uv_transform: Matrix3,
+    pub transmission_uv_transform: Matrix3,
+    pub thickness_uv_transform: Matrix3,
+    pub specular_uv_transform: Matrix3,
+    pub specular_color_uv_transform: Matrix3,
+    pub sheen_color_uv_transform: Matrix3,
+    pub sheen_roughness_uv_transform: Matrix3,
 }
 
 impl Material {
@@ -41,8 +101,11 @@ impl Material {
         root: &mut Root,
         imp: &ImportData,
         base_path: &Path
-    ) -> Material {
+    ) -> Result<Material, String> {
         let pbr = g_material.pbr_metallic_roughness();
+        // raw JSON, for extensions not supported by the `gltf` crate
+        let json = g_material.index().map_or(&Value::Null, |index| &imp.json["materials"][index]);
+        let emissive_strength = number(&json["extensions"]["KHR_materials_emissive_strength"]["emissiveStrength"], 1.0);
 
         let mut material = Material {
             index: g_material.index(),
@@ -54,45 +117,195 @@ impl Material {
             roughness_factor: pbr.roughness_factor(),
             metallic_roughness_texture: None,
 
+            specular_glossiness_factor: None,
+            specular_glossiness_texture: None,
+
             normal_texture: None,
             normal_scale: None,
 
             occlusion_texture: None,
             occlusion_strength: 0.0,
 
-            emissive_factor: g_material.emissive_factor().into(),
+            emissive_factor: Vector3::from(g_material.emissive_factor()) * emissive_strength,
             emissive_texture: None,
 
             alpha_cutoff: g_material.alpha_cutoff(),
             alpha_mode: g_material.alpha_mode(),
 
             double_sided: g_material.double_sided(),
+            unlit: g_material.unlit(),
+
+            clearcoat_factor: 0.0,
+            clearcoat_texture: None,
+            clearcoat_roughness_factor: 0.0,
+            clearcoat_roughness_texture: None,
+            clearcoat_normal_texture: None,
+            clearcoat_normal_scale: 1.0,
+
+            ior: 1.5,
+
+            specular_factor: 1.0,
+            specular_texture: None,
+            specular_color_factor: vec3(1.0, 1.0, 1.0),
+            specular_color_texture: None,
+
+            sheen_color_factor: vec3(0.0, 0.0, 0.0),
+            sheen_color_texture: None,
+            sheen_roughness_factor: 0.0,
+            sheen_roughness_texture: None,
+            sheen_lut: None,
+
+            transmission_factor: 0.0,
+            transmission_texture: None,
+
+            thickness_factor: 0.0,
+            thickness_texture: None,
+            attenuation_distance: f32::INFINITY,
+            attenuation_color: vec3(1.0, 1.0, 1.0),
+
+            base_color_uv_transform: Matrix3::identity(),
+            metallic_roughness_uv_transform: Matrix3::identity(),
+            specular_glossiness_uv_transform: Matrix3::identity(),
+            normal_uv_transform: Matrix3::identity(),
+            occlusion_uv_transform: Matrix3::identity(),
+            emissive_uv_transform: Matrix3::identity(),
+            clearcoat_uv_transform: Matrix3::identity(),
+            clearcoat_roughness_uv_transform: Matrix3::identity(),
+            clearcoat_normal_uv_transform: Matrix3::identity(),
+            transmission_uv_transform: Matrix3::identity(),
+            thickness_uv_transform: Matrix3::identity(),
+            specular_uv_transform: Matrix3::identity(),
+            specular_color_uv_transform: Matrix3::identity(),
+            sheen_color_uv_transform: Matrix3::identity(),
+            sheen_roughness_uv_transform: Matrix3::identity(),
         };
 
-        if let Some(color_info) = pbr.base_color_texture() {
-            material.base_color_texture = Some(
-                load_texture(&color_info.texture(), color_info.tex_coord(), root, imp, base_path));
-        }
-        if let Some(mr_info) = pbr.metallic_roughness_texture() {
-            material.metallic_roughness_texture = Some(
-                load_texture(&mr_info.texture(), mr_info.tex_coord(), root, imp, base_path));
+        // texture infos in the raw JSON, for KHR_texture_transform
+        let json_pbr = &json["pbrMetallicRoughness"];
+
+        // spec: the specular-glossiness properties take precedence over the metallic-roughness ones
+        let spec_gloss = &json["extensions"]["KHR_materials_pbrSpecularGlossiness"];
+        if spec_gloss.is_object() {
+            material.base_color_factor = vector4(&spec_gloss["diffuseFactor"], 1.0);
+            if let Some((texture, transform)) = load_texture_info(&spec_gloss["diffuseTexture"], true, root, imp, base_path)? {
+                material.base_color_texture = Some(texture);
+                material.base_color_uv_transform = transform;
+            }
+            let specular = vector3(&spec_gloss["specularFactor"], 1.0);
+            material.specular_glossiness_factor = Some(specular.extend(number(&spec_gloss["glossinessFactor"], 1.0)));
+            if let Some((texture, transform)) = load_texture_info(&spec_gloss["specularGlossinessTexture"], true, root, imp, base_path)? {
+                material.specular_glossiness_texture = Some(texture);
+                material.specular_glossiness_uv_transform = transform;
+            }
+        } else {
+            if let Some(color_info) = pbr.base_color_texture() {
+                let (tex_coord, transform) = texture_transform(&json_pbr["baseColorTexture"], color_info.tex_coord());
+                material.base_color_texture = Some(
+                    load_texture(&color_info.texture(), tex_coord, true, root, imp, base_path)?);
+                material.base_color_uv_transform = transform;
+            }
+            if let Some(mr_info) = pbr.metallic_roughness_texture() {
+                let (tex_coord, transform) = texture_transform(&json_pbr["metallicRoughnessTexture"], mr_info.tex_coord());
+                material.metallic_roughness_texture = Some(
+                    load_texture(&mr_info.texture(), tex_coord, false, root, imp, base_path)?);
+                material.metallic_roughness_uv_transform = transform;
+            }
         }
         if let Some(normal_texture) = g_material.normal_texture() {
+            let (tex_coord, transform) = texture_transform(&json["normalTexture"], normal_texture.tex_coord());
             material.normal_texture = Some(
-                load_texture(&normal_texture.texture(), normal_texture.tex_coord(), root, imp, base_path));
+                load_texture(&normal_texture.texture(), tex_coord, false, root, imp, base_path)?);
             material.normal_scale = Some(normal_texture.scale());
+            material.normal_uv_transform = transform;
         }
         if let Some(occ_texture) = g_material.occlusion_texture() {
+            let (tex_coord, transform) = texture_transform(&json["occlusionTexture"], occ_texture.tex_coord());
             material.occlusion_texture = Some(
-                load_texture(&occ_texture.texture(), occ_texture.tex_coord(), root, imp, base_path));
+                load_texture(&occ_texture.texture(), tex_coord, false, root, imp, base_path)?);
             material.occlusion_strength = occ_texture.strength();
+            material.occlusion_uv_transform = transform;
         }
         if let Some(em_info) = g_material.emissive_texture() {
+            let (tex_coord, transform) = texture_transform(&json["emissiveTexture"], em_info.tex_coord());
             material.emissive_texture = Some(
-                load_texture(&em_info.texture(), em_info.tex_coord(), root, imp, base_path));
+                load_texture(&em_info.texture(), tex_coord, true, root, imp, base_path)?);
+            material.emissive_uv_transform = transform;
+        }
+
+        let clearcoat = &json["extensions"]["KHR_materials_clearcoat"];
+        if clearcoat.is_object() {
+            material.clearcoat_factor = number(&clearcoat["clearcoatFactor"], 0.0);
+            material.clearcoat_roughness_factor = number(&clearcoat["clearcoatRoughnessFactor"], 0.0);
+            if let Some((texture, transform)) = load_texture_info(&clearcoat["clearcoatTexture"], false, root, imp, base_path)? {
+                material.clearcoat_texture = Some(texture);
+                material.clearcoat_uv_transform = transform;
+            }
+            if let Some((texture, transform)) = load_texture_info(&clearcoat["clearcoatRoughnessTexture"], false, root, imp, base_path)? {
+                material.clearcoat_roughness_texture = Some(texture);
+                material.clearcoat_roughness_uv_transform = transform;
+            }
+            let normal_info = &clearcoat["clearcoatNormalTexture"];
+            if let Some((texture, transform)) = load_texture_info(normal_info, false, root, imp, base_path)? {
+                material.clearcoat_normal_texture = Some(texture);
+                material.clearcoat_normal_uv_transform = transform;
+                material.clearcoat_normal_scale = number(&normal_info["scale"], 1.0);
+            }
+        }
+
+        material.ior = number(&json["extensions"]["KHR_materials_ior"]["ior"], 1.5);
+
+        let specular = &json["extensions"]["KHR_materials_specular"];
+        if specular.is_object() {
+            material.specular_factor = number(&specular["specularFactor"], 1.0);
+            material.specular_color_factor = vector3(&specular["specularColorFactor"], 1.0);
+            if let Some((texture, transform)) = load_texture_info(&specular["specularTexture"], false, root, imp, base_path)? {
+                material.specular_texture = Some(texture);
+                material.specular_uv_transform = transform;
+            }
+            if let Some((texture, transform)) = load_texture_info(&specular["specularColorTexture"], true, root, imp, base_path)? {
+                material.specular_color_texture = Some(texture);
+                material.specular_color_uv_transform = transform;
+            }
+        }
+
+        let sheen = &json["extensions"]["KHR_materials_sheen"];
+        if sheen.is_object() {
+            material.sheen_color_factor = vector3(&sheen["sheenColorFactor"], 0.0);
+            material.sheen_roughness_factor = number(&sheen["sheenRoughnessFactor"], 0.0);
+            if let Some((texture, transform)) = load_texture_info(&sheen["sheenColorTexture"], true, root, imp, base_path)? {
+                material.sheen_color_texture = Some(texture);
+                material.sheen_color_uv_transform = transform;
+            }
+            if let Some((texture, transform)) = load_texture_info(&sheen["sheenRoughnessTexture"], false, root, imp, base_path)? {
+                material.sheen_roughness_texture = Some(texture);
+                material.sheen_roughness_uv_transform = transform;
+            }
+            if material.has_sheen() {
+                material.sheen_lut = Some(root.sheen_lut());
+            }
         }
 
-        material
+        let transmission = &json["extensions"]["KHR_materials_transmission"];
+        if transmission.is_object() {
+            material.transmission_factor = number(&transmission["transmissionFactor"], 0.0);
+            if let Some((texture, transform)) = load_texture_info(&transmission["transmissionTexture"], false, root, imp, base_path)? {
+                material.transmission_texture = Some(texture);
+                material.transmission_uv_transform = transform;
+            }
+        }
+
+        let volume = &json["extensions"]["KHR_materials_volume"];
+        if volume.is_object() {
+            material.thickness_factor = number(&volume["thicknessFactor"], 0.0);
+            if let Some((texture, transform)) = load_texture_info(&volume["thicknessTexture"], false, root, imp, base_path)? {
+                material.thickness_texture = Some(texture);
+                material.thickness_uv_transform = transform;
+            }
+            material.attenuation_distance = number(&volume["attenuationDistance"], f32::INFINITY);
+            material.attenuation_color = vector3(&volume["attenuationColor"], 1.0);
+        }
+
+        Ok(material)
     }
 
     pub fn shader_flags(&self) -> ShaderFlags {
@@ -109,26 +322,194 @@ impl Material {
         if self.metallic_roughness_texture.is_some() {
             flags |= ShaderFlags::HAS_METALROUGHNESSMAP;
         }
+        if self.specular_glossiness_factor.is_some() {
+            flags |= ShaderFlags::HAS_SPECULARGLOSSINESS;
+            if self.specular_glossiness_texture.is_some() {
+                flags |= ShaderFlags::HAS_SPECULARGLOSSINESSMAP;
+            }
+        }
         if self.occlusion_texture.is_some() {
             flags |= ShaderFlags::HAS_OCCLUSIONMAP;
         }
+        if self.unlit {
+            flags |= ShaderFlags::UNLIT;
+        }
+        if self.clearcoat_factor > 0.0 {
+            flags |= ShaderFlags::HAS_CLEARCOAT;
+            if self.clearcoat_texture.is_some() {
+                flags |= ShaderFlags::HAS_CLEARCOATMAP;
+            }
+            if self.clearcoat_roughness_texture.is_some() {
+                flags |= ShaderFlags::HAS_CLEARCOATROUGHNESSMAP;
+            }
+            if self.clearcoat_normal_texture.is_some() {
+                flags |= ShaderFlags::HAS_CLEARCOATNORMALMAP;
+            }
+        }
+        if self.specular_texture.is_some() {
+            flags |= ShaderFlags::HAS_SPECULARMAP;
+        }
+        if self.specular_color_texture.is_some() {
+            flags |= ShaderFlags::HAS_SPECULARCOLORMAP;
+        }
+        if self.has_sheen() {
+            flags |= ShaderFlags::HAS_SHEEN;
+            if self.sheen_color_texture.is_some() {
+                flags |= ShaderFlags::HAS_SHEENCOLORMAP;
+            }
+            if self.sheen_roughness_texture.is_some() {
+                flags |= ShaderFlags::HAS_SHEENROUGHNESSMAP;
+            }
+        }
+        if self.transmission_factor > 0.0 {
+            flags |= ShaderFlags::HAS_TRANSMISSION;
+            if self.transmission_texture.is_some() {
+                flags |= ShaderFlags::HAS_TRANSMISSIONMAP;
+            }
+            // the volume extension only applies to transmissive materials
+            if self.thickness_factor > 0.0 {
+                flags |= ShaderFlags::HAS_VOLUME;
+                if self.thickness_texture.is_some() {
+                    flags |= ShaderFlags::HAS_THICKNESSMAP;
+                }
+            }
+        }
         flags
     }
 
+    pub fn has_sheen(&self) -> bool {
+        self.sheen_color_factor != vec3(0.0, 0.0, 0.0)
+    }
+
+    /// Number of texture coordinate sets needed by the textures (highest `texCoord` + 1)
+    pub fn num_tex_coord_sets(&self) -> u32 {
+        [
+            &self.base_color_texture,
+            &self.metallic_roughness_texture,
+            &self.specular_glossiness_texture,
+            &self.normal_texture,
+            &self.occlusion_texture,
+            &self.emissive_texture,
+            &self.clearcoat_texture,
+            &self.clearcoat_roughness_texture,
+            &self.clearcoat_normal_texture,
+            &self.transmission_texture,
+            &self.thickness_texture,
+            &self.specular_texture,
+            &self.specular_color_texture,
+            &self.sheen_color_texture,
+            &self.sheen_roughness_texture,
+        ].iter()
+            .filter_map(|texture| texture.as_ref())
+            .map(|texture| texture.tex_coord + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+}
+
+fn number(value: &Value, default: f32) -> f32 {
+    value.as_f64().map_or(default, |n| n as f32)
+}
+
+fn vector3(value: &Value, default: f32) -> Vector3 {
+    let component = |i: usize| number(&value[i], default);
+    vec3(component(0), component(1), component(2))
+}
+
+fn vector4(value: &Value, default: f32) -> Vector4 {
+    vector3(value, default).extend(number(&value[3], default))
+}
+
+/// Loads the texture of a texture info in the raw JSON (for material extensions)
+fn load_texture_info(
+    texture_info: &Value,
+    srgb: bool,
+    root: &mut Root,
+    imp: &ImportData,
+    base_path: &Path) -> Result<Option<(Rc<Texture>, Matrix3)>, String>
+{
+    let g_texture = match texture_info["index"].as_u64().and_then(|index| imp.doc.textures().nth(index as usize)) {
+        Some(g_texture) => g_texture,
+        None => return Ok(None),
+    };
+    let tex_coord = texture_info["texCoord"].as_u64().unwrap_or(0) as u32;
+    let (tex_coord, transform) = texture_transform(texture_info, tex_coord);
+    Ok(Some((load_texture(&g_texture, tex_coord, srgb, root, imp, base_path)?, transform)))
+}
+
+/// Texture coordinate set and UV transform of a texture info's `KHR_texture_transform`
+/// (the identity without it). The extension's `texCoord` overrides the texture info's.
+fn texture_transform(texture_info: &Value, tex_coord: u32) -> (u32, Matrix3) {
+    let extension = &texture_info["extensions"]["KHR_texture_transform"];
+    if extension.is_null() {
+        return (tex_coord, Matrix3::identity())
+    }
+    let (offset_x, offset_y) = (number(&extension["offset"][0], 0.0), number(&extension["offset"][1], 0.0));
+    let (scale_x, scale_y) = (number(&extension["scale"][0], 1.0), number(&extension["scale"][1], 1.0));
+    let (sin, cos) = number(&extension["rotation"], 0.0).sin_cos();
+    let tex_coord = extension["texCoord"].as_u64().map_or(tex_coord, |n| n as u32);
+    // translation * rotation * scale (column-major)
+    let transform = Matrix3::new(
+        cos * scale_x, -sin * scale_x, 0.0,
+        sin * scale_y, cos * scale_y, 0.0,
+        offset_x, offset_y, 1.0,
+    );
+    (tex_coord, transform)
 }
 
 fn load_texture(
     g_texture: &gltf::texture::Texture<'_>,
     tex_coord: u32,
+    srgb: bool,
     root: &mut Root,
     imp: &ImportData,
-    base_path: &Path) -> Rc<Texture>
+    base_path: &Path) -> Result<Rc<Texture>, String>
 {
-    if let Some(tex) = root.textures.iter().find(|tex| (***tex).index == g_texture.index()) {
-        return Rc::clone(tex)
+    // NOTE: the same image might be used as color and as data texture, which needs different formats
+    if let Some(tex) = root.textures.iter()
+        .find(|tex| tex.index == g_texture.index() && tex.srgb == srgb && tex.tex_coord == tex_coord)
+    {
+        return Ok(Rc::clone(tex))
     }
 
-    let texture = Rc::new(Texture::from_gltf(g_texture, tex_coord, imp, base_path));
+    let sampler = load_sampler(&g_texture.sampler(), root);
+    let image_sources = Texture::image_sources(g_texture, imp);
+    let texture = match root.textures.iter().find(|tex| tex.srgb == srgb && tex.image_sources == image_sources) {
+        Some(shared) => shared.share_image(g_texture, tex_coord, sampler),
+        None => Texture::from_gltf(g_texture, tex_coord, srgb, sampler, imp, base_path)?,
+    };
+    let texture = Rc::new(texture);
     root.textures.push(Rc::clone(&texture));
-    texture
+    Ok(texture)
+}
+
+/// Returns the sampler object of the glTF sampler, creating it on first use
+fn load_sampler(g_sampler: &gltf::texture::Sampler<'_>, root: &mut Root) -> Rc<Sampler> {
+    if let Some(sampler) = root.samplers.iter().find(|sampler| sampler.index == g_sampler.index()) {
+        return Rc::clone(sampler)
+    }
+    let sampler = Rc::new(Sampler::from_gltf(g_sampler));
+    root.samplers.push(Rc::clone(&sampler));
+    sampler
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn texture_transforms() {
+        assert_eq!(texture_transform(&json!({ "index": 0 }), 1), (1, Matrix3::identity()));
+
+        let info = json!({ "index": 0, "extensions": { "KHR_texture_transform": {
+            "offset": [0.5, 0.25], "rotation": std::f32::consts::FRAC_PI_2, "scale": [2, 4], "texCoord": 1
+        }}});
+        let (tex_coord, transform) = texture_transform(&info, 0);
+        assert_eq!(tex_coord, 1);
+        // rotated clockwise in UV space (counter-clockwise in image space)
+        let uv = transform * vec3(1.0, 0.0, 1.0);
+        assert!((uv - vec3(0.5, -1.75, 1.0)).magnitude() < 1e-6);
+    }
 }
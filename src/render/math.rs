@@ -14,11 +14,19 @@ pub type Vector4 = cgmath::Vector4<f32>;
 
 pub type Point3 = cgmath::Point3<f32>;
 
+pub type Matrix3 = cgmath::Matrix3<f32>;
 pub type Matrix4 = cgmath::Matrix4<f32>;
 pub type Quaternion = cgmath::Quaternion<f32>;
 
 pub type Aabb3 = collision::Aabb3<f32>;
 
+/// Union of all bounds (`None` if there are none)
+pub fn union_bounds(bounds: impl IntoIterator<Item = Aabb3>) -> Option<Aabb3> {
+    use collision::Union;
+    bounds.into_iter()
+        .fold(None, |union, bounds| Some(union.map_or(bounds, |union: Aabb3| union.union(&bounds))))
+}
+
 // A point's spherical coordinates, inspired by ThreeJS version
 pub struct Spherical {
     pub radius: f32,
@@ -1,12 +1,10 @@
 // use std::rc::Rc;
 use std::path::Path;
 
-use collision::{Aabb, Aabb3, Union};
-
 use gltf;
 
 use crate::render::math::*;
-use crate::render::{Primitive, Root};
+use crate::render::{DrawState, Primitive, Root, primitive_bounds};
 use crate::importdata::ImportData;
 
 pub struct Mesh {
@@ -15,8 +13,6 @@ pub struct Mesh {
     // TODO: weights
     // pub weights: Vec<Rc<?>>
     pub name: Option<String>,
-
-    pub bounds: Aabb3<f32>,
 }
 
 impl Mesh {
@@ -25,28 +21,30 @@ impl Mesh {
         root: &mut Root,
         imp: &ImportData,
         base_path: &Path,
-    ) -> Mesh {
-        let primitives: Vec<Primitive> = g_mesh.primitives()
+    ) -> Result<Mesh, String> {
+        let primitives = g_mesh.primitives()
             .enumerate()
             .map(|(i, g_prim)| {
                 Primitive::from_gltf(&g_prim, i, g_mesh.index(), root, imp, base_path)
             })
-            .collect();
-
-        let bounds = primitives.iter()
-            .fold(Aabb3::zero(), |bounds, prim| prim.bounds.union(&bounds));
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Mesh {
+        Ok(Mesh {
             index: g_mesh.index(),
             primitives,
             name: g_mesh.name().map(|s| s.into()),
-            bounds,
-        }
+        })
+    }
+
+    /// Union of the primitive bounds (see `primitive_bounds`), without loading the mesh.
+    /// Primitives without positions are skipped.
+    pub fn bounds_from_gltf(g_mesh: &gltf::Mesh<'_>) -> Option<Aabb3> {
+        union_bounds(g_mesh.primitives().filter_map(|g_primitive| primitive_bounds(&g_primitive)))
     }
 
-    pub fn draw(&self, model_matrix: &Matrix4, mvp_matrix: &Matrix4, camera_position: &Vector3) {
+    pub fn draw(&self, model_matrix: &Matrix4, mvp_matrix: &Matrix4, camera_position: &Vector3, state: &mut DrawState) {
         for primitive in &self.primitives {
-            unsafe { primitive.draw(model_matrix, mvp_matrix, camera_position) }
+            unsafe { primitive.draw(model_matrix, mvp_matrix, camera_position, state) }
         }
     }
 }
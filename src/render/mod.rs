@@ -11,11 +11,20 @@ mod mesh;
 pub use self::mesh::*;
 mod primitive;
 pub use self::primitive::*;
+mod vertex;
+pub use self::vertex::*;
 
 mod material;
 pub use self::material::*;
 mod texture;
 pub use self::texture::*;
+mod sampler;
+pub use self::sampler::*;
+mod sheen_lut;
+pub use self::sheen_lut::*;
 
 mod camera;
 pub use self::camera::*;
+
+mod draw_state;
+pub use self::draw_state::*;
@@ -3,12 +3,12 @@ use std::path::Path;
 
 use gltf;
 
-use collision::{Aabb, Union};
+use collision::Aabb;
 
 use crate::controls::CameraParams;
 use crate::render::math::*;
 use crate::render::mesh::Mesh;
-use crate::render::Root;
+use crate::render::{DrawState, Root};
 use crate::render::camera::Camera;
 use crate::importdata::ImportData;
 
@@ -25,7 +25,6 @@ pub struct Node {
     pub name: Option<String>,
 
     pub final_transform: Matrix4, // including parent transforms
-    pub bounds: Aabb3,
 }
 
 
@@ -35,10 +34,8 @@ impl Node {
         root: &mut Root,
         imp: &ImportData,
         base_path: &Path
-    ) -> Node {
-        let (trans, rot, scale) = g_node.transform().decomposed();
-        let r = rot;
-        let rotation = Quaternion::new(r[3], r[0], r[1], r[2]); // NOTE: different element order!
+    ) -> Result<Node, String> {
+        let (translation, rotation, scale) = decomposed_transform(g_node);
 
         let mut mesh = None;
         if let Some(g_mesh) = g_node.mesh() {
@@ -47,7 +44,7 @@ impl Node {
             }
 
             if mesh.is_none() { // not using else due to borrow-checking madness
-                mesh = Some(Rc::new(Mesh::from_gltf(&g_mesh, root, imp, base_path)));
+                mesh = Some(Rc::new(Mesh::from_gltf(&g_mesh, root, imp, base_path)?));
                 root.meshes.push(mesh.clone().unwrap());
             }
         }
@@ -55,20 +52,18 @@ impl Node {
                 .map(|g_node| g_node.index())
                 .collect();
 
-        Node {
+        Ok(Node {
             index: g_node.index(),
             children,
             mesh,
             rotation,
-            scale: scale.into(),
-            translation: trans.into(),
+            scale,
+            translation,
             camera: g_node.camera().as_ref().map(Camera::from_gltf),
             name: g_node.name().map(|s| s.into()),
 
             final_transform: Matrix4::identity(),
-
-            bounds: Aabb3::zero(),
-        }
+        })
     }
 
     pub fn update_transform(&mut self, root: &mut Root, parent_transform: &Matrix4) {
@@ -76,9 +71,7 @@ impl Node {
 
         // TODO: cache local tranform when adding animations?
         self.final_transform = self.final_transform *
-            Matrix4::from_translation(self.translation) *
-            Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z) *
-            Matrix4::from(self.rotation);
+            local_transform(self.translation, self.rotation, self.scale);
 
         for node_id in &self.children {
             let node = root.unsafe_get_node_mut(*node_id);
@@ -86,30 +79,41 @@ impl Node {
         }
     }
 
-    /// Should be called after update_transforms
-    pub fn update_bounds(&mut self, root: &mut Root) {
-        self.bounds = Aabb3::zero();
-        if let Some(ref mesh) = self.mesh {
-            self.bounds = mesh.bounds
-                .transform(&self.final_transform);
-        }
-
-        for node_id in &self.children {
-            let node = root.unsafe_get_node_mut(*node_id);
-            node.update_bounds(root);
-            self.bounds = self.bounds.union(&node.bounds);
-        }
+    /// Bounds of the node and its children (including `parent_transform`), without loading the meshes
+    /// (`None` if there are no positions)
+    pub fn bounds_from_gltf(g_node: &gltf::Node<'_>, parent_transform: &Matrix4) -> Option<Aabb3> {
+        let (translation, rotation, scale) = decomposed_transform(g_node);
+        let transform = parent_transform * local_transform(translation, rotation, scale);
+
+        let bounds = g_node.mesh()
+            .and_then(|g_mesh| Mesh::bounds_from_gltf(&g_mesh))
+            .map(|bounds| bounds.transform(&transform));
+        let child_bounds = g_node.children()
+            .filter_map(|g_child| Node::bounds_from_gltf(&g_child, &transform));
+        union_bounds(bounds.into_iter().chain(child_bounds))
     }
 
-    pub fn draw(&mut self, root: &mut Root, cam_params: &CameraParams) {
+    pub fn draw(&mut self, root: &mut Root, cam_params: &CameraParams, state: &mut DrawState) {
         if let Some(ref mesh) = self.mesh {
             let mvp_matrix = cam_params.projection_matrix * cam_params.view_matrix * self.final_transform;
 
-            (*mesh).draw(&self.final_transform, &mvp_matrix, &cam_params.position);
+            (*mesh).draw(&self.final_transform, &mvp_matrix, &cam_params.position, state);
         }
         for node_id in &self.children {
             let node = root.unsafe_get_node_mut(*node_id);
-            node.draw(root, cam_params);
+            node.draw(root, cam_params, state);
         }
     }
 }
+
+fn decomposed_transform(g_node: &gltf::Node<'_>) -> (Vector3, Quaternion, Vector3) {
+    let (translation, r, scale) = g_node.transform().decomposed();
+    let rotation = Quaternion::new(r[3], r[0], r[1], r[2]); // NOTE: different element order!
+    (translation.into(), rotation, scale.into())
+}
+
+fn local_transform(translation: Vector3, rotation: Quaternion, scale: Vector3) -> Matrix4 {
+    Matrix4::from_translation(translation) *
+        Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z) *
+        Matrix4::from(rotation)
+}
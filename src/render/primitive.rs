@@ -1,45 +1,25 @@
+use std::cell::Cell;
 use std::mem::size_of;
 use std::os::raw::c_void;
 use std::path::Path;
 use std::ptr;
 use std::rc::Rc;
+use std::slice;
 
 use gl;
 use gl::types::GLenum;
 use gltf;
+use gltf::Semantic;
 use log::{warn, debug};
 
+use crate::draco;
 use crate::render::math::*;
-use crate::render::{Material, Root};
+use crate::render::{DrawState, Material, Root, VertexAttribute};
 use crate::shader::*;
 use crate::importdata::ImportData;
 
-#[derive(Debug)]
-pub struct Vertex {
-    pub position: Vector3,
-    pub normal: Vector3,
-    pub tangent: Vector4,
-    pub tex_coord_0: Vector2,
-    pub tex_coord_1: Vector2,
-    pub color_0: Vector4,
-    pub joints_0: [u16; 4],
-    pub weights_0: Vector4,
-}
-
-impl Default for Vertex {
-    fn default() -> Self {
-        Vertex {
-            position: Vector3::zero(),
-            normal: Vector3::zero(),
-            tangent: Vector4::zero(),
-            tex_coord_0: Vector2::zero(),
-            tex_coord_1: Vector2::zero(),
-            color_0: Vector4::zero(),
-            joints_0: [0; 4],
-            weights_0: Vector4::zero(),
-        }
-    }
-}
+/// Maximum number of texture coordinate sets (vertex attribute locations 8-15)
+pub const MAX_TEX_COORD_SETS: usize = 8;
 
 #[derive(Clone, Debug)]
 pub struct Texture {
@@ -49,14 +29,18 @@ pub struct Texture {
 }
 
 pub struct Primitive {
-    pub bounds: Aabb3,
-
     vao: u32,
+    /// All vertex attributes, one after another
     vbo: u32,
     num_vertices: u32,
 
     ebo: Option<u32>,
     num_indices: u32,
+    /// UNSIGNED_SHORT or UNSIGNED_INT
+    index_type: GLenum,
+
+    /// Size of the vertex and index buffers in bytes
+    pub memory_size: usize,
 
     mode: GLenum,
 
@@ -64,13 +48,18 @@ pub struct Primitive {
 
     pbr_shader: Rc<PbrShader>,
 
+    /// `KHR_materials_variants` mappings: variant indices with their material and shader
+    variants: Vec<(Vec<usize>, Rc<Material>, Rc<PbrShader>)>,
+    /// Index into `variants` of the selected variant's mapping (`None`: default material)
+    active_variant: Cell<Option<usize>>,
+
     // TODO!: mode, targets
 }
 
 impl Primitive {
     pub fn new(
-        bounds: Aabb3,
-        vertices: &[Vertex],
+        num_vertices: usize,
+        attributes: &[VertexAttribute],
         indices: Option<Vec<u32>>,
         mode: GLenum,
         material: Rc<Material>,
@@ -78,17 +67,20 @@ impl Primitive {
     ) -> Primitive {
         let num_indices = indices.as_ref().map(|i| i.len()).unwrap_or(0);
         let mut prim = Primitive {
-            bounds,
-            num_vertices: vertices.len() as u32,
+            num_vertices: num_vertices as u32,
             num_indices: num_indices as u32,
             vao: 0, vbo: 0, ebo: None,
+            index_type: gl::UNSIGNED_INT,
+            memory_size: 0,
             mode,
             material,
             pbr_shader: shader,
+            variants: Vec::new(),
+            active_variant: Cell::new(None),
         };
 
         // now that we have all the required data, set the vertex buffers and its attribute pointers.
-        unsafe { prim.setup_primitive(vertices, indices) }
+        unsafe { prim.setup_primitive(attributes, indices) }
         prim
     }
 
@@ -98,212 +90,328 @@ impl Primitive {
         mesh_index: usize,
         root: &mut Root,
         imp: &ImportData,
-        base_path: &Path) -> Primitive
+        base_path: &Path) -> Result<Primitive, String>
     {
         let buffers = &imp.buffers;
         let reader = g_primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-        let positions = {
-            let iter = reader
-                .read_positions()
-                .unwrap_or_else(||
-                    panic!("primitives must have the POSITION attribute (mesh: {}, primitive: {})",
-                        mesh_index, primitive_index)
-                );
-            iter.collect::<Vec<_>>()
+        let draco_extension = &imp.json["meshes"][mesh_index]["primitives"][primitive_index]
+            ["extensions"]["KHR_draco_mesh_compression"];
+        let draco_mesh = if draco_extension.is_null() { None } else {
+            let has_fallback = g_primitive.attributes().all(|(_, accessor)| accessor.view().is_some())
+                && g_primitive.indices().is_none_or(|accessor| accessor.view().is_some());
+            match decode_draco(draco_extension, imp) {
+                Ok(mesh) => Some(mesh),
+                Err(err) if has_fallback => {
+                    warn!("Failed to decode the Draco compressed primitive {} of mesh {} ({}), using the uncompressed data",
+                        primitive_index, mesh_index, err);
+                    None
+                }
+                Err(err) => return Err(format!("failed to decode the Draco compressed primitive {} of mesh {}: {}",
+                    primitive_index, mesh_index, err)),
+            }
         };
-
-        let bounds = g_primitive.bounding_box();
-        let bounds = Aabb3 {
-            min: bounds.min.into(),
-            max: bounds.max.into()
+        // NOTE: attributes are kept in their source format, also when quantized (KHR_mesh_quantization)
+        let attribute = |semantic: Semantic, location: u32| {
+            let accessor = g_primitive.get(&semantic)?;
+            if let Some(ref mesh) = draco_mesh {
+                // attributes that aren't compressed are read from their accessor
+                let id = draco_extension["attributes"][semantic.to_string()].as_u64();
+                if let Some(decoded) = id.and_then(|id| mesh.attributes.iter().find(|a| u64::from(a.unique_id) == id)) {
+                    return Some(VertexAttribute::from_draco(location, &accessor, decoded))
+                }
+            }
+            Some(VertexAttribute::from_accessor(location, &accessor, buffers))
         };
 
-        let mut vertices: Vec<Vertex> = positions
-            .into_iter()
-            .map(|position| {
-                Vertex {
-                    position: Vector3::from(position),
-                    ..Vertex::default()
-                }
-            }).collect();
+        let mut positions = attribute(Semantic::Positions, 0)
+            .unwrap_or_else(||
+                panic!("primitives must have the POSITION attribute (mesh: {}, primitive: {})",
+                    mesh_index, primitive_index)
+            );
 
         let mut shader_flags = ShaderFlags::empty();
 
-        // normals
-        if let Some(normals) = reader.read_normals() {
-            for (i, normal) in normals.enumerate() {
-                vertices[i].normal = Vector3::from(normal);
+        // NOTE: positions, normals and tangents might be processed here (flat normals, tangent generation),
+        // the other attributes are read afterwards in the resulting vertex order.
+        let mut normals = attribute(Semantic::Normals, 1);
+        let has_normals = normals.is_some();
+        let mut tangents = attribute(Semantic::Tangents, 2);
+        let has_tangents = tangents.is_some();
+
+        let mut indices = match draco_mesh {
+            Some(ref mesh) => Some(mesh.indices.clone()),
+            None => reader
+                .read_indices()
+                .map(|read_indices| {
+                    read_indices.into_u32().collect::<Vec<_>>()
+                }),
+        };
+        // glTF vertex of each vertex, if the primitive had to be de-indexed
+        let mut remap: Option<Vec<u32>> = None;
+
+        let mut mode = g_primitive.mode().as_gl_enum();
+
+        // spec: When normals are not specified, client implementations should calculate flat normals.
+        if !has_normals {
+            if let gl::TRIANGLES | gl::TRIANGLE_STRIP | gl::TRIANGLE_FAN = mode {
+                debug!("Found no NORMALs for primitive {} of mesh {} - calculating flat normals",
+                    primitive_index, mesh_index);
+                let triangles = triangle_list(mode, indices.as_deref(), positions.count());
+                positions = positions.remap(&triangles);
+                let flat_normals = generate_flat_normals(&vec3s(&positions));
+                normals = Some(VertexAttribute::new(1, 3, gl::FLOAT, false, &flat_normals));
+                tangents = tangents.map(|tangents| tangents.remap(&triangles));
+                remap = Some(triangles);
+                indices = None;
+                mode = gl::TRIANGLES;
             }
-            shader_flags |= ShaderFlags::HAS_NORMALS;
         }
-        else {
-            debug!("Found no NORMALs for primitive {} of mesh {} \
-                   (flat normal calculation not implemented yet)", primitive_index, mesh_index);
+
+        let material = Self::load_material(&g_primitive.material(), root, imp, base_path)?;
+
+        // KHR_materials_variants: materials replacing the default one when one of the variants is selected
+        let mut variant_materials = Vec::new();
+        let mappings = &imp.json["meshes"][mesh_index]["primitives"][primitive_index]
+            ["extensions"]["KHR_materials_variants"]["mappings"];
+        for mapping in mappings.as_array().into_iter().flatten() {
+            let g_material = mapping["material"].as_u64()
+                .and_then(|index| imp.doc.materials().nth(index as usize));
+            let g_material = match g_material {
+                Some(g_material) => g_material,
+                None => {
+                    warn!("Ignoring variant mapping with invalid material (mesh: {}, primitive: {})",
+                        mesh_index, primitive_index);
+                    continue
+                }
+            };
+            let variants = mapping["variants"].as_array().into_iter().flatten()
+                .filter_map(|variant| variant.as_u64())
+                .map(|variant| variant as usize)
+                .collect::<Vec<_>>();
+            variant_materials.push((variants, Self::load_material(&g_material, root, imp, base_path)?));
         }
+        let materials = || Some(&material).into_iter().chain(variant_materials.iter().map(|(_, mat)| mat));
 
-        // tangents
-        if let Some(tangents) = reader.read_tangents() {
-            for (i, tangent) in tangents.enumerate() {
-                vertices[i].tangent = Vector4::from(tangent);
+        // spec: Implementation note: When the 'mode' property is set to a non-triangular type
+        // (such as POINTS or LINES) some additional considerations must be taken while
+        // considering the proper rendering technique:
+        //   For LINES with NORMAL and TANGENT properties can render with standard lighting including normal maps.
+        //   For all POINTS or LINES with no TANGENT property, render with standard lighting but ignore any normal maps on the material.
+        //   For POINTS or LINES with no NORMAL property, don't calculate lighting and instead output the COLOR value for each pixel drawn.
+        let mut ignore_normal_maps = false;
+        if let gl::POINTS | gl::LINES | gl::LINE_STRIP | gl::LINE_LOOP = mode {
+            ignore_normal_maps = !has_tangents;
+            if !has_normals {
+                shader_flags |= ShaderFlags::UNLIT;
             }
-            shader_flags |= ShaderFlags::HAS_TANGENTS;
-        }
-        else {
-            debug!("Found no TANGENTS for primitive {} of mesh {} \
-                   (tangent calculation not implemented yet)", primitive_index, mesh_index);
-        }
-
-        // texture coordinates
-        let mut tex_coord_set = 0;
-        while let Some(tex_coords) = reader.read_tex_coords(tex_coord_set) {
-            if tex_coord_set > 1 {
-                warn!("Ignoring texture coordinate set {}, \
-                        only supporting 2 sets at the moment. (mesh: {}, primitive: {})",
-                        tex_coord_set, mesh_index, primitive_index);
-                tex_coord_set += 1;
-                continue;
+            if mode != gl::POINTS {
+                shader_flags |= ShaderFlags::WIDE_LINES;
             }
-            for (i, tex_coord) in tex_coords.into_f32().enumerate() {
-                match tex_coord_set {
-                    0 => vertices[i].tex_coord_0 = Vector2::from(tex_coord),
-                    1 => vertices[i].tex_coord_1 = Vector2::from(tex_coord),
-                    _ => unreachable!()
+        }
+
+        // spec: When tangents are not specified, client implementations should calculate tangents using
+        // default MikkTSpace algorithms with the specified vertex positions, normals, and texture coordinates
+        // associated with the normal texture.
+        let normal_texture = materials().find_map(|mat| mat.normal_texture.as_ref());
+        if let (Some(normal_texture), Some(vertex_normals), None) = (normal_texture, normals.as_ref(), tangents.as_ref()) {
+            if let gl::TRIANGLES | gl::TRIANGLE_STRIP | gl::TRIANGLE_FAN = mode {
+                if let Some(tex_coords) = attribute(Semantic::TexCoords(normal_texture.tex_coord), 0) {
+                    debug!("Found no TANGENTs for primitive {} of mesh {} - calculating MikkTSpace tangents",
+                        primitive_index, mesh_index);
+                    let tex_coords = match remap { Some(ref remap) => tex_coords.remap(remap), None => tex_coords };
+                    let triangles = triangle_list(mode, indices.as_deref(), positions.count());
+                    let triangle_positions = positions.remap(&triangles);
+                    let triangle_normals = vertex_normals.remap(&triangles);
+                    let generated = generate_tangents(
+                        &vec3s(&triangle_positions),
+                        &vec3s(&triangle_normals),
+                        &vec2s(&tex_coords.remap(&triangles)));
+                    if let Some(generated) = generated {
+                        positions = triangle_positions;
+                        normals = Some(triangle_normals);
+                        tangents = Some(VertexAttribute::new(2, 4, gl::FLOAT, false, &generated));
+                        remap = Some(match remap { Some(ref remap) => deindex(remap, &triangles), None => triangles });
+                        indices = None;
+                        mode = gl::TRIANGLES;
+                    } else {
+                        warn!("Tangent generation failed for primitive {} of mesh {}",
+                            primitive_index, mesh_index);
+                    }
+                } else {
+                    warn!("Cannot calculate tangents for primitive {} of mesh {}: missing TEXCOORD_{} of the normal texture",
+                        primitive_index, mesh_index, normal_texture.tex_coord);
                 }
             }
-            shader_flags |= ShaderFlags::HAS_UV;
-            tex_coord_set += 1;
         }
 
-        // colors
-        if let Some(colors) = reader.read_colors(0) {
-            let colors = colors.into_rgba_f32();
-            for (i, c) in colors.enumerate() {
-                vertices[i].color_0 = c.into();
-            }
-            shader_flags |= ShaderFlags::HAS_COLORS;
+        let remapped = |attribute: VertexAttribute| match remap {
+            Some(ref remap) => attribute.remap(remap),
+            None => attribute,
+        };
+
+        let num_vertices = positions.count();
+        let mut attributes = vec![positions];
+        if let Some(normals) = normals {
+            attributes.push(normals);
+            shader_flags |= ShaderFlags::HAS_NORMALS;
         }
-        if reader.read_colors(1).is_some() {
-            warn!("Ignoring further color attributes, only supporting COLOR_0. (mesh: {}, primitive: {})",
-                mesh_index, primitive_index);
+        if let Some(tangents) = tangents {
+            attributes.push(tangents);
+            shader_flags |= ShaderFlags::HAS_TANGENTS;
         }
 
-        if let Some(joints) = reader.read_joints(0) {
-            for (i, joint) in joints.into_u16().enumerate() {
-                vertices[i].joints_0 = joint;
+        // texture coordinates: only the sets referenced by the materials' textures are uploaded
+        let mut num_tex_coord_sets = materials().map(|mat| mat.num_tex_coord_sets()).max().unwrap_or(0);
+        if num_tex_coord_sets as usize > MAX_TEX_COORD_SETS {
+            warn!("Ignoring texture coordinate sets above TEXCOORD_{}, only supporting {} sets. (mesh: {}, primitive: {})",
+                MAX_TEX_COORD_SETS - 1, MAX_TEX_COORD_SETS, mesh_index, primitive_index);
+            num_tex_coord_sets = MAX_TEX_COORD_SETS as u32;
+        }
+        for set in 0..num_tex_coord_sets {
+            if let Some(tex_coords) = attribute(Semantic::TexCoords(set), 8 + set) {
+                attributes.push(remapped(tex_coords));
+            } else {
+                // the attribute stays disabled, i.e. (0, 0)
+                warn!("Material of primitive {} of mesh {} uses TEXCOORD_{}, which the primitive doesn't have",
+                    primitive_index, mesh_index, set);
             }
         }
-        if reader.read_joints(1).is_some() {
-            warn!("Ignoring further joint attributes, only supporting JOINTS_0. (mesh: {}, primitive: {})",
-                mesh_index, primitive_index);
+        if num_tex_coord_sets > 0 {
+            shader_flags |= ShaderFlags::HAS_UV;
         }
 
-        if let Some(weights) = reader.read_weights(0) {
-            for (i, weights) in weights.into_f32().enumerate() {
-                vertices[i].weights_0 = weights.into();
-            }
+        // colors
+        if let Some(colors) = attribute(Semantic::Colors(0), 5) {
+            attributes.push(remapped(colors));
+            shader_flags |= ShaderFlags::HAS_COLORS;
         }
-        if reader.read_weights(1).is_some() {
-            warn!("Ignoring further weight attributes, only supporting WEIGHTS_0. (mesh: {}, primitive: {})",
-                mesh_index, primitive_index);
+        // spec: only COLOR_0 is used for shading (as multiplier of the base color),
+        // the meaning of further sets is application-specific
+        let mut color_set = 1;
+        while g_primitive.get(&Semantic::Colors(color_set)).is_some() {
+            debug!("COLOR_{} of primitive {} of mesh {} is not used for shading",
+                color_set, primitive_index, mesh_index);
+            color_set += 1;
         }
 
-        let indices = reader
-            .read_indices()
-            .map(|read_indices| {
-                read_indices.into_u32().collect::<Vec<_>>()
-            });
+        // NOTE: JOINTS_0/WEIGHTS_0 (locations 6/7) aren't uploaded until skinning is implemented
 
-        // TODO: spec:
-        // Implementation note: When the 'mode' property is set to a non-triangular type
-        //(such as POINTS or LINES) some additional considerations must be taken while
-        //considering the proper rendering technique:
-        //   For LINES with NORMAL and TANGENT properties can render with standard lighting including normal maps.
-        //   For all POINTS or LINES with no TANGENT property, render with standard lighting but ignore any normal maps on the material.
-        //   For POINTS or LINES with no NORMAL property, don't calculate lighting and instead output the COLOR value for each pixel drawn.
-        let mode = g_primitive.mode().as_gl_enum();
-
-        let g_material = g_primitive.material();
+        let shader = |material: &Material, root: &mut Root| {
+            let mut flags = shader_flags | material.shader_flags();
+            if ignore_normal_maps {
+                flags.remove(ShaderFlags::HAS_NORMALMAP | ShaderFlags::HAS_CLEARCOATNORMALMAP);
+            }
+            Rc::clone(root.shaders.entry((flags, num_tex_coord_sets))
+                .or_insert_with(|| PbrShader::new(flags, num_tex_coord_sets).into()))
+        };
+        let pbr_shader = shader(&material, root);
+        let variants = variant_materials.iter()
+            .map(|(variants, mat)| (variants.clone(), Rc::clone(mat), shader(mat, root)))
+            .collect();
+
+        let mut primitive = Primitive::new(num_vertices, &attributes, indices, mode, material, pbr_shader);
+        primitive.variants = variants;
+        Ok(primitive)
+    }
 
-        let mut material = None;
+    /// Returns the already loaded material or loads it
+    fn load_material(g_material: &gltf::Material<'_>, root: &mut Root, imp: &ImportData, base_path: &Path)
+        -> Result<Rc<Material>, String>
+    {
         if let Some(mat) = root.materials.iter().find(|m| (***m).index == g_material.index()) {
-            material = Rc::clone(mat).into()
+            return Ok(Rc::clone(mat))
         }
+        let mat = Rc::new(Material::from_gltf(g_material, root, imp, base_path)?);
+        root.materials.push(Rc::clone(&mat));
+        Ok(mat)
+    }
 
-        if material.is_none() { // no else due to borrow checker madness
-            let mat = Rc::new(Material::from_gltf(&g_material, root, imp, base_path));
-            root.materials.push(Rc::clone(&mat));
-            material = Some(mat);
-        };
-        let material = material.unwrap();
-        shader_flags |= material.shader_flags();
-
-        let mut new_shader = false; // borrow checker workaround
-        let shader =
-            if let Some(shader) = root.shaders.get(&shader_flags) {
-                Rc::clone(shader)
-            }
-            else {
-                new_shader = true;
-                PbrShader::new(shader_flags).into()
+    /// Selects the material of the given `KHR_materials_variants` variant (`None`: default material).
+    /// Primitives without a mapping for the variant keep their default material.
+    pub fn select_variant(&self, variant: Option<usize>) {
+        self.active_variant.set(variant.and_then(|variant|
+            self.variants.iter().position(|(variants, _, _)| variants.contains(&variant))));
+    }
 
-            };
-        if new_shader {
-            root.shaders.insert(shader_flags, Rc::clone(&shader));
+    /// Material and shader of the selected variant
+    pub fn active_material(&self) -> (&Rc<Material>, &Rc<PbrShader>) {
+        match self.active_variant.get() {
+            Some(index) => (&self.variants[index].1, &self.variants[index].2),
+            None => (&self.material, &self.pbr_shader),
         }
-
-        Primitive::new(bounds, &vertices, indices, mode, material, shader)
     }
 
     /// render the mesh
-    pub unsafe fn draw(&self, model_matrix: &Matrix4, mvp_matrix: &Matrix4, camera_position: &Vector3) {
+    pub unsafe fn draw(&self, model_matrix: &Matrix4, mvp_matrix: &Matrix4, camera_position: &Vector3, state: &mut DrawState) {
         // TODO!: determine if shader+material already active to reduce work...
+        let (material, pbr_shader) = self.active_material();
+        if !state.pass.includes(material.transmission_factor > 0.0) {
+            return
+        }
 
-        if self.material.double_sided {
+        if material.double_sided {
             gl::Disable(gl::CULL_FACE);
         } else {
             gl::Enable(gl::CULL_FACE);
         }
 
         if self.mode == gl::POINTS {
-            gl::PointSize(10.0);
+            gl::PointSize(state.point_size);
         }
 
-        self.configure_shader(model_matrix, mvp_matrix, camera_position);
+        self.configure_shader(model_matrix, mvp_matrix, camera_position, state);
+
+        if pbr_shader.flags.contains(ShaderFlags::WIDE_LINES) {
+            let (width, height) = state.viewport_size;
+            let shader = &pbr_shader.shader;
+            shader.set_vec2(pbr_shader.uniforms.u_ViewportSize, width, height);
+            shader.set_float(pbr_shader.uniforms.u_LineWidth, state.line_width);
+        }
 
         // draw mesh
         gl::BindVertexArray(self.vao);
         if self.ebo.is_some() {
-            gl::DrawElements(self.mode, self.num_indices as i32, gl::UNSIGNED_INT, ptr::null());
+            gl::DrawElements(self.mode, self.num_indices as i32, self.index_type, ptr::null());
         }
         else {
             gl::DrawArrays(self.mode, 0, self.num_vertices as i32)
         }
+        state.stats.draw_calls += 1;
+        state.stats.triangles += u64::from(self.num_triangles());
 
         gl::BindVertexArray(0);
         gl::ActiveTexture(gl::TEXTURE0);
 
-        if self.material.alpha_mode != gltf::material::AlphaMode::Opaque {
-            let shader = &self.pbr_shader.shader;
+        if material.alpha_mode != gltf::material::AlphaMode::Opaque {
+            let shader = &pbr_shader.shader;
 
             gl::Disable(gl::BLEND);
-            shader.set_float(self.pbr_shader.uniforms.u_AlphaBlend, 0.0);
-            if self.material.alpha_mode == gltf::material::AlphaMode::Mask {
-                shader.set_float(self.pbr_shader.uniforms.u_AlphaCutoff, 0.0);
+            shader.set_float(pbr_shader.uniforms.u_AlphaBlend, 0.0);
+            if material.alpha_mode == gltf::material::AlphaMode::Mask {
+                shader.set_float(pbr_shader.uniforms.u_AlphaCutoff, 0.0);
             }
         }
     }
 
     unsafe fn configure_shader(&self, model_matrix: &Matrix4,
-        mvp_matrix: &Matrix4, camera_position: &Vector3)
+        mvp_matrix: &Matrix4, camera_position: &Vector3, state: &mut DrawState)
     {
         // let pbr_shader = &Rc::get_mut(&mut self.pbr_shader).unwrap();
-        let mat = &self.material;
-        let shader = &self.pbr_shader.shader;
-        let uniforms = &self.pbr_shader.uniforms;
-        self.pbr_shader.shader.use_program();
+        let (mat, pbr_shader) = self.active_material();
+        let shader = &pbr_shader.shader;
+        let uniforms = &pbr_shader.uniforms;
+        if state.current_shader != shader.id {
+            shader.use_program();
+            state.current_shader = shader.id;
+            state.stats.shader_switches += 1;
+        }
 
         // camera params
         shader.set_mat4(uniforms.u_ModelMatrix, model_matrix);
+        // inverse transpose, so that normals stay correct with non-uniform scale
+        // (e.g. node transforms that dequantize KHR_mesh_quantization positions)
+        let normal_matrix = model_matrix.invert().unwrap_or(*model_matrix).transpose();
+        shader.set_mat4(uniforms.u_NormalMatrix, &normal_matrix);
         shader.set_mat4(uniforms.u_MVPMatrix, mvp_matrix);
         shader.set_vector3(uniforms.u_Camera, camera_position);
 
@@ -322,40 +430,136 @@ impl Primitive {
         // NOTE: for sampler numbers, see also PbrShader constructor
         shader.set_vector4(uniforms.u_BaseColorFactor, &mat.base_color_factor);
         if let Some(ref base_color_texture) = mat.base_color_texture {
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, base_color_texture.id);
+            base_color_texture.bind(0);
             shader.set_int(uniforms.u_BaseColorTexCoord, base_color_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_BaseColorUVTransform, &mat.base_color_uv_transform);
         }
         if let Some(ref normal_texture) = mat.normal_texture {
-            gl::ActiveTexture(gl::TEXTURE1);
-            gl::BindTexture(gl::TEXTURE_2D, normal_texture.id);
+            normal_texture.bind(1);
             shader.set_int(uniforms.u_NormalTexCoord, normal_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_NormalUVTransform, &mat.normal_uv_transform);
             shader.set_float(uniforms.u_NormalScale, mat.normal_scale.unwrap_or(1.0));
         }
         if let Some(ref emissive_texture) = mat.emissive_texture {
-            gl::ActiveTexture(gl::TEXTURE2);
-            gl::BindTexture(gl::TEXTURE_2D, emissive_texture.id);
+            emissive_texture.bind(2);
             shader.set_int(uniforms.u_EmissiveTexCoord, emissive_texture.tex_coord as i32);
-            shader.set_vector3(uniforms.u_EmissiveFactor, &mat.emissive_factor);
+            shader.set_mat3(uniforms.u_EmissiveUVTransform, &mat.emissive_uv_transform);
         }
+        shader.set_vector3(uniforms.u_EmissiveFactor, &mat.emissive_factor);
 
         if let Some(ref mr_texture) = mat.metallic_roughness_texture {
-            gl::ActiveTexture(gl::TEXTURE3);
-            gl::BindTexture(gl::TEXTURE_2D, mr_texture.id);
+            mr_texture.bind(3);
             shader.set_int(uniforms.u_MetallicRoughnessTexCoord, mr_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_MetallicRoughnessUVTransform, &mat.metallic_roughness_uv_transform);
         }
         shader.set_vec2(uniforms.u_MetallicRoughnessValues,
             mat.metallic_factor, mat.roughness_factor);
 
+        if let Some(ref specular_glossiness_factor) = mat.specular_glossiness_factor {
+            shader.set_vector4(uniforms.u_SpecularGlossinessValues, specular_glossiness_factor);
+        }
+        if let Some(ref sg_texture) = mat.specular_glossiness_texture {
+            sg_texture.bind(3);
+            shader.set_int(uniforms.u_SpecularGlossinessTexCoord, sg_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_SpecularGlossinessUVTransform, &mat.specular_glossiness_uv_transform);
+        }
+
         if let Some(ref occlusion_texture) = mat.occlusion_texture {
-            gl::ActiveTexture(gl::TEXTURE4);
-            gl::BindTexture(gl::TEXTURE_2D, occlusion_texture.id);
+            occlusion_texture.bind(4);
             shader.set_int(uniforms.u_OcclusionTexCoord, occlusion_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_OcclusionUVTransform, &mat.occlusion_uv_transform);
             shader.set_float(uniforms.u_OcclusionStrength, mat.occlusion_strength);
         }
+
+        if mat.clearcoat_factor > 0.0 {
+            shader.set_float(uniforms.u_ClearcoatFactor, mat.clearcoat_factor);
+            shader.set_float(uniforms.u_ClearcoatRoughnessFactor, mat.clearcoat_roughness_factor);
+        }
+        if let Some(ref clearcoat_texture) = mat.clearcoat_texture {
+            clearcoat_texture.bind(5);
+            shader.set_int(uniforms.u_ClearcoatTexCoord, clearcoat_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_ClearcoatUVTransform, &mat.clearcoat_uv_transform);
+        }
+        if let Some(ref clearcoat_roughness_texture) = mat.clearcoat_roughness_texture {
+            clearcoat_roughness_texture.bind(6);
+            shader.set_int(uniforms.u_ClearcoatRoughnessTexCoord, clearcoat_roughness_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_ClearcoatRoughnessUVTransform, &mat.clearcoat_roughness_uv_transform);
+        }
+        if let Some(ref clearcoat_normal_texture) = mat.clearcoat_normal_texture {
+            clearcoat_normal_texture.bind(7);
+            shader.set_int(uniforms.u_ClearcoatNormalTexCoord, clearcoat_normal_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_ClearcoatNormalUVTransform, &mat.clearcoat_normal_uv_transform);
+            shader.set_float(uniforms.u_ClearcoatNormalScale, mat.clearcoat_normal_scale);
+        }
+
+        shader.set_float(uniforms.u_Ior, mat.ior);
+        shader.set_float(uniforms.u_SpecularFactor, mat.specular_factor);
+        shader.set_vector3(uniforms.u_SpecularColorFactor, &mat.specular_color_factor);
+        if let Some(ref specular_texture) = mat.specular_texture {
+            specular_texture.bind(13);
+            shader.set_int(uniforms.u_SpecularTexCoord, specular_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_SpecularUVTransform, &mat.specular_uv_transform);
+        }
+        if let Some(ref specular_color_texture) = mat.specular_color_texture {
+            specular_color_texture.bind(14);
+            shader.set_int(uniforms.u_SpecularColorTexCoord, specular_color_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_SpecularColorUVTransform, &mat.specular_color_uv_transform);
+        }
+
+        if mat.has_sheen() {
+            shader.set_vector3(uniforms.u_SheenColorFactor, &mat.sheen_color_factor);
+            shader.set_float(uniforms.u_SheenRoughnessFactor, mat.sheen_roughness_factor);
+        }
+        if let Some(ref sheen_lut) = mat.sheen_lut {
+            gl::ActiveTexture(gl::TEXTURE15);
+            gl::BindTexture(gl::TEXTURE_2D, sheen_lut.texture);
+        }
+        if let Some(ref sheen_color_texture) = mat.sheen_color_texture {
+            sheen_color_texture.bind(11);
+            shader.set_int(uniforms.u_SheenColorTexCoord, sheen_color_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_SheenColorUVTransform, &mat.sheen_color_uv_transform);
+        }
+        if let Some(ref sheen_roughness_texture) = mat.sheen_roughness_texture {
+            sheen_roughness_texture.bind(12);
+            shader.set_int(uniforms.u_SheenRoughnessTexCoord, sheen_roughness_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_SheenRoughnessUVTransform, &mat.sheen_roughness_uv_transform);
+        }
+
+        if mat.transmission_factor > 0.0 {
+            shader.set_float(uniforms.u_TransmissionFactor, mat.transmission_factor);
+            gl::ActiveTexture(gl::TEXTURE10);
+            gl::BindTexture(gl::TEXTURE_2D, state.transmission_texture);
+            // for projecting the refracted ray into the scene color
+            let view_projection = mvp_matrix * model_matrix.invert().unwrap_or_else(Matrix4::identity);
+            shader.set_mat4(uniforms.u_ViewProjectionMatrix, &view_projection);
+
+            shader.set_float(uniforms.u_ThicknessFactor, mat.thickness_factor);
+            shader.set_float(uniforms.u_AttenuationDistance, mat.attenuation_distance);
+            shader.set_vector3(uniforms.u_AttenuationColor, &mat.attenuation_color);
+        }
+        if let Some(ref transmission_texture) = mat.transmission_texture {
+            transmission_texture.bind(8);
+            shader.set_int(uniforms.u_TransmissionTexCoord, transmission_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_TransmissionUVTransform, &mat.transmission_uv_transform);
+        }
+        if let Some(ref thickness_texture) = mat.thickness_texture {
+            thickness_texture.bind(9);
+            shader.set_int(uniforms.u_ThicknessTexCoord, thickness_texture.tex_coord as i32);
+            shader.set_mat3(uniforms.u_ThicknessUVTransform, &mat.thickness_uv_transform);
+        }
+    }
+
+    /// Number of triangles drawn (0 for points and lines)
+    fn num_triangles(&self) -> u32 {
+        let count = if self.ebo.is_some() { self.num_indices } else { self.num_vertices };
+        match self.mode {
+            gl::TRIANGLES => count / 3,
+            gl::TRIANGLE_STRIP | gl::TRIANGLE_FAN => count.saturating_sub(2),
+            _ => 0,
+        }
     }
 
-    unsafe fn setup_primitive(&mut self, vertices: &[Vertex], indices: Option<Vec<u32>>) {
+    unsafe fn setup_primitive(&mut self, attributes: &[VertexAttribute], indices: Option<Vec<u32>>) {
         // create buffers/arrays
         gl::GenVertexArrays(1, &mut self.vao);
         gl::GenBuffers(1, &mut self.vbo);
@@ -366,48 +570,193 @@ impl Primitive {
         }
 
         gl::BindVertexArray(self.vao);
-        // load data into vertex buffers
+        // load data into vertex buffers (attributes one after another, 4 byte aligned)
+        let mut offsets = Vec::with_capacity(attributes.len());
+        let mut size = 0;
+        for attribute in attributes {
+            offsets.push(size);
+            size += (attribute.data.len() + 3) & !3;
+        }
         gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-        let size = (vertices.len() * size_of::<Vertex>()) as isize;
-        let data = &vertices[0] as *const Vertex as *const c_void;
-        gl::BufferData(gl::ARRAY_BUFFER, size, data, gl::STATIC_DRAW);
+        gl::BufferData(gl::ARRAY_BUFFER, size as isize, ptr::null(), gl::STATIC_DRAW);
+        self.memory_size = size;
+
+        // set the vertex attribute pointers
+        for (attribute, &offset) in attributes.iter().zip(&offsets) {
+            gl::BufferSubData(gl::ARRAY_BUFFER, offset as isize, attribute.data.len() as isize,
+                attribute.data.as_ptr() as *const c_void);
+            gl::EnableVertexAttribArray(attribute.location);
+            gl::VertexAttribPointer(attribute.location, attribute.components, attribute.component_type,
+                attribute.normalized as u8, 0, offset as *const c_void);
+        }
 
         if let Some(ebo) = self.ebo {
             let indices = indices.unwrap();
             gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-            let size = (indices.len() * size_of::<u32>()) as isize;
-            let data = &indices[0] as *const u32 as *const c_void;
-            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, size, data, gl::STATIC_DRAW);
+            // use 16 bit indices when possible
+            let (size, data) = if indices.iter().all(|&i| i <= u32::from(u16::MAX)) {
+                let indices: Vec<u16> = indices.iter().map(|&i| i as u16).collect();
+                self.index_type = gl::UNSIGNED_SHORT;
+                let size = indices.len() * size_of::<u16>();
+                (size, slice::from_raw_parts(indices.as_ptr() as *const u8, size).to_vec())
+            } else {
+                self.index_type = gl::UNSIGNED_INT;
+                let size = indices.len() * size_of::<u32>();
+                (size, slice::from_raw_parts(indices.as_ptr() as *const u8, size).to_vec())
+            };
+            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, size as isize, data.as_ptr() as *const c_void, gl::STATIC_DRAW);
+            self.memory_size += size;
         }
 
-        // set the vertex attribute pointers
-        let size = size_of::<Vertex>() as i32;
-        // POSITION
-        gl::EnableVertexAttribArray(0);
-        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, position) as *const c_void);
-        // NORMAL
-        gl::EnableVertexAttribArray(1);
-        gl::VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, normal) as *const c_void);
-        // TANGENT
-        gl::EnableVertexAttribArray(2);
-        gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, tangent) as *const c_void);
-        // TEXCOORD_0
-        gl::EnableVertexAttribArray(3);
-        gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, tex_coord_0) as *const c_void);
-        // TEXCOORD_1
-        gl::EnableVertexAttribArray(4);
-        gl::VertexAttribPointer(4, 2, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, tex_coord_1) as *const c_void);
-        // COLOR_0
-        gl::EnableVertexAttribArray(5);
-        gl::VertexAttribPointer(5, 4, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, color_0) as *const c_void);
-        // JOINTS_0
-        gl::EnableVertexAttribArray(6);
-        // TODO: normalization?
-        gl::VertexAttribPointer(6, 4, gl::UNSIGNED_SHORT, gl::FALSE, size, offset_of!(Vertex, joints_0) as *const c_void);
-        // WEIGHTS_0
-        gl::EnableVertexAttribArray(7);
-        gl::VertexAttribPointer(7, 4, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, weights_0) as *const c_void);
-
         gl::BindVertexArray(0);
     }
 }
+
+/// Converts triangle strips/fans (and optional indices) to a plain triangle list
+fn triangle_list(mode: GLenum, indices: Option<&[u32]>, num_vertices: usize) -> Vec<u32> {
+    let indices: Vec<u32> = match indices {
+        Some(indices) => indices.to_vec(),
+        None => (0..num_vertices as u32).collect(),
+    };
+    match mode {
+        gl::TRIANGLE_STRIP => (0..indices.len().saturating_sub(2))
+            .flat_map(|i| if i % 2 == 0 {
+                // keep the winding order consistent
+                [indices[i], indices[i + 1], indices[i + 2]]
+            } else {
+                [indices[i + 1], indices[i], indices[i + 2]]
+            }.to_vec())
+            .collect(),
+        gl::TRIANGLE_FAN => (1..indices.len().saturating_sub(1))
+            .flat_map(|i| vec![indices[0], indices[i], indices[i + 1]])
+            .collect(),
+        _ => indices,
+    }
+}
+
+/// Decodes the buffer view of a `KHR_draco_mesh_compression` extension
+fn decode_draco(extension: &serde_json::Value, imp: &ImportData) -> Result<draco::Mesh, String> {
+    let view = extension["bufferView"].as_u64()
+        .and_then(|index| imp.doc.views().nth(index as usize))
+        .ok_or("invalid bufferView")?;
+    let data = imp.buffers[view.buffer().index()].get(view.offset()..view.offset() + view.length())
+        .ok_or("bufferView exceeds the buffer")?;
+    draco::decode(data)
+}
+
+fn vec2s(attribute: &VertexAttribute) -> Vec<Vector2> {
+    attribute.to_f32().chunks_exact(2).map(|v| Vector2::new(v[0], v[1])).collect()
+}
+
+fn vec3s(attribute: &VertexAttribute) -> Vec<Vector3> {
+    attribute.to_f32().chunks_exact(3).map(|v| Vector3::new(v[0], v[1], v[2])).collect()
+}
+
+/// Resolves the indices of a per-vertex attribute
+fn deindex<T: Clone>(values: &[T], indices: &[u32]) -> Vec<T> {
+    indices.iter().map(|&i| values[i as usize].clone()).collect()
+}
+
+/// Calculates the face normal for each vertex of a de-indexed triangle list
+fn generate_flat_normals(positions: &[Vector3]) -> Vec<Vector3> {
+    let mut normals = Vec::with_capacity(positions.len());
+    for triangle in positions.chunks_exact(3) {
+        let normal = (triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]);
+        // degenerate triangles get a zero normal instead of NaNs
+        let normal = if normal.magnitude2() > 0.0 { normal.normalize() } else { Vector3::zero() };
+        normals.extend_from_slice(&[normal; 3]);
+    }
+    normals
+}
+
+/// De-indexed triangle list passed to MikkTSpace
+struct TangentGeometry<'a> {
+    positions: &'a [Vector3],
+    normals: &'a [Vector3],
+    tex_coords: &'a [Vector2],
+    tangents: Vec<Vector4>,
+}
+
+impl<'a> bevy_mikktspace::Geometry for TangentGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[face * 3 + vert].into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[face * 3 + vert].into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let uv = self.tex_coords[face * 3 + vert];
+        // glTF has the UV origin at the top left, MikkTSpace (i.e. Blender & co.) at the bottom left
+        [uv.x, 1.0 - uv.y]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = tangent.into();
+    }
+}
+
+/// Calculates MikkTSpace tangents for a de-indexed triangle list
+/// (MikkTSpace works on face corners).
+fn generate_tangents(positions: &[Vector3], normals: &[Vector3], tex_coords: &[Vector2]) -> Option<Vec<Vector4>> {
+    let mut geometry = TangentGeometry {
+        positions,
+        normals,
+        tex_coords,
+        tangents: vec![Vector4::zero(); positions.len()],
+    };
+    if bevy_mikktspace::generate_tangents(&mut geometry) {
+        Some(geometry.tangents)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_and_fan_to_triangle_list() {
+        assert_eq!(triangle_list(gl::TRIANGLE_STRIP, None, 5), vec![0, 1, 2, 2, 1, 3, 2, 3, 4]);
+        assert_eq!(triangle_list(gl::TRIANGLE_FAN, Some(&[4, 5, 6, 7]), 8), vec![4, 5, 6, 4, 6, 7]);
+        assert_eq!(triangle_list(gl::TRIANGLES, None, 3), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn flat_normals() {
+        let positions = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].iter()
+            .map(|&(x, y)| Vector3::new(x, y, 0.0))
+            .collect::<Vec<_>>();
+        let positions = deindex(&positions, &[0, 1, 2, 2, 1, 3]);
+        let normals = generate_flat_normals(&positions);
+        assert_eq!(normals.len(), 6);
+        assert!(normals.iter().all(|&n| n == Vector3::unit_z()));
+    }
+
+    #[test]
+    fn mikktspace_tangents() {
+        // quad in the XY plane, glTF UVs (V pointing down)
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)];
+        let triangles = [0, 1, 2, 2, 1, 3];
+        let positions: Vec<_> = corners.iter().map(|&(x, y)| Vector3::new(x, y, 0.0)).collect();
+        let tex_coords: Vec<_> = corners.iter().map(|&(x, y)| Vector2::new(x, 1.0 - y)).collect();
+        let tangents = generate_tangents(
+            &deindex(&positions, &triangles),
+            &[Vector3::unit_z(); 6],
+            &deindex(&tex_coords, &triangles)).unwrap();
+        assert_eq!(tangents.len(), 6);
+        for tangent in &tangents {
+            assert!((tangent - Vector4::new(1.0, 0.0, 0.0, 1.0)).magnitude() < 1e-5, "{:?}", tangent);
+        }
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use crate::shader::*;
-use crate::render::{Mesh, Node, Material};
+use crate::render::{Mesh, Node, Material, Sampler, SheenLut};
 use crate::render::texture::Texture;
 use crate::importdata::ImportData;
 
@@ -12,25 +12,37 @@ pub struct Root {
     pub nodes: Vec<Node>,
     pub meshes: Vec<Rc<Mesh>>, // TODO!: use gltf indices; drop Rc?
     pub textures: Vec<Rc<Texture>>,
+    /// Sampler objects (shared by the textures using the same glTF sampler)
+    pub samplers: Vec<Rc<Sampler>>,
     pub materials: Vec<Rc<Material>>,
-    pub shaders: HashMap<ShaderFlags, Rc<PbrShader>>,
+    /// Shared by the materials with sheen (created by the first one)
+    pub sheen_lut: Option<Rc<SheenLut>>,
+    /// PBR shader variants by flags and number of texture coordinate sets
+    pub shaders: HashMap<(ShaderFlags, u32), Rc<PbrShader>>,
 
     pub camera_nodes: Vec<usize>, // indices of camera nodes
+    /// Names of the `KHR_materials_variants` variants
+    pub variants: Vec<String>,
     // TODO!: joint_nodes, mesh_nodes?
 }
 
 impl Root {
-    pub fn from_gltf(imp: &ImportData, base_path: &Path) -> Self {
+    pub fn from_gltf(imp: &ImportData, base_path: &Path) -> Result<Self, String> {
         let mut root = Root::default();
         let nodes = imp.doc.nodes()
             .map(|g_node| Node::from_gltf(&g_node, &mut root, imp, base_path))
-            .collect();
+            .collect::<Result<_, _>>()?;
         root.nodes = nodes;
         root.camera_nodes = root.nodes.iter()
             .filter(|node| node.camera.is_some())
             .map(|node| node.index)
             .collect();
-        root
+        root.variants = imp.variant_names();
+        Ok(root)
+    }
+
+    pub fn sheen_lut(&mut self) -> Rc<SheenLut> {
+        Rc::clone(self.sheen_lut.get_or_insert_with(|| Rc::new(SheenLut::new())))
     }
 
     /// Get a mutable reference to a node without borrowing `Self` or `Self::nodes`.
@@ -42,6 +54,31 @@ impl Root {
         }
     }
 
+    /// Whether any primitive's active material (see `select_variant`) uses `KHR_materials_transmission`
+    /// (needs `DrawPass::Transmission`)
+    pub fn has_transmission(&self) -> bool {
+        self.meshes.iter()
+            .flat_map(|mesh| &mesh.primitives)
+            .any(|primitive| primitive.active_material().0.transmission_factor > 0.0)
+    }
+
+    /// Sets the maximum degree of anisotropic filtering of all samplers (1: off)
+    pub fn set_anisotropy(&self, anisotropy: f32) {
+        for sampler in &self.samplers {
+            sampler.set_anisotropy(anisotropy)
+        }
+    }
+
+    /// Switches all primitives to the materials of the given `KHR_materials_variants` variant
+    /// (`None`: default materials)
+    pub fn select_variant(&self, variant: Option<usize>) {
+        for mesh in &self.meshes {
+            for primitive in &mesh.primitives {
+                primitive.select_variant(variant)
+            }
+        }
+    }
+
     /// Note: index refers to the vec of camera node indices!
     pub fn get_camera_node(&self, index: usize) -> &Node {
         &self.nodes[self.camera_nodes[index]]
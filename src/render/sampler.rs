@@ -0,0 +1,99 @@
+use gltf::json::texture::MinFilter;
+use log::warn;
+
+use crate::utils::gl_extensions;
+
+/// `GL_EXT_texture_filter_anisotropic` (not part of the core profile bindings)
+const TEXTURE_MAX_ANISOTROPY_EXT: gl::types::GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: gl::types::GLenum = 0x84FF;
+
+/// OpenGL sampler object of a glTF sampler, shared by all textures using it
+pub struct Sampler {
+    pub index: Option<usize>, // glTF index (`None`: default sampler)
+
+    pub id: u32, // OpenGL id
+    /// Whether images need to be resized to power-of-two dimensions (mip mapping or wrapping)
+    pub needs_power_of_two: bool,
+}
+
+impl Sampler {
+    pub fn from_gltf(sampler: &gltf::texture::Sampler<'_>) -> Sampler {
+        let mut id = 0;
+        unsafe { gl::GenSamplers(1, &mut id) };
+
+        // **Mipmapping Implementation Note**: When a sampler's minification filter (`minFilter`)
+        // uses mipmapping (`NEAREST_MIPMAP_NEAREST`, `NEAREST_MIPMAP_LINEAR`, `LINEAR_MIPMAP_NEAREST`,
+        // or `LINEAR_MIPMAP_LINEAR`), any texture referencing the sampler needs to have mipmaps,
+        // e.g., by calling GL's `generateMipmap()` function.
+        let mip_maps = match sampler.min_filter() {
+            Some(MinFilter::NearestMipmapNearest) |
+            Some(MinFilter::LinearMipmapNearest) |
+            Some(MinFilter::NearestMipmapLinear) |
+            Some(MinFilter::LinearMipmapLinear) |
+            None => true, // see below
+            _ => false
+        };
+
+        // **Default Filtering Implementation Note:** When filtering options are defined,
+        // runtime must use them. Otherwise, it is free to adapt filtering to performance or quality goals.
+        let min_filter = sampler.min_filter().map_or(gl::LINEAR_MIPMAP_LINEAR, |filter| filter.as_gl_enum());
+        let mag_filter = sampler.mag_filter().map_or(gl::LINEAR, |filter| filter.as_gl_enum());
+        let wrap_s = sampler.wrap_s().as_gl_enum();
+        let wrap_t = sampler.wrap_t().as_gl_enum();
+        unsafe {
+            gl::SamplerParameteri(id, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+            gl::SamplerParameteri(id, gl::TEXTURE_MAG_FILTER, mag_filter as i32);
+            gl::SamplerParameteri(id, gl::TEXTURE_WRAP_S, wrap_s as i32);
+            gl::SamplerParameteri(id, gl::TEXTURE_WRAP_T, wrap_t as i32);
+        }
+
+        Sampler {
+            index: sampler.index(),
+            id,
+            needs_power_of_two: wrap_s != gl::CLAMP_TO_EDGE || wrap_t != gl::CLAMP_TO_EDGE || mip_maps,
+        }
+    }
+
+    /// Sets the maximum degree of anisotropic filtering (1: off)
+    pub fn set_anisotropy(&self, anisotropy: f32) {
+        unsafe { gl::SamplerParameterf(self.id, TEXTURE_MAX_ANISOTROPY_EXT, anisotropy) }
+    }
+
+    /// Binds the sampler to the given texture unit (0-based)
+    pub unsafe fn bind(&self, unit: u32) {
+        gl::BindSampler(unit, self.id)
+    }
+
+    /// Unbinds the samplers of the texture units used by materials, so other passes
+    /// (post-processing, overlay) sample with the parameters of their textures again
+    pub unsafe fn unbind_all() {
+        for unit in 0..16 {
+            gl::BindSampler(unit, 0)
+        }
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteSamplers(1, &self.id) }
+    }
+}
+
+/// Clamps the requested anisotropy to what the OpenGL implementation supports.
+/// Returns 1 (no anisotropic filtering) without `GL_EXT_texture_filter_anisotropic`.
+pub fn supported_anisotropy(requested: f32) -> f32 {
+    if requested <= 1.0 {
+        return 1.0
+    }
+    if !unsafe { gl_extensions() }.iter().any(|ext| ext == "GL_EXT_texture_filter_anisotropic" || ext == "GL_ARB_texture_filter_anisotropic") {
+        warn!("Anisotropic filtering is not supported (GL_EXT_texture_filter_anisotropic is missing)");
+        return 1.0
+    }
+    let mut max = 1.0;
+    unsafe { gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max) };
+    if requested > max {
+        warn!("Anisotropy {} exceeds the maximum of {} - using {}", requested, max, max);
+        return max
+    }
+    requested
+}
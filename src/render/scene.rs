@@ -1,9 +1,9 @@
 use gltf;
 
-use collision::{Aabb, Union};
+use collision::Aabb;
 
 use crate::controls::CameraParams;
-use crate::render::{Root};
+use crate::render::{DrawState, Node, Root};
 use crate::render::math::*;
 
 pub struct Scene {
@@ -37,20 +37,27 @@ impl Scene {
         for node_id in &scene.nodes {
             let node = root.unsafe_get_node_mut(*node_id);
             node.update_transform(root, &root_transform);
-            node.update_bounds(root);
-            scene.bounds = scene.bounds.union(&node.bounds);
         }
+        scene.bounds = Scene::bounds_from_gltf(g_scene).unwrap_or_else(Aabb3::zero);
 
         scene
     }
 
+    /// Bounds of the scene from the accessor bounds of the positions, without loading it
+    /// (also used by `--info`)
+    /// (`None` if there are no positions)
+    pub fn bounds_from_gltf(g_scene: &gltf::Scene<'_>) -> Option<Aabb3> {
+        union_bounds(g_scene.nodes()
+            .filter_map(|g_node| Node::bounds_from_gltf(&g_node, &Matrix4::identity())))
+    }
+
     // TODO: flatten draw call hierarchy (global Vec<Primitive>?)
-    pub fn draw(&mut self, root: &mut Root, cam_params: &CameraParams) {
+    pub fn draw(&mut self, root: &mut Root, cam_params: &CameraParams, state: &mut DrawState) {
         // TODO!: for correct alpha blending, sort by material alpha mode and
         // render opaque objects first.
         for node_id in &self.nodes {
             let node = root.unsafe_get_node_mut(*node_id);
-            node.draw(root, cam_params);
+            node.draw(root, cam_params, state);
         }
     }
 }
@@ -0,0 +1,115 @@
+use std::f32::consts::PI;
+use std::os::raw::c_void;
+
+/// Directional albedo E(NdotV, sheenRoughness) of the sheen BRDF of `KHR_materials_sheen`,
+/// for darkening the base layer by the light reflected by the sheen (like the `u_SheenELUT`
+/// of the Khronos sample viewer). Integrated numerically when a material with sheen is loaded.
+pub struct SheenLut {
+    pub texture: u32,
+}
+
+const SIZE: usize = 32;
+/// Integration steps of the polar and azimuthal angles
+const STEPS: usize = 32;
+
+impl SheenLut {
+    pub fn new() -> SheenLut {
+        let mut data = Vec::with_capacity(SIZE * SIZE);
+        for y in 0..SIZE {
+            let roughness = (y as f32 + 0.5) / SIZE as f32;
+            for x in 0..SIZE {
+                let n_dot_v = (x as f32 + 0.5) / SIZE as f32;
+                data.push(sheen_albedo(n_dot_v, roughness));
+            }
+        }
+
+        let mut texture = 0;
+        unsafe {
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R32F as i32, SIZE as i32, SIZE as i32, 0,
+                gl::RED, gl::FLOAT, data.as_ptr() as *const c_void);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        SheenLut { texture }
+    }
+}
+
+impl Drop for SheenLut {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.texture) }
+    }
+}
+
+/// Integral of the sheen BRDF times NdotL over the hemisphere (midpoint rule;
+/// the BRDF is symmetric in the azimuth, so half of it is integrated twice).
+fn sheen_albedo(n_dot_v: f32, roughness: f32) -> f32 {
+    let v = [(1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v];
+    let d_theta = 0.5 * PI / STEPS as f32;
+    let d_phi = PI / STEPS as f32;
+    let mut sum = 0.0;
+    for i in 0..STEPS {
+        let theta = (i as f32 + 0.5) * d_theta;
+        let (sin_theta, n_dot_l) = theta.sin_cos();
+        for j in 0..STEPS {
+            let phi = (j as f32 + 0.5) * d_phi;
+            let l = [sin_theta * phi.cos(), sin_theta * phi.sin(), n_dot_l];
+            let h = [l[0] + v[0], l[1] + v[1], l[2] + v[2]];
+            let n_dot_h = h[2] / (h[0] * h[0] + h[1] * h[1] + h[2] * h[2]).sqrt();
+            sum += sheen_distribution(roughness, n_dot_h) * sheen_visibility(n_dot_l, n_dot_v, roughness)
+                * n_dot_l * sin_theta;
+        }
+    }
+    2.0 * sum * d_theta * d_phi
+}
+
+// NOTE: same as in pbr-frag.glsl
+
+fn sheen_distribution(sheen_roughness: f32, n_dot_h: f32) -> f32 {
+    let alpha_g = sheen_roughness * sheen_roughness;
+    let inv_r = 1.0 / alpha_g;
+    let sin2h = 1.0 - n_dot_h * n_dot_h;
+    (2.0 + inv_r) * sin2h.powf(inv_r * 0.5) / (2.0 * PI)
+}
+
+fn lambda_sheen_numeric_helper(x: f32, alpha_g: f32) -> f32 {
+    let one_minus_alpha_sq = (1.0 - alpha_g) * (1.0 - alpha_g);
+    let mix = |a: f32, b: f32| a + (b - a) * one_minus_alpha_sq;
+    let a = mix(21.5473, 25.3245);
+    let b = mix(3.82987, 3.32435);
+    let c = mix(0.19823, 0.16801);
+    let d = mix(-1.97760, -1.27393);
+    let e = mix(-4.32054, -4.85967);
+    a / (1.0 + b * x.powf(c)) + d * x + e
+}
+
+fn lambda_sheen(cos_theta: f32, alpha_g: f32) -> f32 {
+    if cos_theta.abs() < 0.5 {
+        lambda_sheen_numeric_helper(cos_theta, alpha_g).exp()
+    } else {
+        (2.0 * lambda_sheen_numeric_helper(0.5, alpha_g) - lambda_sheen_numeric_helper(1.0 - cos_theta, alpha_g)).exp()
+    }
+}
+
+fn sheen_visibility(n_dot_l: f32, n_dot_v: f32, sheen_roughness: f32) -> f32 {
+    let alpha_g = sheen_roughness * sheen_roughness;
+    let visibility = 1.0 / ((1.0 + lambda_sheen(n_dot_v, alpha_g) + lambda_sheen(n_dot_l, alpha_g)) * (4.0 * n_dot_v * n_dot_l));
+    visibility.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn albedo() {
+        // reference values: same integral with 128x128 steps
+        assert!((sheen_albedo(1.0, 1.0) - 0.1638).abs() < 0.002);
+        assert!((sheen_albedo(0.5, 0.5) - 0.1879).abs() < 0.002);
+        assert!((sheen_albedo(0.1, 0.5) - 0.5532).abs() < 0.002);
+    }
+}
@@ -1,20 +1,28 @@
+use std::borrow::Cow;
+use std::io::Read;
 use std::os::raw::c_void;
 use std::path::Path;
-use std::{fs, io};
+use std::rc::Rc;
+use std::io;
 
-use base64;
 use gl;
+use gl::types::GLenum;
 use gltf;
-use gltf::json::texture::MinFilter;
 use gltf::image::Source;
 
 use image;
 use image::ImageFormat::{JPEG, PNG};
+use image::{DynamicImage, ImageBuffer};
 use image::DynamicImage::*;
 use image::GenericImageView;
 use image::FilterType;
+use ktx2::{ColorModel, Format, SupercompressionScheme};
+use log::warn;
 
-use crate::importdata::ImportData;
+use crate::basisu;
+use crate::importdata::{self, ImportData};
+use crate::render::Sampler;
+use crate::utils::gl_extensions;
 
 pub struct Texture {
     pub index: usize, // glTF index
@@ -22,85 +30,136 @@ pub struct Texture {
 
     pub id: u32, // OpenGL id
     pub tex_coord: u32, // the tex coord set to use
+    /// Color data (base color/emissive) in sRGB encoding - linearized by OpenGL when sampling
+    pub srgb: bool,
+    /// Indices of the images the texture may be loaded from (extension sources first).
+    /// Textures with the same sources (and encoding) share the uploaded image.
+    pub image_sources: Vec<usize>,
+    pub sampler: Rc<Sampler>,
+
+    /// Estimated GPU memory in bytes (including mip maps), 0 if the image is shared with another texture
+    pub memory_size: usize,
 }
 
 impl Texture {
-    pub fn from_gltf(g_texture: &gltf::Texture<'_>, tex_coord: u32, imp: &ImportData, base_path: &Path) -> Texture {
-        let buffers = &imp.buffers;
+    pub fn from_gltf(g_texture: &gltf::Texture<'_>, tex_coord: u32, srgb: bool, sampler: Rc<Sampler>,
+        imp: &ImportData, base_path: &Path) -> Result<Texture, String>
+    {
         let mut texture_id = 0;
         unsafe {
             gl::GenTextures(1, &mut texture_id);
             gl::BindTexture(gl::TEXTURE_2D, texture_id);
         }
-        let (needs_power_of_two, generate_mip_maps) =
-            unsafe { Self::set_sampler_params(&g_texture.sampler()) };
-
-        // TODO!: share images via Rc? detect if occurs?
-        // TODO!!: better I/O abstraction...
-        let g_img = g_texture.source();
-        let img = match g_img.source() {
-            Source::View { view, mime_type } => {
-                let parent_buffer_data = &buffers[view.buffer().index()].0;
-                let begin = view.offset();
-                let end = begin + view.length();
-                let data = &parent_buffer_data[begin..end];
-                match mime_type {
-                    "image/jpeg" => image::load_from_memory_with_format(data, JPEG),
-                    "image/png" => image::load_from_memory_with_format(data, PNG),
-                    _ => panic!(format!("unsupported image type (image: {}, mime_type: {})",
-                        g_img.index(), mime_type)),
-                }
-            },
-            Source::Uri { uri, mime_type } => {
-                if uri.starts_with("data:") {
-                    let encoded = uri.split(',').nth(1).unwrap();
-                    let data = base64::decode(&encoded).unwrap();
-                    let mime_type = if let Some(ty) = mime_type {
-                        ty
-                    } else {
-                        uri.split(',')
-                            .nth(0).unwrap()
-                            .split(':')
-                            .nth(1).unwrap()
-                            .split(';')
-                            .nth(0).unwrap()
-                    };
-
-                    match mime_type {
-                        "image/jpeg" => image::load_from_memory_with_format(&data, JPEG),
-                        "image/png" => image::load_from_memory_with_format(&data, PNG),
-                        _ => panic!(format!("unsupported image type (image: {}, mime_type: {})",
-                            g_img.index(), mime_type)),
-                    }
-                }
-                else if let Some(mime_type) = mime_type {
-                    let path = base_path.parent().unwrap_or_else(|| Path::new("./")).join(uri);
-                    let file = fs::File::open(path).unwrap();
-                    let reader = io::BufReader::new(file);
-                    match mime_type {
-                        "image/jpeg" => image::load(reader, JPEG),
-                        "image/png" => image::load(reader, PNG),
-                        _ => panic!(format!("unsupported image type (image: {}, mime_type: {})",
-                            g_img.index(), mime_type)),
-                    }
-                }
-                else {
-                    let path = base_path.parent().unwrap_or_else(||Path::new("./")).join(uri);
-                    image::open(path)
-                }
+        // NOTE: mip maps are always generated, since the image may be shared by textures with different samplers
+        let needs_power_of_two = sampler.needs_power_of_two;
+
+        // extension sources are preferred, the core source is the fallback
+        let mut memory_size = None;
+        for (extension, g_img) in extension_sources(g_texture, imp) {
+            let result = if extension == "KHR_texture_basisu" {
+                image_data(&g_img, &imp.buffers, base_path)
+                    .and_then(|data| unsafe { Self::upload_ktx2(&data, srgb) })
+            } else {
+                Self::load_image(&g_img, srgb, needs_power_of_two, imp, base_path)
+            };
+            match result {
+                Ok(size) => {
+                    memory_size = Some(size);
+                    break
+                },
+                // without a fallback, the texture source is the extension's image (see `ImportData::import`)
+                Err(err) if g_img.index() == g_texture.source().index() => {
+                    unsafe { gl::DeleteTextures(1, &texture_id) };
+                    return Err(format!("Failed to load image {} ({}): {}", g_img.index(), extension, err))
+                },
+                Err(err) => warn!("Failed to load image {} ({}: {}) - using fallback image {}",
+                    g_img.index(), extension, err, g_texture.source().index()),
+            }
+        }
+        let memory_size = match memory_size {
+            Some(size) => size,
+            None => {
+                let g_img = g_texture.source();
+                Self::load_image(&g_img, srgb, needs_power_of_two, imp, base_path)
+                    .map_err(|err| {
+                        unsafe { gl::DeleteTextures(1, &texture_id) };
+                        format!("Failed to load image {}: {}", g_img.index(), err)
+                    })?
             }
         };
 
-        // TODO: handle I/O problems
-        let dyn_img = img.expect("Image loading failed.");
+        Ok(Texture {
+            index: g_texture.index(),
+            name: g_texture.name().map(|s| s.into()),
+            id: texture_id,
+            tex_coord,
+            srgb,
+            image_sources: Self::image_sources(g_texture, imp),
+            sampler,
+            memory_size,
+        })
+    }
+
+    /// Creates a texture using the image of this one (with another sampler or texture coordinate set)
+    pub fn share_image(&self, g_texture: &gltf::Texture<'_>, tex_coord: u32, sampler: Rc<Sampler>) -> Texture {
+        Texture {
+            index: g_texture.index(),
+            name: g_texture.name().map(|s| s.into()),
+            id: self.id,
+            tex_coord,
+            srgb: self.srgb,
+            image_sources: self.image_sources.clone(),
+            sampler,
+            memory_size: 0,
+        }
+    }
 
-        let format = match dyn_img {
-            ImageLuma8(_) => gl::RED,
-            ImageLumaA8(_) => gl::RG,
-            ImageRgb8(_) => gl::RGB,
-            ImageRgba8(_) => gl::RGBA,
-            ImageBgr8(_) => gl::BGR,
-            ImageBgra8(_) => gl::BGRA,
+    /// Indices of the images a texture may be loaded from (extension sources first)
+    pub fn image_sources(g_texture: &gltf::Texture<'_>, imp: &ImportData) -> Vec<usize> {
+        extension_sources(g_texture, imp).iter()
+            .map(|(_, g_img)| g_img.index())
+            .chain(Some(g_texture.source().index()))
+            .collect()
+    }
+
+    /// Binds the texture and its sampler to the given texture unit
+    pub unsafe fn bind(&self, unit: u32) {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(gl::TEXTURE_2D, self.id);
+        self.sampler.bind(unit);
+    }
+
+    /// Decodes and uploads the image (with mip maps) to the bound texture. Returns the estimated GPU memory size.
+    fn load_image(g_img: &gltf::Image<'_>, srgb: bool, needs_power_of_two: bool,
+        imp: &ImportData, base_path: &Path) -> Result<usize, String>
+    {
+        let decoder = image_decoder(g_img)?;
+        let data = image_data(g_img, &imp.buffers, base_path)?;
+        let mut dyn_img = (decoder.decode)(&data)?;
+        if srgb {
+            // there are no single/dual channel sRGB formats
+            dyn_img = match dyn_img {
+                ImageLuma8(_) => ImageRgb8(dyn_img.to_rgb()),
+                ImageLumaA8(_) => ImageRgba8(dyn_img.to_rgba()),
+                img => img,
+            }
+        }
+
+        let (format, components) = match dyn_img {
+            ImageLuma8(_) => (gl::RED, 1),
+            ImageLumaA8(_) => (gl::RG, 2),
+            ImageRgb8(_) => (gl::RGB, 3),
+            ImageRgba8(_) => (gl::RGBA, 4),
+            ImageBgr8(_) => (gl::BGR, 3),
+            ImageBgra8(_) => (gl::BGRA, 4),
+        };
+        let internal_format = match format {
+            gl::RGB | gl::BGR if srgb => gl::SRGB8,
+            gl::RGBA | gl::BGRA if srgb => gl::SRGB8_ALPHA8,
+            gl::RED => gl::R8,
+            gl::RG => gl::RG8,
+            gl::RGB | gl::BGR => gl::RGB8,
+            _ => gl::RGBA8,
         };
 
         // **Non-Power-Of-Two Texture Implementation Note**: glTF does not guarantee that a texture's
@@ -123,61 +182,272 @@ impl Texture {
             };
 
         unsafe {
-            gl::TexImage2D(gl::TEXTURE_2D, 0, format as i32, width as i32, height as i32,
+            gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as i32, width as i32, height as i32,
                 0, format, gl::UNSIGNED_BYTE, &data[0] as *const u8 as *const c_void);
-
-            if generate_mip_maps {
-                gl::GenerateMipmap(gl::TEXTURE_2D);
-            }
-        }
-        Texture {
-            index: g_texture.index(),
-            name: g_texture.name().map(|s| s.into()),
-            id: texture_id,
-            tex_coord,
+            gl::GenerateMipmap(gl::TEXTURE_2D);
         }
+        Ok(width as usize * height as usize * components * 4 / 3)
     }
 
-    // Returns whether image needs to be Power-Of-Two-sized and whether mip maps should be generated
-    // TODO: refactor return type into enum?
-    unsafe fn set_sampler_params(sampler: &gltf::texture::Sampler<'_>) -> (bool, bool) {
-        // **Mipmapping Implementation Note**: When a sampler's minification filter (`minFilter`)
-        // uses mipmapping (`NEAREST_MIPMAP_NEAREST`, `NEAREST_MIPMAP_LINEAR`, `LINEAR_MIPMAP_NEAREST`,
-        // or `LINEAR_MIPMAP_LINEAR`), any texture referencing the sampler needs to have mipmaps,
-        // e.g., by calling GL's `generateMipmap()` function.
-        let mip_maps = match sampler.min_filter() {
-            Some(MinFilter::NearestMipmapNearest) |
-            Some(MinFilter::LinearMipmapNearest) |
-            Some(MinFilter::NearestMipmapLinear) |
-            Some(MinFilter::LinearMipmapLinear) |
-            None => true, // see below
-            _ => false
+    /// Uploads the (embedded) mip levels of a KTX2 image to the bound texture. Returns the GPU memory size.
+    /// Images in GPU formats are uploaded directly (optionally Zstandard supercompressed), Basis Universal
+    /// payloads (BasisLZ/ETC1S or UASTC) are transcoded to BC7 or ETC2 if supported, else to RGBA8.
+    unsafe fn upload_ktx2(data: &[u8], srgb: bool) -> Result<usize, String> {
+        let reader = ktx2::Reader::new(data).map_err(|err| format!("invalid KTX2 file: {:?}", err))?;
+        let header = reader.header();
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            return Err("only 2D textures are supported".to_owned())
+        }
+
+        let levels = reader.levels()
+            .map(|level| match header.supercompression_scheme {
+                // BasisLZ slices are decoded by the transcoder
+                None | Some(SupercompressionScheme::BasisLZ) => Ok(Cow::Borrowed(level)),
+                Some(SupercompressionScheme::Zstandard) => {
+                    let mut decoded = Vec::new();
+                    ruzstd::StreamingDecoder::new(level)
+                        .map_err(|err| err.to_string())?
+                        .read_to_end(&mut decoded)
+                        .map_err(|err| err.to_string())?;
+                    Ok(Cow::Owned(decoded))
+                },
+                Some(scheme) => Err(format!("unsupported supercompression scheme {:?}", scheme)),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let (format, levels) = match header.format {
+            Some(format) if header.supercompression_scheme != Some(SupercompressionScheme::BasisLZ) =>
+                (format, levels),
+            _ => {
+                let (format, transcoded) = Self::transcode_basisu(&reader, &levels)?;
+                (format, transcoded.into_iter().map(Cow::Owned).collect())
+            }
         };
+        let (internal_format, pixel_format) = ktx2_gl_format(format, srgb)
+            .ok_or_else(|| format!("unsupported format {:?}{}", format, if srgb { " (sRGB)" } else { "" }))?;
 
-        // **Default Filtering Implementation Note:** When filtering options are defined,
-        // runtime must use them. Otherwise, it is free to adapt filtering to performance or quality goals.
-        if let Some(min_filter) = sampler.min_filter() {
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min_filter.as_gl_enum() as i32);
-        }
-        else {
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        let mut memory_size = 0;
+        for (i, level) in levels.iter().enumerate() {
+            let width = (header.pixel_width >> i).max(1) as i32;
+            let height = (header.pixel_height >> i).max(1) as i32;
+            let ptr = level.as_ptr() as *const c_void;
+            match pixel_format {
+                Some(pixel_format) => gl::TexImage2D(gl::TEXTURE_2D, i as i32, internal_format as i32, width, height,
+                    0, pixel_format, gl::UNSIGNED_BYTE, ptr),
+                None => gl::CompressedTexImage2D(gl::TEXTURE_2D, i as i32, internal_format, width, height,
+                    0, level.len() as i32, ptr),
+            }
+            if gl::GetError() != gl::NO_ERROR {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+                return Err(format!("format {:?} is not supported by the OpenGL implementation", format))
+            }
+            memory_size += level.len();
         }
-        if let Some(mag_filter) = sampler.mag_filter() {
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag_filter.as_gl_enum() as i32);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+        // compressed formats can't be rendered to, so mip maps can only be generated for uncompressed ones
+        if levels.len() == 1 && pixel_format.is_some() {
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            memory_size = memory_size * 4 / 3;
+        } else {
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, levels.len() as i32 - 1);
         }
-        else {
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        Ok(memory_size)
+    }
+
+    /// Transcodes the levels of a Basis Universal KTX2 image to a format supported by the OpenGL implementation
+    unsafe fn transcode_basisu(reader: &ktx2::Reader<&[u8]>, levels: &[Cow<'_, [u8]>]) -> Result<(Format, Vec<Vec<u8>>), String> {
+        let header = reader.header();
+        let color_model = reader.data_format_descriptors().next()
+            .and_then(|dfd| ktx2::BasicDataFormatDescriptor::parse(dfd.data).ok())
+            .and_then(|dfd| dfd.color_model);
+        let extensions = gl_extensions();
+        let target = if extensions.iter().any(|ext| ext == "GL_ARB_texture_compression_bptc" || ext == "GL_EXT_texture_compression_bptc") {
+            basisu::Target::Bc7
+        } else if extensions.iter().any(|ext| ext == "GL_ARB_ES3_compatibility") {
+            basisu::Target::Etc2
+        } else {
+            basisu::Target::Rgba8
+        };
+        let levels: Vec<&[u8]> = levels.iter().map(|level| &level[..]).collect();
+        match (color_model, header.supercompression_scheme) {
+            (Some(ColorModel::ETC1S), Some(SupercompressionScheme::BasisLZ)) => basisu::transcode_etc1s(
+                reader.supercompression_global_data(), &levels, header.pixel_width, header.pixel_height, target),
+            (Some(ColorModel::UASTC), None) | (Some(ColorModel::UASTC), Some(SupercompressionScheme::Zstandard)) =>
+                basisu::transcode_uastc(&levels, header.pixel_width, header.pixel_height, target),
+            (color_model, scheme) =>
+                Err(format!("unsupported color model {:?} (supercompression {:?})", color_model, scheme)),
         }
+        .map_err(|err| format!("failed to transcode the Basis Universal image: {}", err))
+    }
+}
+
+/// Images of the texture's extensions (`KHR_texture_basisu`, `EXT_texture_webp`), in order of preference
+fn extension_sources<'a>(g_texture: &gltf::Texture<'_>, imp: &'a ImportData) -> Vec<(&'static str, gltf::Image<'a>)> {
+    ["KHR_texture_basisu", "EXT_texture_webp"].iter()
+        .filter_map(|&extension| {
+            imp.json["textures"][g_texture.index()]["extensions"][extension]["source"].as_u64()
+                .and_then(|index| imp.doc.images().nth(index as usize))
+                .map(|g_img| (extension, g_img))
+        })
+        .collect()
+}
+
+/// The encoded data of an image
+fn image_data(g_img: &gltf::Image<'_>, buffers: &[gltf::buffer::Data], base_path: &Path) -> Result<Vec<u8>, String> {
+    match g_img.source() {
+        Source::View { view, .. } => {
+            let begin = view.offset();
+            Ok(buffers[view.buffer().index()].0[begin..begin + view.length()].to_vec())
+        },
+        Source::Uri { uri, .. } => {
+            let base = base_path.parent().unwrap_or_else(|| Path::new("./"));
+            importdata::read_uri(base, uri).map_err(|err| format!("{}: {}", uri, err))
+        },
+    }
+}
+
+struct ImageDecoder {
+    mime_type: &'static str,
+    /// File extensions, for URIs without a mime type
+    extensions: &'static [&'static str],
+    decode: fn(&[u8]) -> Result<DynamicImage, String>,
+}
+
+const IMAGE_DECODERS: &[ImageDecoder] = &[
+    ImageDecoder { mime_type: "image/png", extensions: &["png"], decode: decode_png },
+    ImageDecoder { mime_type: "image/jpeg", extensions: &["jpg", "jpeg"], decode: decode_jpeg },
+    ImageDecoder { mime_type: "image/webp", extensions: &["webp"], decode: decode_webp },
+];
+
+/// Looks up the decoder by the mime type (of the image or data URI) or else by the file extension.
+fn image_decoder(g_img: &gltf::Image<'_>) -> Result<&'static ImageDecoder, String> {
+    let (mime_type, uri) = match g_img.source() {
+        Source::View { mime_type, .. } => (Some(mime_type), None),
+        Source::Uri { mime_type, uri } => (mime_type, Some(uri)),
+    };
+    let mime_type = mime_type.or_else(|| {
+        uri.filter(|uri| uri.starts_with("data:"))
+            .and_then(|uri| uri["data:".len()..].split([';', ',']).next())
+    });
+    let decoder = match (mime_type, uri) {
+        (Some(mime_type), _) => IMAGE_DECODERS.iter().find(|decoder| decoder.mime_type == mime_type),
+        (None, Some(uri)) => {
+            let extension = Path::new(uri).extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.to_lowercase())
+                .unwrap_or_default();
+            IMAGE_DECODERS.iter().find(|decoder| decoder.extensions.contains(&extension.as_str()))
+        },
+        (None, None) => None,
+    };
+    decoder.ok_or_else(|| match (mime_type, uri) {
+        (Some(mime_type), _) => format!("unsupported image type (mime_type: {})", mime_type),
+        (None, uri) => format!("unsupported image type (uri: {})", uri.unwrap_or_default()),
+    })
+}
+
+fn decode_png(data: &[u8]) -> Result<DynamicImage, String> {
+    image::load_from_memory_with_format(data, PNG).map_err(|err| err.to_string())
+}
+
+fn decode_jpeg(data: &[u8]) -> Result<DynamicImage, String> {
+    image::load_from_memory_with_format(data, JPEG).map_err(|err| err.to_string())
+}
+
+fn decode_webp(data: &[u8]) -> Result<DynamicImage, String> {
+    let mut decoder = image_webp::WebPDecoder::new(io::Cursor::new(data)).map_err(|err| err.to_string())?;
+    let (width, height) = decoder.dimensions();
+    let mut buf = vec![0; decoder.output_buffer_size().ok_or("image too large")?];
+    decoder.read_image(&mut buf).map_err(|err| err.to_string())?;
+    let img = if decoder.has_alpha() {
+        ImageBuffer::from_raw(width, height, buf).map(ImageRgba8)
+    } else {
+        ImageBuffer::from_raw(width, height, buf).map(ImageRgb8)
+    };
+    img.ok_or_else(|| "invalid image size".to_owned())
+}
+
+// S3TC (BC1-3) and ASTC aren't part of core OpenGL
+const COMPRESSED_RGB_S3TC_DXT1: GLenum = 0x83F0;
+const COMPRESSED_SRGB_S3TC_DXT1: GLenum = 0x8C4C;
+const COMPRESSED_RGBA_S3TC_DXT1: GLenum = 0x83F1;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1: GLenum = 0x8C4D;
+const COMPRESSED_RGBA_S3TC_DXT3: GLenum = 0x83F2;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3: GLenum = 0x8C4E;
+const COMPRESSED_RGBA_S3TC_DXT5: GLenum = 0x83F3;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5: GLenum = 0x8C4F;
+const COMPRESSED_RGBA_ASTC_4X4: GLenum = 0x93B0;
+const COMPRESSED_SRGB8_ALPHA8_ASTC_4X4: GLenum = 0x93D0;
+
+/// OpenGL internal format and pixel format (`None` for compressed formats) of a KTX2 (Vulkan) format.
+/// The transfer function is determined by the usage (`srgb`), not by the format.
+fn ktx2_gl_format(format: Format, srgb: bool) -> Option<(GLenum, Option<GLenum>)> {
+    let (linear, srgb_format, pixel_format) = match format {
+        Format::R8_UNORM | Format::R8_SRGB => (gl::R8, None, Some(gl::RED)),
+        Format::R8G8_UNORM | Format::R8G8_SRGB => (gl::RG8, None, Some(gl::RG)),
+        Format::R8G8B8_UNORM | Format::R8G8B8_SRGB => (gl::RGB8, Some(gl::SRGB8), Some(gl::RGB)),
+        Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB => (gl::RGBA8, Some(gl::SRGB8_ALPHA8), Some(gl::RGBA)),
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGB_SRGB_BLOCK =>
+            (COMPRESSED_RGB_S3TC_DXT1, Some(COMPRESSED_SRGB_S3TC_DXT1), None),
+        Format::BC1_RGBA_UNORM_BLOCK | Format::BC1_RGBA_SRGB_BLOCK =>
+            (COMPRESSED_RGBA_S3TC_DXT1, Some(COMPRESSED_SRGB_ALPHA_S3TC_DXT1), None),
+        Format::BC2_UNORM_BLOCK | Format::BC2_SRGB_BLOCK =>
+            (COMPRESSED_RGBA_S3TC_DXT3, Some(COMPRESSED_SRGB_ALPHA_S3TC_DXT3), None),
+        Format::BC3_UNORM_BLOCK | Format::BC3_SRGB_BLOCK =>
+            (COMPRESSED_RGBA_S3TC_DXT5, Some(COMPRESSED_SRGB_ALPHA_S3TC_DXT5), None),
+        Format::BC4_UNORM_BLOCK => (gl::COMPRESSED_RED_RGTC1, None, None),
+        Format::BC5_UNORM_BLOCK => (gl::COMPRESSED_RG_RGTC2, None, None),
+        Format::BC7_UNORM_BLOCK | Format::BC7_SRGB_BLOCK =>
+            (gl::COMPRESSED_RGBA_BPTC_UNORM, Some(gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM), None),
+        Format::ETC2_R8G8B8_UNORM_BLOCK | Format::ETC2_R8G8B8_SRGB_BLOCK =>
+            (gl::COMPRESSED_RGB8_ETC2, Some(gl::COMPRESSED_SRGB8_ETC2), None),
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK | Format::ETC2_R8G8B8A1_SRGB_BLOCK =>
+            (gl::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2, Some(gl::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2), None),
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK | Format::ETC2_R8G8B8A8_SRGB_BLOCK =>
+            (gl::COMPRESSED_RGBA8_ETC2_EAC, Some(gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC), None),
+        Format::EAC_R11_UNORM_BLOCK => (gl::COMPRESSED_R11_EAC, None, None),
+        Format::EAC_R11G11_UNORM_BLOCK => (gl::COMPRESSED_RG11_EAC, None, None),
+        _ => {
+            // ASTC 4x4 to 12x12: alternating UNORM/SRGB formats, in the same order as in OpenGL
+            let value = format.0.get();
+            if value < Format::ASTC_4x4_UNORM_BLOCK.0.get() || value > Format::ASTC_12x12_SRGB_BLOCK.0.get() {
+                return None
+            }
+            let block_size = (value - Format::ASTC_4x4_UNORM_BLOCK.0.get()) / 2;
+            (COMPRESSED_RGBA_ASTC_4X4 + block_size, Some(COMPRESSED_SRGB8_ALPHA8_ASTC_4X4 + block_size), None)
+        },
+    };
+    let internal_format = if srgb { srgb_format? } else { linear };
+    Some((internal_format, pixel_format))
+}
 
-        let wrap_s = sampler.wrap_s().as_gl_enum();
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, wrap_s as i32);
-        let wrap_t = sampler.wrap_t().as_gl_enum();
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, wrap_t as i32);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let needs_power_of_two =
-            wrap_s != gl::CLAMP_TO_EDGE ||
-            wrap_t != gl::CLAMP_TO_EDGE ||
-            mip_maps;
-        (needs_power_of_two, mip_maps)
+    #[test]
+    fn webp() {
+        // 8x8 lossless checkerboard
+        let data = [
+        0x52, 0x49, 0x46, 0x46, 0xb0, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38, 0x4c,
+        0xa4, 0x00, 0x00, 0x00, 0x2f, 0x07, 0xc0, 0x01, 0x00, 0xcd, 0x55, 0x20, 0x22, 0x02, 0x1e, 0x88,
+        0x04, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x78, 0x20, 0x12, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0xe7, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x0f, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe0, 0xfc, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x2b, 0x1c, 0x0e,
+        0xc7, 0x0e, 0x87, 0xc3, 0x87, 0xc3, 0xe1, 0xd8, 0xe1, 0x70, 0xf8, 0x70, 0x38, 0x1c, 0x3b, 0x1c,
+        0x0e, 0x1f, 0x0e, 0x87, 0x63, 0x87, 0xc3, 0xe1
+        ];
+        let img = decode_webp(&data).unwrap().to_rgb();
+        assert_eq!(img.dimensions(), (8, 8));
+        assert_eq!(img.get_pixel(0, 0).data, [50, 50, 200]);
+        assert_eq!(img.get_pixel(1, 0).data, [200, 50, 50]);
+        assert!(decode_webp(b"RIFF\0\0\0\0WEBPjunk").is_err());
     }
 }
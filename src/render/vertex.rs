@@ -0,0 +1,190 @@
+use std::convert::TryFrom;
+use std::mem::size_of_val;
+use std::slice;
+
+use gl::types::GLenum;
+use gltf::accessor::{DataType, Item, Iter};
+
+use crate::draco::{self, Values};
+use crate::render::math::*;
+
+/// A vertex attribute in its source format (e.g. normalized `u8` texture coordinates
+/// or quantized positions), uploaded as a tightly packed block of the primitive's vertex buffer.
+pub struct VertexAttribute {
+    /// Shader attribute location (see pbr-vert.glsl)
+    pub location: u32,
+    pub components: i32,
+    pub component_type: GLenum,
+    pub normalized: bool,
+    pub data: Vec<u8>,
+}
+
+impl VertexAttribute {
+    pub fn new<T: Copy>(location: u32, components: i32, component_type: GLenum, normalized: bool, values: &[T]) -> Self {
+        VertexAttribute {
+            location,
+            components,
+            component_type,
+            normalized,
+            data: as_bytes(values).to_vec(),
+        }
+    }
+
+    /// Reads the accessor in its component type (supports all types allowed by `KHR_mesh_quantization`)
+    pub fn from_accessor(location: u32, accessor: &gltf::Accessor<'_>, buffers: &[gltf::buffer::Data]) -> Self {
+        let components = accessor.dimensions().multiplicity();
+        let data = match accessor.data_type() {
+            DataType::I8 => read_elements::<i8>(accessor, components, buffers),
+            DataType::U8 => read_elements::<u8>(accessor, components, buffers),
+            DataType::I16 => read_elements::<i16>(accessor, components, buffers),
+            DataType::U16 => read_elements::<u16>(accessor, components, buffers),
+            DataType::U32 => read_elements::<u32>(accessor, components, buffers),
+            DataType::F32 => read_elements::<f32>(accessor, components, buffers),
+        };
+        VertexAttribute {
+            location,
+            components: components as i32,
+            component_type: accessor.data_type().as_gl_enum(),
+            normalized: accessor.normalized(),
+            data,
+        }
+    }
+
+    /// Converts an attribute decoded from `KHR_draco_mesh_compression` to the component type of the accessor
+    pub fn from_draco(location: u32, accessor: &gltf::Accessor<'_>, attribute: &draco::Attribute) -> Self {
+        let components = attribute.components as i32;
+        let normalized = accessor.normalized();
+        match attribute.values {
+            Values::Float(ref values) => VertexAttribute::new(location, components, gl::FLOAT, false, values),
+            Values::Int(ref values) => {
+                let component_type = accessor.data_type().as_gl_enum();
+                match accessor.data_type() {
+                    DataType::I8 => Self::new(location, components, component_type, normalized, &cast::<i8>(values)),
+                    DataType::U8 => Self::new(location, components, component_type, normalized, &cast::<u8>(values)),
+                    DataType::I16 => Self::new(location, components, component_type, normalized, &cast::<i16>(values)),
+                    DataType::U16 => Self::new(location, components, component_type, normalized, &cast::<u16>(values)),
+                    DataType::U32 => Self::new(location, components, component_type, normalized, &cast::<u32>(values)),
+                    DataType::F32 => Self::new(location, components, gl::FLOAT, false,
+                        &values.iter().map(|&v| v as f32).collect::<Vec<_>>()),
+                }
+            }
+        }
+    }
+
+    /// Size of a single element in bytes
+    pub fn element_size(&self) -> usize {
+        self.components as usize * component_size(self.component_type)
+    }
+
+    /// Number of elements
+    pub fn count(&self) -> usize {
+        self.data.len() / self.element_size()
+    }
+
+    /// Resolves the given vertex indices (e.g. when de-indexing for flat normals)
+    pub fn remap(&self, indices: &[u32]) -> Self {
+        let element_size = self.element_size();
+        let mut data = Vec::with_capacity(indices.len() * element_size);
+        for &i in indices {
+            let start = i as usize * element_size;
+            data.extend_from_slice(&self.data[start..start + element_size]);
+        }
+        VertexAttribute { data, ..*self }
+    }
+
+    /// Converts the components to `f32` like the GPU does (dequantization of normalized types)
+    pub fn to_f32(&self) -> Vec<f32> {
+        let size = component_size(self.component_type);
+        self.data.chunks_exact(size)
+            .map(|c| match self.component_type {
+                gl::BYTE => f32::from(c[0] as i8),
+                gl::UNSIGNED_BYTE => f32::from(c[0]),
+                gl::SHORT => f32::from(i16::from_le_bytes([c[0], c[1]])),
+                gl::UNSIGNED_SHORT => f32::from(u16::from_le_bytes([c[0], c[1]])),
+                gl::UNSIGNED_INT => u32::from_le_bytes([c[0], c[1], c[2], c[3]]) as f32,
+                _ => f32::from_le_bytes([c[0], c[1], c[2], c[3]]),
+            })
+            .map(|value| dequantize(value, self.component_type, self.normalized))
+            .collect()
+    }
+}
+
+/// Converts a (raw) component value to the value seen by the shader
+pub fn dequantize(value: f32, component_type: GLenum, normalized: bool) -> f32 {
+    if !normalized {
+        return value
+    }
+    match component_type {
+        gl::BYTE => (value / 127.0).max(-1.0),
+        gl::UNSIGNED_BYTE => value / 255.0,
+        gl::SHORT => (value / 32767.0).max(-1.0),
+        gl::UNSIGNED_SHORT => value / 65535.0,
+        _ => value,
+    }
+}
+
+/// Bounding box of the primitive as seen by the shader: accessor min/max contain
+/// the raw values, so normalized (quantized) positions need to be dequantized.
+/// `None` if the primitive has no positions.
+pub fn primitive_bounds(g_primitive: &gltf::Primitive<'_>) -> Option<Aabb3> {
+    let accessor = g_primitive.get(&gltf::Semantic::Positions)?;
+    let bounds = g_primitive.bounding_box();
+    let component_type = accessor.data_type().as_gl_enum();
+    let normalized = accessor.normalized();
+    let dequantize = |[x, y, z]: [f32; 3]| Point3::new(
+        dequantize(x, component_type, normalized),
+        dequantize(y, component_type, normalized),
+        dequantize(z, component_type, normalized));
+    Some(Aabb3 {
+        min: dequantize(bounds.min),
+        max: dequantize(bounds.max),
+    })
+}
+
+fn component_size(component_type: GLenum) -> usize {
+    match component_type {
+        gl::BYTE | gl::UNSIGNED_BYTE => 1,
+        gl::SHORT | gl::UNSIGNED_SHORT => 2,
+        _ => 4,
+    }
+}
+
+/// Truncates the decoded integers to the component type (like the `static_cast` of the Draco decoder)
+fn cast<T: TryFrom<i64> + Default>(values: &[i32]) -> Vec<T> {
+    values.iter().map(|&v| T::try_from(i64::from(v)).unwrap_or_default()).collect()
+}
+
+fn as_bytes<T: Copy>(values: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(values.as_ptr() as *const u8, size_of_val(values)) }
+}
+
+fn read_elements<C>(accessor: &gltf::Accessor<'_>, components: usize, buffers: &[gltf::buffer::Data]) -> Vec<u8>
+    where C: Item + Copy
+{
+    match components {
+        1 => read::<C>(accessor, buffers),
+        2 => read::<[C; 2]>(accessor, buffers),
+        3 => read::<[C; 3]>(accessor, buffers),
+        _ => read::<[C; 4]>(accessor, buffers),
+    }
+}
+
+fn read<T: Item + Copy>(accessor: &gltf::Accessor<'_>, buffers: &[gltf::buffer::Data]) -> Vec<u8> {
+    let values: Vec<T> = Iter::new(accessor.clone(), |buffer: gltf::Buffer<'_>| buffers.get(buffer.index()).map(|data| &data[..]))
+        .unwrap_or_else(|| panic!("missing buffer data for accessor {}", accessor.index()))
+        .collect();
+    as_bytes(&values).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dequantize_normalized() {
+        let attribute = VertexAttribute::new(0, 2, gl::SHORT, true, &[[32767i16, -32768], [0, 16384]]);
+        assert_eq!(attribute.to_f32(), vec![1.0, -1.0, 0.0, 16384.0 / 32767.0]);
+        let attribute = VertexAttribute::new(0, 2, gl::UNSIGNED_BYTE, false, &[[255u8, 1]]);
+        assert_eq!(attribute.to_f32(), vec![255.0, 1.0]);
+    }
+}
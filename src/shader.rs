@@ -8,7 +8,7 @@ use std::str;
 use gl;
 use gl::types::*;
 
-use cgmath::{Matrix, Matrix4, Vector3, Vector4};
+use cgmath::{Matrix, Matrix3, Matrix4, Vector3, Vector4};
 use cgmath::prelude::*;
 
 use bitflags::bitflags;
@@ -38,6 +38,15 @@ impl Shader {
     }
 
     pub fn from_source(vertex_code: &str, fragment_code: &str, defines: &[String]) -> Shader {
+        Self::from_source_with_geometry(vertex_code, None, fragment_code, defines)
+    }
+
+    pub fn from_source_with_geometry(
+        vertex_code: &str,
+        geometry_code: Option<&str>,
+        fragment_code: &str,
+        defines: &[String]) -> Shader
+    {
         let mut shader = Shader {
             id: 0,
             uniform_location_cache: HashMap::new()
@@ -45,6 +54,10 @@ impl Shader {
 
         let vertex_code = Self::add_defines(vertex_code, defines);
         let v_shader_code = CString::new(vertex_code.as_bytes()).unwrap();
+        let g_shader_code = geometry_code.map(|geometry_code| {
+            let geometry_code = Self::add_defines(geometry_code, defines);
+            CString::new(geometry_code.as_bytes()).unwrap()
+        });
         let fragment_code = Self::add_defines(fragment_code, defines);
         let f_shader_code = CString::new(fragment_code.as_bytes()).unwrap();
 
@@ -55,6 +68,14 @@ impl Shader {
             gl::ShaderSource(vertex, 1, &v_shader_code.as_ptr(), ptr::null());
             gl::CompileShader(vertex);
             shader.check_compile_errors(vertex, "VERTEX");
+            // geometry shader (optional)
+            let geometry = g_shader_code.map(|g_shader_code| {
+                let geometry = gl::CreateShader(gl::GEOMETRY_SHADER);
+                gl::ShaderSource(geometry, 1, &g_shader_code.as_ptr(), ptr::null());
+                gl::CompileShader(geometry);
+                shader.check_compile_errors(geometry, "GEOMETRY");
+                geometry
+            });
             // fragment Shader
             let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
             gl::ShaderSource(fragment, 1, &f_shader_code.as_ptr(), ptr::null());
@@ -63,11 +84,17 @@ impl Shader {
             // shader Program
             let id = gl::CreateProgram();
             gl::AttachShader(id, vertex);
+            if let Some(geometry) = geometry {
+                gl::AttachShader(id, geometry);
+            }
             gl::AttachShader(id, fragment);
             gl::LinkProgram(id);
             shader.check_compile_errors(id, "PROGRAM");
             // delete the shaders as they're linked into our program now and no longer necessary
             gl::DeleteShader(vertex);
+            if let Some(geometry) = geometry {
+                gl::DeleteShader(geometry);
+            }
             gl::DeleteShader(fragment);
             shader.id = id;
         }
@@ -129,6 +156,10 @@ impl Shader {
         gl::Uniform3f(location, x, y, z);
     }
     /// ------------------------------------------------------------------------
+    pub unsafe fn set_mat3(&self, location: i32, mat: &Matrix3<f32>) {
+        gl::UniformMatrix3fv(location, 1, gl::FALSE, mat.as_ptr());
+    }
+    /// ------------------------------------------------------------------------
     pub unsafe fn set_mat4(&self, location: i32, mat: &Matrix4<f32>) {
         gl::UniformMatrix4fv(location, 1, gl::FALSE, mat.as_ptr());
     }
@@ -182,7 +213,7 @@ impl Shader {
 
 bitflags! {
     /// Flags matching the defines in the PBR shader
-    pub struct ShaderFlags: u16 {
+    pub struct ShaderFlags: u32 {
         // vertex shader + fragment shader
         const HAS_NORMALS           = 1;
         const HAS_TANGENTS          = 1 << 1;
@@ -197,13 +228,43 @@ bitflags! {
         const HAS_METALROUGHNESSMAP = 1 << 8;
         const HAS_OCCLUSIONMAP      = 1 << 9;
         const USE_TEX_LOD           = 1 << 10;
+        /// Output the base color without lighting and tone mapping
+        /// (`KHR_materials_unlit`, or POINTS/LINES without NORMAL)
+        const UNLIT                 = 1 << 11;
+
+        // geometry shader
+        /// Expand LINES to screen-space quads of `u_LineWidth` pixels
+        const WIDE_LINES            = 1 << 12;
+
+        // fragment shader only (KHR_materials_clearcoat)
+        const HAS_CLEARCOAT                 = 1 << 13;
+        const HAS_CLEARCOATMAP              = 1 << 14;
+        const HAS_CLEARCOATROUGHNESSMAP     = 1 << 15;
+        const HAS_CLEARCOATNORMALMAP        = 1 << 16;
+
+        // fragment shader only (KHR_materials_transmission, KHR_materials_volume)
+        const HAS_TRANSMISSION              = 1 << 17;
+        const HAS_TRANSMISSIONMAP           = 1 << 18;
+        const HAS_VOLUME                    = 1 << 19;
+        const HAS_THICKNESSMAP              = 1 << 20;
+
+        // fragment shader only (KHR_materials_sheen, KHR_materials_specular)
+        const HAS_SHEEN                     = 1 << 21;
+        const HAS_SHEENCOLORMAP             = 1 << 22;
+        const HAS_SHEENROUGHNESSMAP         = 1 << 23;
+        const HAS_SPECULARMAP               = 1 << 24;
+        const HAS_SPECULARCOLORMAP          = 1 << 25;
+
+        // fragment shader only (KHR_materials_pbrSpecularGlossiness)
+        const HAS_SPECULARGLOSSINESS        = 1 << 26;
+        const HAS_SPECULARGLOSSINESSMAP     = 1 << 27;
     }
 }
 
 impl ShaderFlags {
     pub fn as_strings(self) -> Vec<String> {
-        (0..15)
-            .map(|i| 1u16 << i)
+        (0..32)
+            .map(|i| 1u32 << i)
             .filter(|i| self.bits & i != 0)
             .map(|i| format!("{:?}", ShaderFlags::from_bits_truncate(i)))
             .collect()
@@ -216,6 +277,7 @@ pub struct PbrUniformLocations {
     // TODO!: UBO for matrices, camera, light(s)?
     pub u_MVPMatrix: i32,
     pub u_ModelMatrix: i32,
+    pub u_NormalMatrix: i32,
     pub u_Camera: i32,
 
     pub u_LightDirection: i32,
@@ -233,27 +295,86 @@ pub struct PbrUniformLocations {
 
     pub u_BaseColorSampler: i32,
     pub u_BaseColorTexCoord: i32,
+    pub u_BaseColorUVTransform: i32,
     pub u_BaseColorFactor: i32,
 
     pub u_NormalSampler: i32,
     pub u_NormalTexCoord: i32,
+    pub u_NormalUVTransform: i32,
     pub u_NormalScale: i32,
 
     pub u_EmissiveSampler: i32,
     pub u_EmissiveTexCoord: i32,
+    pub u_EmissiveUVTransform: i32,
     pub u_EmissiveFactor: i32,
 
     pub u_MetallicRoughnessSampler: i32,
     pub u_MetallicRoughnessTexCoord: i32,
+    pub u_MetallicRoughnessUVTransform: i32,
     pub u_MetallicRoughnessValues: i32,
+    pub u_SpecularGlossinessSampler: i32,
+    pub u_SpecularGlossinessTexCoord: i32,
+    pub u_SpecularGlossinessUVTransform: i32,
+    pub u_SpecularGlossinessValues: i32,
 
     pub u_OcclusionSampler: i32,
     pub u_OcclusionTexCoord: i32,
+    pub u_OcclusionUVTransform: i32,
     pub u_OcclusionStrength: i32,
 
+    pub u_ClearcoatFactor: i32,
+    pub u_ClearcoatSampler: i32,
+    pub u_ClearcoatTexCoord: i32,
+    pub u_ClearcoatUVTransform: i32,
+    pub u_ClearcoatRoughnessFactor: i32,
+    pub u_ClearcoatRoughnessSampler: i32,
+    pub u_ClearcoatRoughnessTexCoord: i32,
+    pub u_ClearcoatRoughnessUVTransform: i32,
+    pub u_ClearcoatNormalSampler: i32,
+    pub u_ClearcoatNormalTexCoord: i32,
+    pub u_ClearcoatNormalUVTransform: i32,
+    pub u_ClearcoatNormalScale: i32,
+
+    pub u_TransmissionFactor: i32,
+    pub u_TransmissionSampler: i32,
+    pub u_TransmissionTexCoord: i32,
+    pub u_TransmissionUVTransform: i32,
+    pub u_TransmissionFramebufferSampler: i32,
+    pub u_ViewProjectionMatrix: i32,
+    pub u_Ior: i32,
+
+    pub u_ThicknessFactor: i32,
+    pub u_ThicknessSampler: i32,
+    pub u_ThicknessTexCoord: i32,
+    pub u_ThicknessUVTransform: i32,
+    pub u_AttenuationDistance: i32,
+    pub u_AttenuationColor: i32,
+
+    pub u_SheenColorFactor: i32,
+    pub u_SheenColorSampler: i32,
+    pub u_SheenColorTexCoord: i32,
+    pub u_SheenColorUVTransform: i32,
+    pub u_SheenRoughnessFactor: i32,
+    pub u_SheenRoughnessSampler: i32,
+    pub u_SheenRoughnessTexCoord: i32,
+    pub u_SheenRoughnessUVTransform: i32,
+    pub u_SheenELUT: i32,
+
+    pub u_SpecularFactor: i32,
+    pub u_SpecularSampler: i32,
+    pub u_SpecularTexCoord: i32,
+    pub u_SpecularUVTransform: i32,
+    pub u_SpecularColorFactor: i32,
+    pub u_SpecularColorSampler: i32,
+    pub u_SpecularColorTexCoord: i32,
+    pub u_SpecularColorUVTransform: i32,
+
     pub u_AlphaBlend: i32,
     pub u_AlphaCutoff: i32,
 
+    pub u_ViewportSize: i32,
+    pub u_LineWidth: i32,
+
     // TODO!: use/remove debugging uniforms
     // debugging flags used for shader output of intermediate PBR variables
     pub u_ScaleDiffBaseMR: i32,
@@ -268,11 +389,20 @@ pub struct PbrShader {
 }
 
 impl PbrShader {
-    pub fn new(flags: ShaderFlags) -> Self {
-        let mut shader = Shader::from_source(
+    pub fn new(flags: ShaderFlags, num_tex_coord_sets: u32) -> Self {
+        let mut defines = flags.as_strings();
+        // the varyings need at least one element
+        defines.push(format!("UV_SETS {}", num_tex_coord_sets.max(1)));
+        let geometry_code = if flags.contains(ShaderFlags::WIDE_LINES) {
+            Some(include_str!("shaders/pbr-lines-geom.glsl"))
+        } else {
+            None
+        };
+        let mut shader = Shader::from_source_with_geometry(
             include_str!("shaders/pbr-vert.glsl"),
+            geometry_code,
             include_str!("shaders/pbr-frag.glsl"),
-            &flags.as_strings());
+            &defines);
 
         // NOTE: shader debug version
         // let mut shader = Shader::new(
@@ -284,6 +414,7 @@ impl PbrShader {
             let uniforms = PbrUniformLocations {
                 u_MVPMatrix: shader.uniform_location("u_MVPMatrix"),
                 u_ModelMatrix: shader.uniform_location("u_ModelMatrix"),
+                u_NormalMatrix: shader.uniform_location("u_NormalMatrix"),
                 u_Camera: shader.uniform_location("u_Camera"),
 
                 u_LightDirection: shader.uniform_location("u_LightDirection"),
@@ -298,27 +429,86 @@ impl PbrShader {
 
                 u_BaseColorSampler: shader.uniform_location("u_BaseColorSampler"),
                 u_BaseColorTexCoord: shader.uniform_location("u_BaseColorTexCoord"),
+                u_BaseColorUVTransform: shader.uniform_location("u_BaseColorUVTransform"),
                 u_BaseColorFactor: shader.uniform_location("u_BaseColorFactor"),
 
                 u_NormalSampler: shader.uniform_location("u_NormalSampler"),
                 u_NormalTexCoord: shader.uniform_location("u_NormalTexCoord"),
+                u_NormalUVTransform: shader.uniform_location("u_NormalUVTransform"),
                 u_NormalScale: shader.uniform_location("u_NormalScale"),
 
                 u_EmissiveSampler: shader.uniform_location("u_EmissiveSampler"),
                 u_EmissiveTexCoord: shader.uniform_location("u_EmissiveTexCoord"),
+                u_EmissiveUVTransform: shader.uniform_location("u_EmissiveUVTransform"),
                 u_EmissiveFactor: shader.uniform_location("u_EmissiveFactor"),
 
                 u_MetallicRoughnessSampler: shader.uniform_location("u_MetallicRoughnessSampler"),
                 u_MetallicRoughnessTexCoord: shader.uniform_location("u_MetallicRoughnessTexCoord"),
+                u_MetallicRoughnessUVTransform: shader.uniform_location("u_MetallicRoughnessUVTransform"),
                 u_MetallicRoughnessValues: shader.uniform_location("u_MetallicRoughnessValues"),
+                u_SpecularGlossinessSampler: shader.uniform_location("u_SpecularGlossinessSampler"),
+                u_SpecularGlossinessTexCoord: shader.uniform_location("u_SpecularGlossinessTexCoord"),
+                u_SpecularGlossinessUVTransform: shader.uniform_location("u_SpecularGlossinessUVTransform"),
+                u_SpecularGlossinessValues: shader.uniform_location("u_SpecularGlossinessValues"),
 
                 u_OcclusionSampler: shader.uniform_location("u_OcclusionSampler"),
                 u_OcclusionTexCoord: shader.uniform_location("u_OcclusionTexCoord"),
+                u_OcclusionUVTransform: shader.uniform_location("u_OcclusionUVTransform"),
                 u_OcclusionStrength: shader.uniform_location("u_OcclusionStrength"),
 
+                u_ClearcoatFactor: shader.uniform_location("u_ClearcoatFactor"),
+                u_ClearcoatSampler: shader.uniform_location("u_ClearcoatSampler"),
+                u_ClearcoatTexCoord: shader.uniform_location("u_ClearcoatTexCoord"),
+                u_ClearcoatUVTransform: shader.uniform_location("u_ClearcoatUVTransform"),
+                u_ClearcoatRoughnessFactor: shader.uniform_location("u_ClearcoatRoughnessFactor"),
+                u_ClearcoatRoughnessSampler: shader.uniform_location("u_ClearcoatRoughnessSampler"),
+                u_ClearcoatRoughnessTexCoord: shader.uniform_location("u_ClearcoatRoughnessTexCoord"),
+                u_ClearcoatRoughnessUVTransform: shader.uniform_location("u_ClearcoatRoughnessUVTransform"),
+                u_ClearcoatNormalSampler: shader.uniform_location("u_ClearcoatNormalSampler"),
+                u_ClearcoatNormalTexCoord: shader.uniform_location("u_ClearcoatNormalTexCoord"),
+                u_ClearcoatNormalUVTransform: shader.uniform_location("u_ClearcoatNormalUVTransform"),
+                u_ClearcoatNormalScale: shader.uniform_location("u_ClearcoatNormalScale"),
+
+                u_TransmissionFactor: shader.uniform_location("u_TransmissionFactor"),
+                u_TransmissionSampler: shader.uniform_location("u_TransmissionSampler"),
+                u_TransmissionTexCoord: shader.uniform_location("u_TransmissionTexCoord"),
+                u_TransmissionUVTransform: shader.uniform_location("u_TransmissionUVTransform"),
+                u_TransmissionFramebufferSampler: shader.uniform_location("u_TransmissionFramebufferSampler"),
+                u_ViewProjectionMatrix: shader.uniform_location("u_ViewProjectionMatrix"),
+                u_Ior: shader.uniform_location("u_Ior"),
+
+                u_ThicknessFactor: shader.uniform_location("u_ThicknessFactor"),
+                u_ThicknessSampler: shader.uniform_location("u_ThicknessSampler"),
+                u_ThicknessTexCoord: shader.uniform_location("u_ThicknessTexCoord"),
+                u_ThicknessUVTransform: shader.uniform_location("u_ThicknessUVTransform"),
+                u_AttenuationDistance: shader.uniform_location("u_AttenuationDistance"),
+                u_AttenuationColor: shader.uniform_location("u_AttenuationColor"),
+
+                u_SheenColorFactor: shader.uniform_location("u_SheenColorFactor"),
+                u_SheenColorSampler: shader.uniform_location("u_SheenColorSampler"),
+                u_SheenColorTexCoord: shader.uniform_location("u_SheenColorTexCoord"),
+                u_SheenColorUVTransform: shader.uniform_location("u_SheenColorUVTransform"),
+                u_SheenRoughnessFactor: shader.uniform_location("u_SheenRoughnessFactor"),
+                u_SheenRoughnessSampler: shader.uniform_location("u_SheenRoughnessSampler"),
+                u_SheenRoughnessTexCoord: shader.uniform_location("u_SheenRoughnessTexCoord"),
+                u_SheenRoughnessUVTransform: shader.uniform_location("u_SheenRoughnessUVTransform"),
+                u_SheenELUT: shader.uniform_location("u_SheenELUT"),
+
+                u_SpecularFactor: shader.uniform_location("u_SpecularFactor"),
+                u_SpecularSampler: shader.uniform_location("u_SpecularSampler"),
+                u_SpecularTexCoord: shader.uniform_location("u_SpecularTexCoord"),
+                u_SpecularUVTransform: shader.uniform_location("u_SpecularUVTransform"),
+                u_SpecularColorFactor: shader.uniform_location("u_SpecularColorFactor"),
+                u_SpecularColorSampler: shader.uniform_location("u_SpecularColorSampler"),
+                u_SpecularColorTexCoord: shader.uniform_location("u_SpecularColorTexCoord"),
+                u_SpecularColorUVTransform: shader.uniform_location("u_SpecularColorUVTransform"),
+
                 u_AlphaBlend: shader.uniform_location("u_AlphaBlend"),
                 u_AlphaCutoff: shader.uniform_location("u_AlphaCutoff"),
 
+                u_ViewportSize: shader.uniform_location("u_ViewportSize"),
+                u_LineWidth: shader.uniform_location("u_LineWidth"),
+
                 u_ScaleDiffBaseMR: shader.uniform_location("u_ScaleDiffBaseMR"),
                 u_ScaleFGDSpec: shader.uniform_location("u_ScaleFGDSpec"),
                 u_ScaleIBLAmbient: shader.uniform_location("u_ScaleIBLAmbient"),
@@ -329,7 +519,19 @@ impl PbrShader {
             shader.set_int(uniforms.u_NormalSampler, 1);
             shader.set_int(uniforms.u_EmissiveSampler, 2);
             shader.set_int(uniforms.u_MetallicRoughnessSampler, 3);
+            shader.set_int(uniforms.u_SpecularGlossinessSampler, 3); // replaces the metallic-roughness texture
             shader.set_int(uniforms.u_OcclusionSampler, 4);
+            shader.set_int(uniforms.u_ClearcoatSampler, 5);
+            shader.set_int(uniforms.u_ClearcoatRoughnessSampler, 6);
+            shader.set_int(uniforms.u_ClearcoatNormalSampler, 7);
+            shader.set_int(uniforms.u_TransmissionSampler, 8);
+            shader.set_int(uniforms.u_ThicknessSampler, 9);
+            shader.set_int(uniforms.u_TransmissionFramebufferSampler, 10);
+            shader.set_int(uniforms.u_SheenColorSampler, 11);
+            shader.set_int(uniforms.u_SheenRoughnessSampler, 12);
+            shader.set_int(uniforms.u_SpecularSampler, 13);
+            shader.set_int(uniforms.u_SpecularColorSampler, 14);
+            shader.set_int(uniforms.u_SheenELUT, 15);
 
             shader.set_vec3(uniforms.u_LightColor, 5.0, 5.0, 5.0);
             // TODO!: optional minus on z
@@ -0,0 +1,13 @@
+#version 330 core
+in vec2 v_UV;
+
+uniform sampler2D u_Font;
+uniform vec4 u_Color;
+
+out vec4 FragColor;
+
+void main()
+{
+    float coverage = texture(u_Font, v_UV).r;
+    FragColor = vec4(u_Color.rgb, u_Color.a * coverage);
+}
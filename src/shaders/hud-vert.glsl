@@ -0,0 +1,14 @@
+#version 330 core
+layout (location = 0) in vec2 a_Position; // in pixels, origin top left
+layout (location = 1) in vec2 a_UV;
+
+uniform vec2 u_ScreenSize;
+
+out vec2 v_UV;
+
+void main()
+{
+    v_UV = a_UV;
+    vec2 ndc = a_Position / u_ScreenSize * 2.0 - 1.0;
+    gl_Position = vec4(ndc.x, -ndc.y, 0.0, 1.0);
+}
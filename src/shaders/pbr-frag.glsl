@@ -35,27 +35,117 @@ uniform sampler2D u_brdfLUT;
 #ifdef HAS_BASECOLORMAP
 uniform sampler2D u_BaseColorSampler;
 uniform int u_BaseColorTexCoord;
+uniform mat3 u_BaseColorUVTransform; // KHR_texture_transform
 #endif
 #ifdef HAS_NORMALMAP
 uniform sampler2D u_NormalSampler;
 uniform int u_NormalTexCoord;
+uniform mat3 u_NormalUVTransform; // KHR_texture_transform
 uniform float u_NormalScale;
 #endif
 #ifdef HAS_EMISSIVEMAP
 uniform sampler2D u_EmissiveSampler;
 uniform int u_EmissiveTexCoord;
-uniform vec3 u_EmissiveFactor;
+uniform mat3 u_EmissiveUVTransform; // KHR_texture_transform
 #endif
+// may exceed 1 (KHR_materials_emissive_strength)
+uniform vec3 u_EmissiveFactor;
 #ifdef HAS_METALROUGHNESSMAP
 uniform sampler2D u_MetallicRoughnessSampler;
 uniform int u_MetallicRoughnessTexCoord;
+uniform mat3 u_MetallicRoughnessUVTransform; // KHR_texture_transform
+#endif
+#ifdef HAS_SPECULARGLOSSINESS
+uniform vec4 u_SpecularGlossinessValues; // KHR_materials_pbrSpecularGlossiness: specular (rgb), glossiness (a)
+#endif
+#ifdef HAS_SPECULARGLOSSINESSMAP
+uniform sampler2D u_SpecularGlossinessSampler;
+uniform int u_SpecularGlossinessTexCoord;
+uniform mat3 u_SpecularGlossinessUVTransform;
 #endif
 #ifdef HAS_OCCLUSIONMAP
 uniform sampler2D u_OcclusionSampler;
 uniform int u_OcclusionTexCoord;
+uniform mat3 u_OcclusionUVTransform; // KHR_texture_transform
 uniform float u_OcclusionStrength;
 #endif
 
+#ifdef HAS_CLEARCOAT
+uniform float u_ClearcoatFactor;
+uniform float u_ClearcoatRoughnessFactor;
+#endif
+#ifdef HAS_CLEARCOATMAP
+uniform sampler2D u_ClearcoatSampler;
+uniform int u_ClearcoatTexCoord;
+uniform mat3 u_ClearcoatUVTransform;
+#endif
+#ifdef HAS_CLEARCOATROUGHNESSMAP
+uniform sampler2D u_ClearcoatRoughnessSampler;
+uniform int u_ClearcoatRoughnessTexCoord;
+uniform mat3 u_ClearcoatRoughnessUVTransform;
+#endif
+#ifdef HAS_CLEARCOATNORMALMAP
+uniform sampler2D u_ClearcoatNormalSampler;
+uniform int u_ClearcoatNormalTexCoord;
+uniform mat3 u_ClearcoatNormalUVTransform;
+uniform float u_ClearcoatNormalScale;
+#endif
+
+#ifdef HAS_TRANSMISSION
+uniform float u_TransmissionFactor;
+// scene color without transmissive primitives (mip-mapped)
+uniform sampler2D u_TransmissionFramebufferSampler;
+uniform mat4 u_ViewProjectionMatrix;
+#endif
+#ifdef HAS_TRANSMISSIONMAP
+uniform sampler2D u_TransmissionSampler;
+uniform int u_TransmissionTexCoord;
+uniform mat3 u_TransmissionUVTransform;
+#endif
+#ifdef HAS_VOLUME
+uniform float u_ThicknessFactor;
+uniform float u_AttenuationDistance;
+uniform vec3 u_AttenuationColor;
+uniform mat4 u_ModelMatrix;
+#endif
+#ifdef HAS_THICKNESSMAP
+uniform sampler2D u_ThicknessSampler;
+uniform int u_ThicknessTexCoord;
+uniform mat3 u_ThicknessUVTransform;
+#endif
+
+// KHR_materials_ior, KHR_materials_specular
+uniform float u_Ior;
+uniform float u_SpecularFactor;
+uniform vec3 u_SpecularColorFactor;
+#ifdef HAS_SPECULARMAP
+uniform sampler2D u_SpecularSampler;
+uniform int u_SpecularTexCoord;
+uniform mat3 u_SpecularUVTransform;
+#endif
+#ifdef HAS_SPECULARCOLORMAP
+uniform sampler2D u_SpecularColorSampler;
+uniform int u_SpecularColorTexCoord;
+uniform mat3 u_SpecularColorUVTransform;
+#endif
+
+#ifdef HAS_SHEEN
+uniform vec3 u_SheenColorFactor;
+uniform float u_SheenRoughnessFactor;
+// directional albedo of the sheen by (NdotV, sheenRoughness)
+uniform sampler2D u_SheenELUT;
+#endif
+#ifdef HAS_SHEENCOLORMAP
+uniform sampler2D u_SheenColorSampler;
+uniform int u_SheenColorTexCoord;
+uniform mat3 u_SheenColorUVTransform;
+#endif
+#ifdef HAS_SHEENROUGHNESSMAP
+uniform sampler2D u_SheenRoughnessSampler;
+uniform int u_SheenRoughnessTexCoord;
+uniform mat3 u_SheenRoughnessUVTransform;
+#endif
+
 uniform vec2 u_MetallicRoughnessValues;
 uniform vec4 u_BaseColorFactor;
 
@@ -70,21 +160,23 @@ uniform vec4 u_ScaleDiffBaseMR;
 uniform vec4 u_ScaleFGDSpec;
 uniform vec4 u_ScaleIBLAmbient;
 
-in vec3 v_Position;
-
-in vec2 v_UV[2];
-
-in vec4 v_Color;
+in Varyings {
+    vec3 v_Position;
+    vec2 v_UV[UV_SETS];
+    vec4 v_Color;
 
 #ifdef HAS_NORMALS
 #ifdef HAS_TANGENTS
-in mat3 v_TBN;
+    mat3 v_TBN;
 #else
-in vec3 v_Normal;
+    vec3 v_Normal;
 #endif
 #endif
+};
 
-out vec4 FragColor;
+layout(location = 0) out vec4 FragColor;
+// 1 for unlit fragments (no exposure/tone mapping); alpha for blending
+layout(location = 1) out vec4 UnlitMask;
 
 // Encapsulate the various inputs used by the various functions in the shading equation
 // We store values in this struct to simplify the integration of alternative implementations
@@ -108,16 +200,26 @@ struct PBRInfo
 const float M_PI = 3.141592653589793;
 const float c_MinRoughness = 0.04;
 
-// Find the normal for this fragment, pulling either from a predefined normal map
-// or from the interpolated mesh normal and tangent attributes.
-vec3 getNormal()
+// Texture coordinates of a texture slot (set and KHR_texture_transform)
+vec2 getUV(int texCoord, mat3 transform)
+{
+    return (transform * vec3(v_UV[texCoord], 1.0)).xy;
+}
+
+// Retrieve the tangent space matrix, from the interpolated mesh normal and tangent attributes
+// or from screen-space derivatives
+mat3 getTBN()
 {
-    // Retrieve the tangent space matrix
 #ifndef HAS_TANGENTS
     vec3 pos_dx = dFdx(v_Position);
     vec3 pos_dy = dFdy(v_Position);
-    vec3 tex_dx = dFdx(vec3(v_UV[0], 0.0));
-    vec3 tex_dy = dFdy(vec3(v_UV[0], 0.0));
+#ifdef HAS_NORMALMAP
+    vec2 uv = getUV(u_NormalTexCoord, u_NormalUVTransform);
+#else
+    vec2 uv = v_UV[0];
+#endif
+    vec3 tex_dx = dFdx(vec3(uv, 0.0));
+    vec3 tex_dy = dFdy(vec3(uv, 0.0));
     vec3 t = (tex_dy.t * pos_dx - tex_dx.t * pos_dy) / (tex_dx.s * tex_dy.t - tex_dy.s * tex_dx.t);
 
 #ifdef HAS_NORMALS
@@ -132,21 +234,46 @@ vec3 getNormal()
 #else // HAS_TANGENTS
     mat3 tbn = v_TBN;
 #endif
+    return tbn;
+}
 
+// Reverse backface normals
+vec3 faceForward(vec3 n)
+{
+#ifndef WIDE_LINES
+    // TODO!: correct/best place? -> https://github.com/KhronosGroup/glTF-WebGL-PBR/issues/51
+    n *= (2.0 * float(gl_FrontFacing) - 1.0);
+#endif
+    return n;
+}
+
+// Find the normal for this fragment, pulling either from a predefined normal map
+// or from the interpolated mesh normal.
+vec3 getNormal(mat3 tbn)
+{
 #ifdef HAS_NORMALMAP
-    vec3 n = texture(u_NormalSampler, v_UV[u_NormalTexCoord]).rgb;
+    vec3 n = texture(u_NormalSampler, getUV(u_NormalTexCoord, u_NormalUVTransform)).rgb;
     n = normalize(tbn * ((2.0 * n - 1.0) * vec3(u_NormalScale, u_NormalScale, 1.0)));
 #else
     // The tbn matrix is linearly interpolated, so we need to re-normalize
     vec3 n = normalize(tbn[2].xyz);
 #endif
+    return faceForward(n);
+}
 
-    // reverse backface normals
-    // TODO!: correct/best place? -> https://github.com/KhronosGroup/glTF-WebGL-PBR/issues/51
-    n *= (2.0 * float(gl_FrontFacing) - 1.0);
-
-    return n;
+#ifdef HAS_CLEARCOAT
+// The clear coat normal is independent of the base normal map
+vec3 getClearcoatNormal(mat3 tbn)
+{
+#ifdef HAS_CLEARCOATNORMALMAP
+    vec3 n = texture(u_ClearcoatNormalSampler, getUV(u_ClearcoatNormalTexCoord, u_ClearcoatNormalUVTransform)).rgb;
+    n = normalize(tbn * ((2.0 * n - 1.0) * vec3(u_ClearcoatNormalScale, u_ClearcoatNormalScale, 1.0)));
+#else
+    vec3 n = normalize(tbn[2].xyz);
+#endif
+    return faceForward(n);
 }
+#endif
 
 #ifdef USE_IBL
 // Calculation of the lighting contribution from an optional Image Based Light source.
@@ -177,6 +304,41 @@ vec3 getIBLContribution(PBRInfo pbrInputs, vec3 n, vec3 reflection)
 }
 #endif
 
+#ifdef HAS_TRANSMISSION
+// Light transmitted through the surface (KHR_materials_transmission): the scene color behind it,
+// blurred according to the roughness, and with a volume (KHR_materials_volume) refracted and attenuated.
+vec3 getTransmission(vec3 n, vec3 v, vec3 baseColor, float perceptualRoughness)
+{
+#ifdef HAS_VOLUME
+    float thickness = u_ThicknessFactor;
+#ifdef HAS_THICKNESSMAP
+    thickness *= texture(u_ThicknessSampler, getUV(u_ThicknessTexCoord, u_ThicknessUVTransform)).g;
+#endif
+    // the thickness is given in the coordinate space of the mesh
+    vec3 modelScale = vec3(length(u_ModelMatrix[0].xyz), length(u_ModelMatrix[1].xyz), length(u_ModelMatrix[2].xyz));
+    vec3 transmissionRay = normalize(refract(-v, n, 1.0 / u_Ior)) * thickness * modelScale;
+#else
+    // thin-walled
+    vec3 transmissionRay = vec3(0.0);
+#endif
+    vec4 ndc = u_ViewProjectionMatrix * vec4(v_Position + transmissionRay, 1.0);
+    vec2 uv = (ndc.xy / ndc.w + 1.0) / 2.0;
+
+    // rougher surfaces (and higher IORs) scatter the transmitted light more
+    float roughness = perceptualRoughness * clamp(u_Ior * 2.0 - 2.0, 0.0, 1.0);
+    float lod = log2(float(textureSize(u_TransmissionFramebufferSampler, 0).x)) * roughness;
+    vec3 transmittedLight = textureLod(u_TransmissionFramebufferSampler, uv, lod).rgb;
+
+#ifdef HAS_VOLUME
+    // Beer's law (no attenuation for an infinite distance)
+    if (!isinf(u_AttenuationDistance)) {
+        transmittedLight *= pow(u_AttenuationColor, vec3(length(transmissionRay) / u_AttenuationDistance));
+    }
+#endif
+    return transmittedLight * baseColor;
+}
+#endif
+
 // Basic Lambertian diffuse
 // Implementation from Lambert's Photometria https://archive.org/details/lambertsphotome00lambgoog
 // See also [1], Equation 1
@@ -217,8 +379,56 @@ float microfacetDistribution(PBRInfo pbrInputs)
     return roughnessSq / (M_PI * f * f);
 }
 
+#ifdef HAS_SHEEN
+// Sheen BRDF of KHR_materials_sheen, as in the Khronos glTF sample viewer:
+// "Charlie" distribution and the visibility term of Estevez and Kulla,
+// "Production Friendly Microfacet Sheen BRDF"
+float sheenDistribution(float sheenRoughness, float NdotH)
+{
+    float alphaG = sheenRoughness * sheenRoughness;
+    float invR = 1.0 / alphaG;
+    float sin2h = 1.0 - NdotH * NdotH;
+    return (2.0 + invR) * pow(sin2h, invR * 0.5) / (2.0 * M_PI);
+}
+
+float lambdaSheenNumericHelper(float x, float alphaG)
+{
+    float oneMinusAlphaSq = (1.0 - alphaG) * (1.0 - alphaG);
+    float a = mix(21.5473, 25.3245, oneMinusAlphaSq);
+    float b = mix(3.82987, 3.32435, oneMinusAlphaSq);
+    float c = mix(0.19823, 0.16801, oneMinusAlphaSq);
+    float d = mix(-1.97760, -1.27393, oneMinusAlphaSq);
+    float e = mix(-4.32054, -4.85967, oneMinusAlphaSq);
+    return a / (1.0 + b * pow(x, c)) + d * x + e;
+}
+
+float lambdaSheen(float cosTheta, float alphaG)
+{
+    if (abs(cosTheta) < 0.5) {
+        return exp(lambdaSheenNumericHelper(cosTheta, alphaG));
+    }
+    return exp(2.0 * lambdaSheenNumericHelper(0.5, alphaG) - lambdaSheenNumericHelper(1.0 - cosTheta, alphaG));
+}
+
+float sheenVisibility(float NdotL, float NdotV, float sheenRoughness)
+{
+    float alphaG = sheenRoughness * sheenRoughness;
+    return clamp(1.0 / ((1.0 + lambdaSheen(NdotV, alphaG) + lambdaSheen(NdotL, alphaG)) * (4.0 * NdotV * NdotL)), 0.0, 1.0);
+}
+#endif
+
 void main()
 {
+#ifdef HAS_SPECULARGLOSSINESS
+    // Specular-glossiness workflow (KHR_materials_pbrSpecularGlossiness): the specular color
+    // is the reflectance at normal incidence, glossiness is the inverse of the roughness
+    vec4 specularGlossiness = u_SpecularGlossinessValues;
+#ifdef HAS_SPECULARGLOSSINESSMAP
+    specularGlossiness *= texture(u_SpecularGlossinessSampler, getUV(u_SpecularGlossinessTexCoord, u_SpecularGlossinessUVTransform));
+#endif
+    float perceptualRoughness = 1.0 - specularGlossiness.a;
+    float metallic = 0.0;
+#else
     // Metallic and Roughness material properties are packed together
     // In glTF, these factors can be specified by fixed scalar values
     // or from a metallic-roughness map
@@ -227,9 +437,10 @@ void main()
 #ifdef HAS_METALROUGHNESSMAP
     // Roughness is stored in the 'g' channel, metallic is stored in the 'b' channel.
     // This layout intentionally reserves the 'r' channel for (optional) occlusion map data
-    vec4 mrSample = texture(u_MetallicRoughnessSampler, v_UV[u_MetallicRoughnessTexCoord]);
+    vec4 mrSample = texture(u_MetallicRoughnessSampler, getUV(u_MetallicRoughnessTexCoord, u_MetallicRoughnessUVTransform));
     perceptualRoughness = mrSample.g * perceptualRoughness;
     metallic = mrSample.b * metallic;
+#endif
 #endif
     perceptualRoughness = clamp(perceptualRoughness, c_MinRoughness, 1.0);
     metallic = clamp(metallic, 0.0, 1.0);
@@ -239,28 +450,50 @@ void main()
 
     // The albedo may be defined from a base texture or a flat color
 #ifdef HAS_BASECOLORMAP
-    vec4 baseColor = texture(u_BaseColorSampler, v_UV[u_BaseColorTexCoord]) * u_BaseColorFactor;
+    vec4 baseColor = texture(u_BaseColorSampler, getUV(u_BaseColorTexCoord, u_BaseColorUVTransform)) * u_BaseColorFactor;
 #else
     vec4 baseColor = u_BaseColorFactor;
 #endif
     // spec: COLOR_0 ... acts as an additional linear multiplier to baseColor
     baseColor *= v_Color;
 
-    vec3 f0 = vec3(0.04);
+#ifdef UNLIT
+    vec3 color = baseColor.rgb;
+#else
+
+#ifdef HAS_SPECULARGLOSSINESS
+    vec3 specularColor = specularGlossiness.rgb;
+    vec3 diffuseColor = baseColor.rgb * (1.0 - max(max(specularColor.r, specularColor.g), specularColor.b));
+    float specularWeight = 1.0;
+#else
+    // reflectance of dielectrics at normal incidence (0.04 for the default IOR of 1.5),
+    // scaled by KHR_materials_specular
+    float specularWeight = u_SpecularFactor;
+#ifdef HAS_SPECULARMAP
+    specularWeight *= texture(u_SpecularSampler, getUV(u_SpecularTexCoord, u_SpecularUVTransform)).a;
+#endif
+    vec3 specularColorFactor = u_SpecularColorFactor;
+#ifdef HAS_SPECULARCOLORMAP
+    specularColorFactor *= texture(u_SpecularColorSampler, getUV(u_SpecularColorTexCoord, u_SpecularColorUVTransform)).rgb;
+#endif
+    float iorReflectance = pow((u_Ior - 1.0) / (u_Ior + 1.0), 2.0);
+    vec3 f0 = min(vec3(iorReflectance) * specularColorFactor, vec3(1.0)) * specularWeight;
     vec3 diffuseColor = baseColor.rgb * (vec3(1.0) - f0);
     diffuseColor *= 1.0 - metallic;
     vec3 specularColor = mix(f0, baseColor.rgb, metallic);
+#endif
 
     // Compute reflectance.
     float reflectance = max(max(specularColor.r, specularColor.g), specularColor.b);
 
     // For typical incident reflectance range (between 4% to 100%) set the grazing reflectance to 100% for typical fresnel effect.
     // For very low reflectance range on highly diffuse objects (below 4%), incrementally reduce grazing reflecance to 0%.
-    float reflectance90 = clamp(reflectance * 25.0, 0.0, 1.0);
+    float reflectance90 = mix(specularWeight, clamp(reflectance * 25.0, 0.0, 1.0), metallic);
     vec3 specularEnvironmentR0 = specularColor.rgb;
     vec3 specularEnvironmentR90 = vec3(1.0, 1.0, 1.0) * reflectance90;
 
-    vec3 n = getNormal();                             // normal at surface point
+    mat3 tbn = getTBN();
+    vec3 n = getNormal(tbn);                          // normal at surface point
     vec3 v = normalize(u_Camera - v_Position);        // Vector from surface point to camera
     vec3 l = normalize(u_LightDirection);             // Vector from surface point to light
     vec3 h = normalize(l+v);                          // Half vector between both l and v
@@ -295,26 +528,94 @@ void main()
     // Calculation of analytical lighting contribution
     vec3 diffuseContrib = (1.0 - F) * diffuse(pbrInputs);
     vec3 specContrib = F * G * D / (4.0 * NdotL * NdotV);
-    vec3 color = NdotL * u_LightColor * (diffuseContrib + specContrib);
+    vec3 diffuseLight = NdotL * u_LightColor * diffuseContrib;
+    vec3 color = NdotL * u_LightColor * specContrib;
 
     // Calculate lighting contribution from image based lighting source (IBL)
 #ifdef USE_IBL
     color += getIBLContribution(pbrInputs, n, reflection);
 #else
     // Add simple ambient light
-    color += u_AmbientLightColor * u_AmbientLightIntensity * baseColor.xyz;
+    diffuseLight += u_AmbientLightColor * u_AmbientLightIntensity * baseColor.xyz;
+#endif
+
+#ifdef HAS_TRANSMISSION
+    // the transmitted light replaces the diffuse light of dielectrics
+    float transmission = u_TransmissionFactor;
+#ifdef HAS_TRANSMISSIONMAP
+    transmission *= texture(u_TransmissionSampler, getUV(u_TransmissionTexCoord, u_TransmissionUVTransform)).r;
+#endif
+    vec3 transmittedLight = (1.0 - F) * getTransmission(n, v, baseColor.rgb, perceptualRoughness);
+    diffuseLight = mix(diffuseLight, transmittedLight, transmission * (1.0 - metallic));
+#endif
+    color += diffuseLight;
+
+#ifdef HAS_SHEEN
+    vec3 sheenColor = u_SheenColorFactor;
+#ifdef HAS_SHEENCOLORMAP
+    sheenColor *= texture(u_SheenColorSampler, getUV(u_SheenColorTexCoord, u_SheenColorUVTransform)).rgb;
+#endif
+    float sheenRoughness = u_SheenRoughnessFactor;
+#ifdef HAS_SHEENROUGHNESSMAP
+    sheenRoughness *= texture(u_SheenRoughnessSampler, getUV(u_SheenRoughnessTexCoord, u_SheenRoughnessUVTransform)).a;
+#endif
+    sheenRoughness = max(sheenRoughness, 0.000001);
+    vec3 sheen = NdotL * u_LightColor * sheenColor
+        * sheenDistribution(sheenRoughness, NdotH) * sheenVisibility(NdotL, NdotV, sheenRoughness);
+    // the base layer is darkened by the light reflected by the sheen (albedo scaling)
+    float sheenAlbedo = texture(u_SheenELUT, vec2(NdotV, sheenRoughness)).r;
+    float sheenAlbedoScaling = 1.0 - max(max(sheenColor.r, sheenColor.g), sheenColor.b) * sheenAlbedo;
+    color = sheen + color * sheenAlbedoScaling;
 #endif
 
     // Apply optional PBR terms for additional (optional) shading
 #ifdef HAS_OCCLUSIONMAP
-    float ao = texture(u_OcclusionSampler, v_UV[u_OcclusionTexCoord]).r;
+    float ao = texture(u_OcclusionSampler, getUV(u_OcclusionTexCoord, u_OcclusionUVTransform)).r;
     color = mix(color, color * ao, u_OcclusionStrength);
 #endif
 
+    vec3 emissive = u_EmissiveFactor;
 #ifdef HAS_EMISSIVEMAP
-    vec3 emissive = texture(u_EmissiveSampler, v_UV[u_EmissiveTexCoord]).rgb * u_EmissiveFactor;
+    emissive *= texture(u_EmissiveSampler, getUV(u_EmissiveTexCoord, u_EmissiveUVTransform)).rgb;
+#endif
     color += emissive;
+
+#ifdef HAS_CLEARCOAT
+    // KHR_materials_clearcoat: a second specular lobe (dielectric, IOR 1.5) layered on top
+    float clearcoat = u_ClearcoatFactor;
+#ifdef HAS_CLEARCOATMAP
+    clearcoat *= texture(u_ClearcoatSampler, getUV(u_ClearcoatTexCoord, u_ClearcoatUVTransform)).r;
+#endif
+    float clearcoatRoughness = u_ClearcoatRoughnessFactor;
+#ifdef HAS_CLEARCOATROUGHNESSMAP
+    clearcoatRoughness *= texture(u_ClearcoatRoughnessSampler, getUV(u_ClearcoatRoughnessTexCoord, u_ClearcoatRoughnessUVTransform)).g;
 #endif
+    clearcoatRoughness = clamp(clearcoatRoughness, c_MinRoughness, 1.0);
+
+    vec3 nc = getClearcoatNormal(tbn);
+    float NcdotL = clamp(dot(nc, l), 0.001, 1.0);
+    float NcdotV = clamp(abs(dot(nc, v)), 0.001, 1.0);
+    PBRInfo clearcoatInputs = PBRInfo(
+        NcdotL,
+        NcdotV,
+        clamp(dot(nc, h), 0.0, 1.0),
+        LdotH,
+        VdotH,
+        clearcoatRoughness,
+        0.0,
+        vec3(0.04),
+        vec3(1.0),
+        clearcoatRoughness * clearcoatRoughness,
+        vec3(0.0),
+        vec3(0.04)
+    );
+    vec3 clearcoatSpec = specularReflection(clearcoatInputs) * geometricOcclusion(clearcoatInputs)
+        * microfacetDistribution(clearcoatInputs) / (4.0 * NcdotL * NcdotV);
+    // the base layer is attenuated by the light reflected at the coat
+    float clearcoatFresnel = 0.04 + 0.96 * pow(1.0 - NcdotV, 5.0);
+    color = color * (1.0 - clearcoat * clearcoatFresnel) + clearcoat * NcdotL * u_LightColor * clearcoatSpec;
+#endif
+#endif // UNLIT
 
     // // This section uses mix to override final color for reference app visualization
     // // of various parameters in the lighting equation.
@@ -341,4 +642,9 @@ void main()
     // TODO!: apply fix from reference shader:
     // https://github.com/KhronosGroup/glTF-WebGL-PBR/pull/55/files#diff-f7232333b020880432a925d5a59e075d
     FragColor = vec4(color, alpha);
+#ifdef UNLIT
+    UnlitMask = vec4(1.0, 0.0, 0.0, alpha);
+#else
+    UnlitMask = vec4(0.0, 0.0, 0.0, alpha);
+#endif
 }
@@ -0,0 +1,74 @@
+#version 330 core
+// Expands LINES (and strips/loops) to screen-space quads, since
+// line widths > 1 are not supported by core profile OpenGL.
+layout (lines) in;
+layout (triangle_strip, max_vertices = 4) out;
+
+uniform vec2 u_ViewportSize;
+uniform float u_LineWidth;
+
+in Varyings {
+  vec3 v_Position;
+  vec2 v_UV[UV_SETS];
+  vec4 v_Color;
+
+#ifdef HAS_NORMALS
+#ifdef HAS_TANGENTS
+  mat3 v_TBN;
+#else
+  vec3 v_Normal;
+#endif
+#endif
+} gs_in[];
+
+out Varyings {
+  vec3 v_Position;
+  vec2 v_UV[UV_SETS];
+  vec4 v_Color;
+
+#ifdef HAS_NORMALS
+#ifdef HAS_TANGENTS
+  mat3 v_TBN;
+#else
+  vec3 v_Normal;
+#endif
+#endif
+} gs_out;
+
+void emit(int i, vec2 offset)
+{
+  gs_out.v_Position = gs_in[i].v_Position;
+  gs_out.v_UV = gs_in[i].v_UV;
+  gs_out.v_Color = gs_in[i].v_Color;
+  #ifdef HAS_NORMALS
+  #ifdef HAS_TANGENTS
+  gs_out.v_TBN = gs_in[i].v_TBN;
+  #else
+  gs_out.v_Normal = gs_in[i].v_Normal;
+  #endif
+  #endif
+
+  vec4 position = gl_in[i].gl_Position;
+  // offset is in pixels -> NDC, scaled by w to undo the perspective division
+  gl_Position = position + vec4(offset / u_ViewportSize * 2.0 * position.w, 0.0, 0.0);
+  EmitVertex();
+}
+
+void main()
+{
+  vec4 p0 = gl_in[0].gl_Position;
+  vec4 p1 = gl_in[1].gl_Position;
+  vec2 s0 = p0.xy / p0.w * u_ViewportSize * 0.5;
+  vec2 s1 = p1.xy / p1.w * u_ViewportSize * 0.5;
+
+  vec2 dir = s1 - s0;
+  dir = length(dir) > 0.0 ? normalize(dir) : vec2(1.0, 0.0);
+  vec2 offset = vec2(-dir.y, dir.x) * u_LineWidth * 0.5;
+
+  // counter-clockwise, so the quad is always front facing
+  emit(0, offset);
+  emit(0, -offset);
+  emit(1, offset);
+  emit(1, -offset);
+  EndPrimitive();
+}
@@ -0,0 +1,87 @@
+#version 330 core
+// Exposure, tone mapping (one of TONEMAP_ACES, TONEMAP_REINHARD, TONEMAP_FILMIC or none)
+// and sRGB encoding of the linear HDR scene color.
+// Unlit materials (u_UnlitMask) are only sRGB encoded.
+in vec2 v_UV;
+
+uniform sampler2D u_HdrColor;
+uniform sampler2D u_UnlitMask;
+uniform float u_Exposure;
+
+out vec4 FragColor;
+
+// ACES fit by Stephen Hill (@self_shadow)
+const mat3 ACESInputMat = mat3(
+    0.59719, 0.07600, 0.02840,
+    0.35458, 0.90834, 0.13383,
+    0.04823, 0.01566, 0.83777
+);
+const mat3 ACESOutputMat = mat3(
+     1.60475, -0.10208, -0.00327,
+    -0.53108,  1.10813, -0.07276,
+    -0.07367, -0.00605,  1.07602
+);
+
+vec3 RRTAndODTFit(vec3 v)
+{
+    vec3 a = v * (v + 0.0245786) - 0.000090537;
+    vec3 b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return a / b;
+}
+
+vec3 toneMapACES(vec3 color)
+{
+    color = ACESInputMat * color;
+    color = RRTAndODTFit(color);
+    color = ACESOutputMat * color;
+    return clamp(color, 0.0, 1.0);
+}
+
+// John Hable's Uncharted 2 operator
+vec3 hableCurve(vec3 x)
+{
+    const float A = 0.15; // shoulder strength
+    const float B = 0.50; // linear strength
+    const float C = 0.10; // linear angle
+    const float D = 0.20; // toe strength
+    const float E = 0.02; // toe numerator
+    const float F = 0.30; // toe denominator
+    return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
+}
+
+vec3 toneMapFilmic(vec3 color)
+{
+    const float W = 11.2; // linear white point
+    const float exposureBias = 2.0;
+    return hableCurve(color * exposureBias) / hableCurve(vec3(W));
+}
+
+vec3 toneMapReinhard(vec3 color)
+{
+    return color / (1.0 + color);
+}
+
+vec3 linearToSRGB(vec3 color)
+{
+    color = clamp(color, 0.0, 1.0);
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, vec3(lessThanEqual(color, vec3(0.0031308))));
+}
+
+void main()
+{
+    vec4 hdr = texture(u_HdrColor, v_UV);
+    vec3 color = hdr.rgb * u_Exposure;
+
+#if defined(TONEMAP_ACES)
+    color = toneMapACES(color);
+#elif defined(TONEMAP_REINHARD)
+    color = toneMapReinhard(color);
+#elif defined(TONEMAP_FILMIC)
+    color = toneMapFilmic(color);
+#endif
+    color = mix(color, hdr.rgb, texture(u_UnlitMask, v_UV).r);
+
+    FragColor = vec4(linearToSRGB(color), hdr.a);
+}
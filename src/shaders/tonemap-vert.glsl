@@ -0,0 +1,10 @@
+#version 330 core
+// Fullscreen triangle without vertex buffer (draw with 3 vertices)
+out vec2 v_UV;
+
+void main()
+{
+    vec2 position = vec2(float((gl_VertexID & 1) << 2), float((gl_VertexID & 2) << 1)) - 1.0;
+    v_UV = position * 0.5 + 0.5;
+    gl_Position = vec4(position, 0.0, 1.0);
+}
@@ -43,6 +43,7 @@ pub struct FrameTimer {
     averaging_window: usize,
     current_frame_start: Instant,
     pub frame_times: Vec<Duration>,
+    pub last_frame_time: Duration,
 }
 
 /// Timing helper that averages timings over `averaging_window`
@@ -54,6 +55,7 @@ impl FrameTimer {
             averaging_window,
             current_frame_start: Instant::now(),
             frame_times: Vec::with_capacity(averaging_window),
+            last_frame_time: Duration::default(),
         }
     }
 
@@ -62,7 +64,8 @@ impl FrameTimer {
     }
 
     pub fn end(&mut self) {
-        self.frame_times.push(self.current_frame_start.elapsed());
+        self.last_frame_time = self.current_frame_start.elapsed();
+        self.frame_times.push(self.last_frame_time);
         if self.frame_times.len() == self.averaging_window {
             self.print_and_reset();
         }
@@ -80,6 +83,63 @@ impl FrameTimer {
     }
 }
 
+/// Measures GPU time with `GL_TIME_ELAPSED` queries.
+/// Alternates between two queries, so reading the result of the previous frame doesn't stall.
+pub struct GpuTimer {
+    queries: [u32; 2],
+    current: usize,
+    started: [bool; 2],
+    pub last_frame_time: Option<Duration>,
+}
+
+impl GpuTimer {
+    pub fn new() -> GpuTimer {
+        let mut queries = [0; 2];
+        unsafe { gl::GenQueries(2, queries.as_mut_ptr()); }
+        GpuTimer {
+            queries,
+            current: 0,
+            started: [false; 2],
+            last_frame_time: None,
+        }
+    }
+
+    pub unsafe fn start(&mut self) {
+        gl::BeginQuery(gl::TIME_ELAPSED, self.queries[self.current]);
+        self.started[self.current] = true;
+    }
+
+    /// Ends the current query and fetches the result of the previous one (if available)
+    pub unsafe fn end(&mut self) {
+        gl::EndQuery(gl::TIME_ELAPSED);
+        self.current = 1 - self.current;
+        if !self.started[self.current] { return }
+
+        let query = self.queries[self.current];
+        let mut available = 0;
+        gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+        if available != 0 {
+            let mut nanos = 0;
+            gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut nanos);
+            self.last_frame_time = Some(Duration::from_nanos(nanos));
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteQueries(2, self.queries.as_ptr()); }
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
 pub unsafe fn gl_check_error(file: &str, line: u32) -> u32 {
     let mut error_code = gl::GetError();
     while error_code != gl::NO_ERROR {
@@ -137,12 +197,16 @@ pub unsafe fn print_context_info() {
     };
     debug!("Context Flags: Debug: {}, Forward Compatible: {}", debug, forward_compatible);
 
+    debug!("Extensions   : {}", gl_extensions().join(", "))
+}
+
+/// Names of the extensions supported by the current OpenGL context
+pub unsafe fn gl_extensions() -> Vec<String> {
     let mut num_extensions = 0;
     gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut num_extensions);
-    let extensions: Vec<_> = (0 .. num_extensions).map(|num| {
+    (0 .. num_extensions).map(|num| {
         gl_string(gl::GetStringi(gl::EXTENSIONS, num as gl::types::GLuint))
-    }).collect();
-    debug!("Extensions   : {}", extensions.join(", "))
+    }).collect()
 }
 
 pub unsafe fn gl_string(raw_string: *const GLubyte) -> String {
@@ -2,7 +2,8 @@ use std::f32::consts::PI;
 use std::os::raw::c_void;
 use std::path::Path;
 use std::process;
-use std::time::Instant;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use cgmath::{ Deg, Point3 };
 use collision::Aabb;
@@ -27,18 +28,53 @@ use log::{error, warn, info};
 
 use crate::controls::{OrbitControls, NavState};
 use crate::controls::CameraMovement::*;
+#[cfg(target_os = "linux")]
+use crate::egl_context::EglContext;
 use crate::framebuffer::Framebuffer;
-use crate::importdata::ImportData;
+use crate::hud::Hud;
+use crate::postprocess::{PostProcess, ToneMapping};
+use crate::importdata::{self, ImportData, ImportError};
 use crate::render::*;
 use crate::render::math::*;
-use crate::utils::{print_elapsed, FrameTimer, gl_check_error, print_context_info};
-
-// TODO!: complete and pass through draw calls? or get rid of multiple shaders?
-// How about state ordering anyway?
-// struct DrawState {
-//     current_shader: ShaderFlags,
-//     back_face_culling_enabled: bool
-// }
+use crate::utils::{print_elapsed, format_bytes, FrameTimer, GpuTimer, gl_check_error, print_context_info};
+
+/// Extensions that may appear in `extensionsRequired`
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "KHR_mesh_quantization",
+    "KHR_draco_mesh_compression",
+    "EXT_meshopt_compression",
+    "KHR_texture_basisu",
+    "EXT_texture_webp",
+    "KHR_texture_transform",
+    "KHR_materials_unlit",
+    "KHR_materials_emissive_strength",
+    "KHR_materials_clearcoat",
+    "KHR_materials_transmission",
+    "KHR_materials_volume",
+    "KHR_materials_ior",
+    "KHR_materials_specular",
+    "KHR_materials_sheen",
+    "KHR_materials_variants",
+    "KHR_materials_pbrSpecularGlossiness",
+];
+
+/// Fails if the file requires extensions the viewer can't handle.
+/// Runs before the actual import, since e.g. accessors of primitives compressed with an
+/// unsupported extension may have no `bufferView`, which fails validation with a less helpful error.
+pub fn check_required_extensions(source: &str) -> Result<(), String> {
+    let json = match importdata::read_json(Path::new(source)) {
+        Ok(json) => json,
+        Err(_) => return Ok(()), // reported by the import
+    };
+    let unsupported: Vec<_> = json["extensionsRequired"].as_array().into_iter().flatten()
+        .filter_map(|ext| ext.as_str())
+        .filter(|ext| !SUPPORTED_EXTENSIONS.contains(ext))
+        .collect();
+    if unsupported.is_empty() {
+        return Ok(())
+    }
+    Err(format!("Unsupported required extension(s): {}", unsupported.join(", ")))
+}
 
 #[derive(Copy, Clone)]
 pub struct CameraOptions {
@@ -49,6 +85,51 @@ pub struct CameraOptions {
     pub straight: bool,
 }
 
+#[derive(Copy, Clone)]
+pub struct RenderOptions {
+    pub tone_mapping: ToneMapping,
+    /// Linear multiplier applied before tone mapping
+    pub exposure: f32,
+    /// Size of POINTS (in logical pixels)
+    pub point_size: f32,
+    /// Width of LINES (in logical pixels)
+    pub line_width: f32,
+    /// Maximum degree of anisotropic texture filtering (1: off)
+    pub anisotropy: f32,
+}
+
+/// How the OpenGL context is created
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Backend {
+    /// Regular window (hidden for screenshots)
+    Window,
+    /// Surfaceless EGL context - no display server needed (Linux only)
+    Egl,
+    /// glutin's headless renderer (OSMesa on Linux)
+    OsMesa,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "window" => Ok(Backend::Window),
+            "egl" => Ok(Backend::Egl),
+            "osmesa" => Ok(Backend::OsMesa),
+            _ => Err(format!("unknown backend: {}", s)),
+        }
+    }
+}
+
+/// Keeps the context of a headless backend alive (dropping it destroys the context)
+#[allow(dead_code)]
+enum HeadlessContext {
+    OsMesa(glutin::HeadlessContext),
+    #[cfg(target_os = "linux")]
+    Egl(Box<EglContext>),
+}
+
 pub struct GltfViewer {
     size: PhysicalSize,
     dpi_factor: f64,
@@ -56,45 +137,61 @@ pub struct GltfViewer {
     orbit_controls: OrbitControls,
     events_loop: Option<glutin::EventsLoop>,
     gl_window: Option<glutin::GlWindow>,
+    _headless_context: Option<HeadlessContext>,
+    /// Render target of headless backends (there's no default framebuffer)
+    output_framebuffer: Option<Framebuffer>,
+    post_process: PostProcess,
+    render_options: RenderOptions,
 
     // TODO!: get rid of scene?
     root: Root,
     scene: Scene,
+    /// Selected `KHR_materials_variants` variant (index into `root.variants`)
+    variant: Option<usize>,
 
     delta_time: f64, // seconds
     last_frame: Instant,
 
     render_timer: FrameTimer,
+
+    /// Statistics overlay (only for interactive windows)
+    hud: Option<Hud>,
+    show_hud: bool,
+    gpu_timer: Option<GpuTimer>,
+    /// Exponential moving average of `delta_time`
+    avg_frame_time: f64,
+    frame_stats: FrameStats,
 }
 
-/// Note about `headless` and `visible`: True headless rendering doesn't work on
+/// Note about `backend` and `visible`: True headless rendering doesn't work on
 /// all operating systems, but an invisible window usually works
 impl GltfViewer {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         source: &str,
         width: u32,
         height: u32,
-        headless: bool,
+        backend: Backend,
         visible: bool,
         camera_options: CameraOptions,
+        render_options: RenderOptions,
         scene_index: usize,
     ) -> GltfViewer {
         let gl_request = GlRequest::Specific(Api::OpenGl, (3, 3));
         let gl_profile = GlProfile::Core;
-        let (events_loop, gl_window, dpi_factor, inner_size) =
+        let headless = backend != Backend::Window;
+        let (events_loop, gl_window, headless_context, output_framebuffer, dpi_factor, inner_size) =
             if headless {
-                let headless_context = glutin::HeadlessRendererBuilder::new(width, height)
-                    // .with_gl(gl_request)
-                    // .with_gl_profile(gl_profile)
-                    .build()
-                    .unwrap();
-                unsafe { headless_context.make_current().unwrap() }
-                gl::load_with(|symbol| headless_context.get_proc_address(symbol) as *const _);
+                let headless_context = Self::create_headless_context(backend, width, height)
+                    .unwrap_or_else(|err| {
+                        error!("Failed to create {:?} context: {}", backend, err);
+                        process::exit(4)
+                    });
                 let framebuffer = Framebuffer::new(width, height);
                 framebuffer.bind();
                 unsafe { gl::Viewport(0, 0, width as i32, height as i32); }
 
-                (None, None, 1.0, PhysicalSize::new(width as f64, height as f64)) // TODO: real height (retina? (should be the same as PhysicalSize when headless?))
+                (None, None, Some(headless_context), Some(framebuffer), 1.0, PhysicalSize::new(width as f64, height as f64)) // TODO: real height (retina? (should be the same as PhysicalSize when headless?))
             }
             else {
                 // glutin: initialize and configure
@@ -122,7 +219,7 @@ impl GltfViewer {
                 // gl: load all OpenGL function pointers
                 gl::load_with(|symbol| gl_window.get_proc_address(symbol) as *const _);
 
-                (Some(events_loop), Some(gl_window), dpi_factor, inner_size)
+                (Some(events_loop), Some(gl_window), None, None, dpi_factor, inner_size)
             };
         
         let mut orbit_controls = OrbitControls::new(
@@ -143,7 +240,8 @@ impl GltfViewer {
                 gl::ClearColor(0.0, 0.0, 0.0, 0.0);
             }
             else {
-                gl::ClearColor(0.1, 0.2, 0.3, 1.0);
+                // NOTE: linear color (the scene is rendered into a linear HDR framebuffer)
+                gl::ClearColor(0.01, 0.033, 0.073, 1.0);
             }
 
             gl::Enable(gl::DEPTH_TEST);
@@ -153,7 +251,14 @@ impl GltfViewer {
             // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
         };
 
+        let post_process = PostProcess::new(inner_size.width as u32, inner_size.height as u32,
+            render_options.tone_mapping, render_options.exposure);
+
         let (root, scene) = Self::load(source, scene_index);
+        let anisotropy = supported_anisotropy(render_options.anisotropy);
+        if anisotropy > 1.0 {
+            root.set_anisotropy(anisotropy);
+        }
         let mut viewer = GltfViewer {
             size: inner_size,
             dpi_factor,
@@ -162,14 +267,25 @@ impl GltfViewer {
 
             events_loop,
             gl_window,
+            _headless_context: headless_context,
+            output_framebuffer,
+            post_process,
+            render_options,
 
             root,
             scene,
+            variant: None,
 
             delta_time: 0.0, // seconds
             last_frame: Instant::now(),
 
             render_timer: FrameTimer::new("rendering", 300),
+
+            hud: if visible && !headless { Some(Hud::new((2.0 * dpi_factor).round() as f32)) } else { None },
+            show_hud: false,
+            gpu_timer: None,
+            avg_frame_time: 0.0,
+            frame_stats: FrameStats::default(),
         };
         unsafe { gl_check_error!(); };
 
@@ -203,6 +319,32 @@ impl GltfViewer {
         viewer
     }
 
+    /// Creates a context for rendering into a framebuffer object, makes it current
+    /// and loads the OpenGL function pointers.
+    fn create_headless_context(backend: Backend, width: u32, height: u32) -> Result<HeadlessContext, String> {
+        match backend {
+            Backend::OsMesa => {
+                let headless_context = glutin::HeadlessRendererBuilder::new(width, height)
+                    // .with_gl(gl_request)
+                    // .with_gl_profile(gl_profile)
+                    .build()
+                    .map_err(|err| err.to_string())?;
+                unsafe { headless_context.make_current().map_err(|err| format!("{:?}", err))? }
+                gl::load_with(|symbol| headless_context.get_proc_address(symbol) as *const _);
+                Ok(HeadlessContext::OsMesa(headless_context))
+            }
+            #[cfg(target_os = "linux")]
+            Backend::Egl => {
+                let egl_context = EglContext::new()?;
+                gl::load_with(|symbol| egl_context.get_proc_address(symbol));
+                Ok(HeadlessContext::Egl(Box::new(egl_context)))
+            }
+            #[cfg(not(target_os = "linux"))]
+            Backend::Egl => Err("the EGL backend is only available on Linux".into()),
+            Backend::Window => unreachable!(),
+        }
+    }
+
     pub fn load(source: &str, scene_index: usize) -> (Root, Scene) {
         let mut start_time = Instant::now();
         // TODO!: http source
@@ -216,17 +358,20 @@ impl GltfViewer {
             // gltf
         }
         //     else {
-        let (doc, buffers, images) = match gltf::import(source) {
-            Ok(tuple) => tuple,
+        if let Err(err) = check_required_extensions(source) {
+            error!("{}", err);
+            process::exit(1)
+        }
+        let imp = match ImportData::import(Path::new(source)) {
+            Ok(imp) => imp,
             Err(err) => {
-                error!("glTF import failed: {:?}", err);
-                if let gltf::Error::Io(_) = err {
+                error!("glTF import failed: {}", err);
+                if let ImportError::Gltf(gltf::Error::Io(_)) = err {
                     error!("Hint: Are the .bin file(s) referenced by the .gltf file available?")
                 }
                 process::exit(1)
             },
         };
-        let imp = ImportData { doc, buffers, images };
 
         print_elapsed("Imported glTF in ", start_time);
         start_time = Instant::now();
@@ -237,10 +382,19 @@ impl GltfViewer {
             process::exit(3)
         }
         let base_path = Path::new(source);
-        let mut root = Root::from_gltf(&imp, base_path);
+        let mut root = match Root::from_gltf(&imp, base_path) {
+            Ok(root) => root,
+            Err(err) => {
+                error!("glTF loading failed: {}", err);
+                process::exit(1)
+            }
+        };
         let scene = Scene::from_gltf(&imp.doc.scenes().nth(scene_index).unwrap(), &mut root);
         print_elapsed(&format!("Loaded scene with {} nodes, {} meshes in ",
                 imp.doc.nodes().count(), imp.doc.meshes().len()), start_time);
+        if !root.variants.is_empty() {
+            info!("Material variants (cycle with V): {}", root.variants.join(", "));
+        }
 
         (root, scene)
     }
@@ -273,6 +427,30 @@ impl GltfViewer {
         self.orbit_controls.camera.update_projection_matrix();
     }
 
+    /// Selects the `KHR_materials_variants` variant with the given name
+    pub fn select_variant(&mut self, name: &str) -> Result<(), String> {
+        let index = self.root.variants.iter().position(|variant| variant == name)
+            .ok_or_else(|| format!("No material variant '{}' found (available: {})",
+                name, self.root.variants.join(", ")))?;
+        self.variant = Some(index);
+        self.root.select_variant(self.variant);
+        Ok(())
+    }
+
+    /// Switches to the next variant, or back to the default materials after the last one
+    fn cycle_variant(&mut self) {
+        if self.root.variants.is_empty() {
+            return
+        }
+        self.variant = match self.variant {
+            None => Some(0),
+            Some(index) if index + 1 < self.root.variants.len() => Some(index + 1),
+            Some(_) => None,
+        };
+        self.root.select_variant(self.variant);
+        info!("Material variant: {}", self.variant.map_or("(default)", |index| &self.root.variants[index]));
+    }
+
     pub fn start_render_loop(&mut self) {
         loop {
             // per-frame time logic
@@ -281,20 +459,43 @@ impl GltfViewer {
             self.last_frame = Instant::now();
 
             // events
+            let size_before_events = self.size;
+            let mut next_variant = false;
             let keep_running = process_events(
                 &mut self.events_loop.as_mut().unwrap(),
                 self.gl_window.as_mut().unwrap(),
                 &mut self.orbit_controls,
                 &mut self.dpi_factor,
-                &mut self.size);
+                &mut self.size,
+                &mut self.show_hud,
+                &mut next_variant);
             if !keep_running {
                 unsafe { gl_check_error!(); } // final error check so errors don't go unnoticed
                 break
             }
+            if self.size != size_before_events {
+                self.post_process.resize(self.size.width as u32, self.size.height as u32);
+            }
+            if next_variant {
+                self.cycle_variant();
+            }
 
             self.orbit_controls.frame_update(self.delta_time); // keyboard navigation
 
+            self.avg_frame_time = if self.avg_frame_time == 0.0 {
+                self.delta_time
+            } else {
+                0.95 * self.avg_frame_time + 0.05 * self.delta_time
+            };
+
+            if self.show_hud && self.gpu_timer.is_none() {
+                self.gpu_timer = Some(GpuTimer::new());
+            }
+
             self.draw();
+            if self.show_hud {
+                self.draw_hud();
+            }
 
             self.gl_window.as_ref().unwrap().swap_buffers().unwrap();
         }
@@ -305,16 +506,65 @@ impl GltfViewer {
         // render
         unsafe {
             self.render_timer.start();
+            let mut gpu_timer = if self.show_hud { self.gpu_timer.as_mut() } else { None };
+            if let Some(timer) = gpu_timer.as_mut() { timer.start() }
 
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            self.post_process.clear();
 
             let cam_params = self.orbit_controls.camera_params();
-            self.scene.draw(&mut self.root, &cam_params);
+            let mut state = DrawState {
+                point_size: self.render_options.point_size * self.dpi_factor as f32,
+                line_width: self.render_options.line_width * self.dpi_factor as f32,
+                viewport_size: (self.size.width as f32, self.size.height as f32),
+                ..DrawState::default()
+            };
+            if self.root.has_transmission() {
+                state.pass = DrawPass::Opaque;
+                self.scene.draw(&mut self.root, &cam_params, &mut state);
+                state.transmission_texture = self.post_process.copy_scene_color();
+                state.pass = DrawPass::Transmission;
+            }
+            self.scene.draw(&mut self.root, &cam_params, &mut state);
+            self.frame_stats = state.stats;
+            Sampler::unbind_all();
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.output_framebuffer.as_ref().map_or(0, |fb| fb.id));
+            self.post_process.draw();
 
+            if let Some(timer) = gpu_timer { timer.end() }
             self.render_timer.end();
         }
     }
 
+    fn draw_hud(&self) {
+        let hud = match self.hud {
+            Some(ref hud) => hud,
+            None => return
+        };
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let gpu_time = self.gpu_timer.as_ref()
+            .and_then(|timer| timer.last_frame_time)
+            .map_or_else(|| "-".to_owned(), |time| format!("{:.2} ms", ms(time)));
+        let texture_memory = self.root.textures.iter().map(|texture| texture.memory_size).sum();
+        let buffer_memory = self.root.meshes.iter()
+            .flat_map(|mesh| mesh.primitives.iter())
+            .map(|primitive| primitive.memory_size)
+            .sum();
+        let stats = &self.frame_stats;
+        let pos = self.orbit_controls.position;
+        let target = self.orbit_controls.target;
+        let lines = [
+            format!("FPS: {:.1} ({:.2} ms)", 1.0 / self.avg_frame_time, self.avg_frame_time * 1000.0),
+            format!("CPU: {:.2} ms  GPU: {}", ms(self.render_timer.last_frame_time), gpu_time),
+            format!("Draw calls: {}  Triangles: {}", stats.draw_calls, stats.triangles),
+            format!("Shader switches: {}", stats.shader_switches),
+            format!("Texture memory: {}  Buffers: {}", format_bytes(texture_memory), format_bytes(buffer_memory)),
+            format!("Camera: ({:.2}, {:.2}, {:.2})", pos.x, pos.y, pos.z),
+            format!("Target: ({:.2}, {:.2}, {:.2})", target.x, target.y, target.z),
+        ];
+        unsafe { hud.draw(&lines, self.size.width as f32, self.size.height as f32) }
+    }
+
     pub fn screenshot(&mut self, filename: &str) {
         self.draw();
 
@@ -356,7 +606,9 @@ fn process_events(
     gl_window: &glutin::GlWindow,
     mut orbit_controls: &mut OrbitControls,
     dpi_factor: &mut f64,
-    size: &mut PhysicalSize) -> bool
+    size: &mut PhysicalSize,
+    show_hud: &mut bool,
+    next_variant: &mut bool) -> bool
 {
     let mut keep_running = true;
     #[allow(clippy::single_match)]
@@ -420,7 +672,7 @@ fn process_events(
                     orbit_controls.process_mouse_scroll(lines * 3.0);
                 }
                 WindowEvent::KeyboardInput { input, .. } => {
-                    keep_running = process_input(input, &mut orbit_controls);
+                    keep_running = process_input(input, &mut orbit_controls, show_hud, next_variant);
                 }
                 _ => ()
             },
@@ -431,7 +683,7 @@ fn process_events(
     keep_running
 }
 
-fn process_input(input: glutin::KeyboardInput, controls: &mut OrbitControls) -> bool {
+fn process_input(input: glutin::KeyboardInput, controls: &mut OrbitControls, show_hud: &mut bool, next_variant: &mut bool) -> bool {
     let pressed = match input.state {
         Pressed => true,
         Released => false
@@ -439,6 +691,8 @@ fn process_input(input: glutin::KeyboardInput, controls: &mut OrbitControls) ->
     if let Some(code) = input.virtual_keycode {
         match code {
             VirtualKeyCode::Escape if pressed => return false,
+            VirtualKeyCode::F1 if pressed => *show_hud = !*show_hud,
+            VirtualKeyCode::V if pressed => *next_variant = true,
             VirtualKeyCode::W | VirtualKeyCode::Up    => controls.process_keyboard(FORWARD, pressed),
             VirtualKeyCode::S | VirtualKeyCode::Down  => controls.process_keyboard(BACKWARD, pressed),
             VirtualKeyCode::A | VirtualKeyCode::Left  => controls.process_keyboard(LEFT, pressed),
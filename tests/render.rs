@@ -0,0 +1,98 @@
+//! Golden image tests: renders models headlessly with fixed camera options and compares
+//! the screenshots with the reference images in `tests/reference`.
+//!
+//! * Set `UPDATE_REFERENCE_IMAGES=1` to (re-)generate the reference images after intended changes.
+//! * Set `GLTF_VIEWER_TEST_BACKEND` to override the context backend (default: `egl` on Linux, `osmesa` otherwise).
+//! * Without an OpenGL context the tests fail. Set `GLTF_VIEWER_SKIP_RENDER_TESTS=1` to skip them
+//!   explicitly instead (e.g. on CI machines without GL).
+//! * The rendered images and diff images (see `gltf-viewer diff`) are written to `target/tmp/render-tests`.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+/// Maximum perceptual color difference (0..1) for two pixels to be considered equal
+const PIXEL_THRESHOLD: f64 = 0.1;
+/// Percentage of pixels that may differ, e.g. due to rasterization differences between drivers
+const MAX_DIFFERING_PIXELS: f64 = 1.0;
+
+/// Exit code of the viewer if the OpenGL context can't be created
+const EXIT_CONTEXT_CREATION_FAILED: i32 = 4;
+
+fn default_backend() -> &'static str {
+    if cfg!(target_os = "linux") { "egl" } else { "osmesa" }
+}
+
+fn render_and_compare(name: &str, gltf_path: &str, extra_args: &[&str]) {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("render-tests");
+    fs::create_dir_all(&out_dir).unwrap();
+    let actual_path = out_dir.join(format!("{}.png", name));
+    let reference_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/reference")
+        .join(format!("{}.png", name));
+
+    let backend = env::var("GLTF_VIEWER_TEST_BACKEND").unwrap_or_else(|_| default_backend().into());
+    let status = Command::new(env!("CARGO_BIN_EXE_gltf-viewer"))
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join(gltf_path))
+        .args(["--backend", &backend])
+        .args(["-w", &WIDTH.to_string(), "-h", &HEIGHT.to_string()])
+        .arg("-s").arg(&actual_path)
+        .args(extra_args)
+        .status()
+        .expect("failed to run gltf-viewer");
+    if status.code() == Some(EXIT_CONTEXT_CREATION_FAILED) {
+        if env::var_os("GLTF_VIEWER_SKIP_RENDER_TESTS").is_some() {
+            eprintln!("SKIPPING {}: no OpenGL context available with backend '{}'", name, backend);
+            return
+        }
+        panic!("no OpenGL context available with backend '{}' for {} \
+            (hint: GLTF_VIEWER_TEST_BACKEND, or GLTF_VIEWER_SKIP_RENDER_TESTS=1 to skip)", backend, name);
+    }
+    assert!(status.success(), "gltf-viewer failed for {} ({})", name, status);
+
+    if env::var_os("UPDATE_REFERENCE_IMAGES").is_some() {
+        fs::copy(&actual_path, &reference_path).unwrap();
+        eprintln!("Updated {}", reference_path.display());
+        return
+    }
+
+    assert!(reference_path.exists(), "missing reference image {} (hint: UPDATE_REFERENCE_IMAGES=1)",
+        reference_path.display());
+
+    let diff_path = out_dir.join(format!("{}-diff.png", name));
+    let output = Command::new(env!("CARGO_BIN_EXE_gltf-viewer"))
+        .arg("diff")
+        .arg(&reference_path)
+        .arg(&actual_path)
+        .arg("--out").arg(&diff_path)
+        .args(["--threshold", &MAX_DIFFERING_PIXELS.to_string()])
+        .args(["--pixel-threshold", &PIXEL_THRESHOLD.to_string()])
+        .output()
+        .expect("failed to run gltf-viewer diff");
+    assert!(output.status.success(), "{} differs from the reference:\n{}{}\n  reference: {}\n  actual:    {}\n  diff:      {}",
+        name, String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr),
+        reference_path.display(), actual_path.display(), diff_path.display());
+}
+
+#[test]
+fn box_gltf() {
+    render_and_compare("Box", "src/data/Box.gltf",
+        &["--cam-index", "-1", "--cam-pos", "1.2,0.9,1.5", "--cam-target", "0,0,0", "--cam-fovy", "60"]);
+}
+
+#[test]
+fn minimal_gltf() {
+    render_and_compare("minimal", "src/data/minimal.gltf",
+        &["--cam-index", "-1", "--cam-pos", "0.5,0.5,1.5", "--cam-target", "0.5,0.5,0", "--cam-fovy", "60"]);
+}
+
+#[test]
+fn box_multi_scene_gltf() {
+    render_and_compare("BoxMultiScene-0", "tests/BoxMultiScene.gltf",
+        &["--cam-index", "-1", "--cam-pos", "1.2,0.9,1.5", "--cam-target", "0,0,0", "--cam-fovy", "60"]);
+    render_and_compare("BoxMultiScene-1", "tests/BoxMultiScene.gltf",
+        &["--scene", "1", "--cam-index", "-1", "--cam-pos", "1.2,0.9,1.5", "--cam-target", "0,0,0", "--cam-fovy", "60"]);
+}